@@ -0,0 +1,41 @@
+//! Example native library for `RUST[native_collections::...]` calls.
+//!
+//! Build it with `cargo build --example native_collections` and copy
+//! `target/debug/examples/libnative_collections.so` into the project's `rust_libs` directory.
+
+use std::collections::BTreeMap;
+use pelin::interpreter::{Shared, Value};
+
+/// # Safety
+/// `args` must point to `len` values, as passed by the interpreter.
+#[no_mangle]
+pub unsafe fn numbers(args: *const Value, len: usize) -> *mut Value {
+    let args = std::slice::from_raw_parts(args, len);
+    let count = match args.first() {
+        Some(Value::Number(n)) => *n as usize,
+        Some(Value::Integer(n)) => *n as usize,
+        _ => return std::ptr::null_mut(),
+    };
+    let values = (1..=count).map(|n| Value::Number(n as f64)).collect();
+    Box::into_raw(Box::new(Value::Array(Shared::new(values))))
+}
+
+/// # Safety
+/// `args` must point to `len` values, as passed by the interpreter.
+#[no_mangle]
+pub unsafe fn summary(args: *const Value, len: usize) -> *mut Value {
+    let args = std::slice::from_raw_parts(args, len);
+    let Some(Value::Array(values)) = args.first() else {
+        return std::ptr::null_mut();
+    };
+    let sum = values.borrow().iter()
+        .map(|value| match value {
+            Value::Number(n) => *n,
+            _ => 0.0,
+        })
+        .sum();
+    let mut map = BTreeMap::new();
+    map.insert("count".to_string(), Value::Number(values.borrow().len() as f64));
+    map.insert("sum".to_string(), Value::Number(sum));
+    Box::into_raw(Box::new(Value::Map(Shared::new(map))))
+}
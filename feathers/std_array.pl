@@ -0,0 +1,63 @@
+fn any find(any arr, any predicate) {
+    RUST[std_func::find](arr, predicate)
+}
+
+fn num find_index(any arr, any predicate) {
+    RUST[std_func::find_index](arr, predicate)
+}
+
+fn any map(any arr, any f) {
+    RUST[std_func::array_map](arr, f)
+}
+
+fn nun push(any arr, any value) {
+    RUST[std_func::array_push](arr, value)
+}
+
+fn any get(any arr, num index) {
+    RUST[std_func::array_get](arr, index)
+}
+
+fn num len(any arr) {
+    RUST[std_func::array_len](arr)
+}
+
+fn any unique(any arr) {
+    RUST[std_func::array_unique](arr)
+}
+
+fn any dedup(any arr) {
+    RUST[std_func::array_dedup](arr)
+}
+
+fn any union(any a, any b) {
+    RUST[std_func::array_union](a, b)
+}
+
+fn any intersection(any a, any b) {
+    RUST[std_func::array_intersection](a, b)
+}
+
+fn any difference(any a, any b) {
+    RUST[std_func::array_difference](a, b)
+}
+
+fn any splice(any array, num start, num delete_count, any items) {
+    RUST[std_func::array_splice](array, start, delete_count, items)
+}
+
+fn any chunk(any array, num size) {
+    RUST[std_func::array_chunk](array, size)
+}
+
+fn any window(any array, num size) {
+    RUST[std_func::array_window](array, size)
+}
+
+fn any fill(any value, num count) {
+    RUST[std_func::array_fill](value, count)
+}
+
+fn any range_to_array(num start, num end) {
+    RUST[std_func::array_range_to_array](start, end)
+}
\ No newline at end of file
@@ -4,4 +4,12 @@ fn num to_num(str s) {
 
 fn str to_str(num n) {
     RUST[std_func::to_str](n)
+}
+
+fn int parse_int(str s, num radix) {
+    RUST[std_func::parse_int](s, radix)
+}
+
+fn num parse_float(str s) {
+    RUST[std_func::parse_float](s)
 }
\ No newline at end of file
@@ -0,0 +1,15 @@
+fn str base64_encode(str s) {
+    RUST[std_func::base64_encode](s)
+}
+
+fn str base64_decode(str s) {
+    RUST[std_func::base64_decode](s)
+}
+
+fn str hex_encode(str s) {
+    RUST[std_func::hex_encode](s)
+}
+
+fn str hex_decode(str s) {
+    RUST[std_func::hex_decode](s)
+}
\ No newline at end of file
@@ -0,0 +1,7 @@
+fn any get(str name) {
+    RUST[std_func::env_get](name)
+}
+
+fn nun set(str name, str value) {
+    RUST[std_func::env_set](name, value)
+}
\ No newline at end of file
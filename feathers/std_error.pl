@@ -0,0 +1,7 @@
+fn any error(any value) {
+    RUST[std_func::error](value)
+}
+
+fn any try(any body, any handler) {
+    RUST[std_func::try](body, handler)
+}
\ No newline at end of file
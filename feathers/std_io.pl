@@ -1,3 +1,27 @@
-fn nun print(str s) {
-    RUST[std_func::print](s)
+fn nun print(any value) {
+    RUST[std_func::print](value)
+}
+
+fn nun println(any value) {
+    RUST[std_func::println](value)
+}
+
+fn nun print_colored(str text, str color) {
+    RUST[std_func::print_colored](text, color)
+}
+
+fn nun styled(str text, map options) {
+    RUST[std_func::print_styled](text, options)
+}
+
+fn nun flush() {
+    RUST[std_func::flush]()
+}
+
+fn any read_line() {
+    RUST[std_func::read_line]()
+}
+
+fn any read_all() {
+    RUST[std_func::read_all]()
 }
\ No newline at end of file
@@ -0,0 +1,7 @@
+fn any parse(str s) {
+    RUST[std_func::json_parse](s)
+}
+
+fn str stringify(any value) {
+    RUST[std_func::json_stringify](value)
+}
\ No newline at end of file
@@ -11,5 +11,5 @@ fn bool xor(bool a, bool b) {
 }
 
 fn bool or(bool a, bool b) {
-    not(and(not(a), not(b)))
+    RUST[std_func::or](a, b)
 }
\ No newline at end of file
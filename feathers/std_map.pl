@@ -0,0 +1,27 @@
+fn any get(any map, any key) {
+    RUST[std_func::map_get](map, key)
+}
+
+fn nun set(any map, any key, any value) {
+    RUST[std_func::map_set](map, key, value)
+}
+
+fn any merge(any a, any b) {
+    RUST[std_func::map_merge](a, b)
+}
+
+fn any remove(any map, any key) {
+    RUST[std_func::map_remove](map, key)
+}
+
+fn bool has(any map, any key) {
+    RUST[std_func::map_has](map, key)
+}
+
+fn any keys(any map) {
+    RUST[std_func::map_keys](map)
+}
+
+fn any get_path(any value, str path) {
+    RUST[std_func::map_get_path](value, path)
+}
\ No newline at end of file
@@ -1,7 +1,55 @@
 fn num sin(num a) {
-    RUST[std_func::sin](a)
+    RUST[std_func::math_sin](a)
 }
 
 fn num cos(num a) {
-    RUST[std_func::cos](a)
+    RUST[std_func::math_cos](a)
+}
+
+fn num tan(num a) {
+    RUST[std_func::math_tan](a)
+}
+
+fn num pow(num base, num exp) {
+    RUST[std_func::math_pow](base, exp)
+}
+
+fn num log(num a) {
+    RUST[std_func::math_log](a)
+}
+
+fn num abs(num a) {
+    RUST[std_func::math_abs](a)
+}
+
+fn num floor(num a) {
+    RUST[std_func::math_floor](a)
+}
+
+fn num ceil(num a) {
+    RUST[std_func::math_ceil](a)
+}
+
+fn num round(num a) {
+    RUST[std_func::math_round](a)
+}
+
+fn num pi() {
+    RUST[std_func::math_pi]()
+}
+
+fn num e() {
+    RUST[std_func::math_e]()
+}
+
+fn num min(num a, num b) {
+    RUST[std_func::math_min](a, b)
+}
+
+fn num max(num a, num b) {
+    RUST[std_func::math_max](a, b)
+}
+
+fn int pow_int(int base, int exp) {
+    RUST[std_func::math_pow_int](base, exp)
 }
\ No newline at end of file
@@ -14,6 +14,54 @@ fn num divide(num a, num b) {
     RUST[std_func::divide](a, b)
 }
 
+fn num mod(num a, num b) {
+    RUST[std_func::mod](a, b)
+}
+
+fn num int_divide(num a, num b) {
+    RUST[std_func::int_divide](a, b)
+}
+
+fn num floor_divide(num a, num b) {
+    RUST[std_func::floor_divide](a, b)
+}
+
 fn num sqrt(num a) {
     RUST[std_func::sqrt](a)
+}
+
+fn str format_number(num n, str thousands, str decimal) {
+    RUST[std_func::format_number](n, thousands, decimal)
+}
+
+fn num round_even(num n) {
+    RUST[std_func::round_even](n)
+}
+
+fn int bit_count(int n) {
+    RUST[std_func::bit_count](n)
+}
+
+fn int leading_zeros(int n) {
+    RUST[std_func::leading_zeros](n)
+}
+
+fn int trailing_zeros(int n) {
+    RUST[std_func::trailing_zeros](n)
+}
+
+fn int clamp_int(int n, int lo, int hi) {
+    RUST[std_func::clamp_int](n, lo, hi)
+}
+
+fn int abs_int(int n) {
+    RUST[std_func::abs_int](n)
+}
+
+fn int wrapping_add(int a, int b) {
+    RUST[std_func::wrapping_add](a, b)
+}
+
+fn bool is_negative_zero(num n) {
+    RUST[std_func::is_negative_zero](n)
 }
\ No newline at end of file
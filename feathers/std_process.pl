@@ -0,0 +1,3 @@
+fn nun exit(num code) {
+    RUST[std_func::exit](code)
+}
\ No newline at end of file
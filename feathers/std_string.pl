@@ -0,0 +1,71 @@
+fn bool eq_ignore_case(str a, str b) {
+    RUST[std_func::str_eq_ignore_case](a, b)
+}
+
+fn str to_title_case(str s) {
+    RUST[std_func::str_to_title_case](s)
+}
+
+fn str trim_start(str s) {
+    RUST[std_func::str_trim_start](s)
+}
+
+fn str trim_end(str s) {
+    RUST[std_func::str_trim_end](s)
+}
+
+fn str strip_prefix(str s, str prefix) {
+    RUST[std_func::str_strip_prefix](s, prefix)
+}
+
+fn str strip_suffix(str s, str suffix) {
+    RUST[std_func::str_strip_suffix](s, suffix)
+}
+
+fn num count(str s, str needle) {
+    RUST[std_func::str_count](s, needle)
+}
+
+fn any lines(str s) {
+    RUST[std_func::str_lines](s)
+}
+
+fn num length(str s) {
+    RUST[std_func::str_length](s)
+}
+
+fn str concat(str a, str b) {
+    RUST[std_func::str_concat](a, b)
+}
+
+fn str substring(str s, num start, num len) {
+    RUST[std_func::str_substring](s, start, len)
+}
+
+fn any split(str s, str delimiter) {
+    RUST[std_func::str_split](s, delimiter)
+}
+
+fn str to_upper(str s) {
+    RUST[std_func::str_to_upper](s)
+}
+
+fn str to_lower(str s) {
+    RUST[std_func::str_to_lower](s)
+}
+
+fn str trim(str s) {
+    RUST[std_func::str_trim](s)
+}
+
+fn bool contains(str s, str needle) {
+    RUST[std_func::str_contains](s, needle)
+}
+
+fn num compare_unicode(str a, str b) {
+    RUST[std_func::str_compare_unicode](a, b)
+}
+
+fn any sort_unicode(any strings) {
+    RUST[std_func::str_sort_unicode](strings)
+}
\ No newline at end of file
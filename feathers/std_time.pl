@@ -0,0 +1,11 @@
+fn num now() {
+    RUST[std_func::time_now]()
+}
+
+fn num monotonic() {
+    RUST[std_func::time_monotonic]()
+}
+
+fn nun sleep(num ms) {
+    RUST[std_func::time_sleep](ms)
+}
\ No newline at end of file
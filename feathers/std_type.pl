@@ -0,0 +1,39 @@
+fn str type_of(any value) {
+    RUST[std_func::type_of](value)
+}
+
+fn bool is_num(any value) {
+    RUST[std_func::is_num](value)
+}
+
+fn bool is_int(any value) {
+    RUST[std_func::is_int](value)
+}
+
+fn bool is_str(any value) {
+    RUST[std_func::is_str](value)
+}
+
+fn bool is_bool(any value) {
+    RUST[std_func::is_bool](value)
+}
+
+fn bool is_nun(any value) {
+    RUST[std_func::is_nun](value)
+}
+
+fn bool is_array(any value) {
+    RUST[std_func::is_array](value)
+}
+
+fn bool is_map(any value) {
+    RUST[std_func::is_map](value)
+}
+
+fn bool is_function(any value) {
+    RUST[std_func::is_function](value)
+}
+
+fn any match_type(any value, any handlers) {
+    RUST[std_func::match_type](value, handlers)
+}
\ No newline at end of file
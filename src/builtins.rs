@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::interpreter::{Interpreter, Shared, Value};
+use crate::std_functions;
+
+pub type Builtin = fn(&Interpreter, Vec<Value>) -> Result<Value, String>;
+/// A builtin registered at runtime with `Interpreter::register_builtin`, may capture state.
+pub type InterpreterBuiltin = Rc<dyn Fn(&Interpreter, Vec<Value>) -> Result<Value, String>>;
+
+/// Builtins that need the interpreter, e.g. to call back into user functions.
+pub fn lookup(name: &str) -> Option<Builtin> {
+    match name {
+        "find" => Some(std_array_find),
+        "find_index" => Some(std_array_find_index),
+        "array_map" => Some(std_array_map),
+        "match_type" => Some(std_type_match_type),
+        "error" => Some(std_error_error),
+        "try" => Some(std_error_try),
+        _ => None,
+    }
+}
+
+fn position_of(interpreter: &Interpreter, name: &str, args: Vec<Value>) -> Result<Option<(usize, Value)>, String> {
+    if args.len() != 2 {
+        return Err(format!("{} function expects 2 arguments", name));
+    }
+    let elements = match &args[0] {
+        Value::Array(elements) => elements.borrow().clone(),
+        _ => return Err(format!("{} function expects an array as first argument", name)),
+    };
+    for (index, element) in elements.into_iter().enumerate() {
+        let found = interpreter.call_value(&args[1], vec![element.clone()])?
+            .as_condition()
+            .map_err(|err| format!("{} predicate: {}", name, err))?;
+        if found {
+            return Ok(Some((index, element)));
+        }
+    }
+    Ok(None)
+}
+
+// array
+pub fn std_array_find(interpreter: &Interpreter, args: Vec<Value>) -> Result<Value, String> {
+    Ok(position_of(interpreter, "find", args)?.map_or(Value::Nun, |(_, element)| element))
+}
+
+/// Returns a new array with `function` applied to every element.
+pub fn std_array_map(interpreter: &Interpreter, args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("map function expects 2 arguments".to_string());
+    }
+    let elements = match &args[0] {
+        Value::Array(elements) => elements.borrow().clone(),
+        _ => return Err("map function expects an array as first argument".to_string()),
+    };
+    let mapped = elements.into_iter()
+        .map(|element| interpreter.call_value(&args[1], vec![element]))
+        .collect::<Result<Vec<Value>, String>>()?;
+    Ok(Value::Array(Shared::new(mapped)))
+}
+
+pub fn std_array_find_index(interpreter: &Interpreter, args: Vec<Value>) -> Result<Value, String> {
+    Ok(position_of(interpreter, "find_index", args)?.map_or(Value::Number(-1.0), |(index, _)| Value::Number(index as f64)))
+}
+
+// type
+/// Calls the handler registered for the value's type name, or the `"_"` handler if there is none.
+pub fn std_type_match_type(interpreter: &Interpreter, args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("match_type function expects 2 arguments".to_string());
+    }
+    let handler = match &args[1] {
+        Value::Map(handlers) => {
+            let handlers = handlers.borrow();
+            // Integers are numbers too, a `num` handler takes them when there is no `int` one
+            let number = matches!(args[0], Value::Integer(_)).then(|| handlers.get("num")).flatten();
+            handlers.get(args[0].type_name()).or(number).or_else(|| handlers.get("_")).cloned()
+        }
+        _ => return Err("match_type function expects a map of handlers as second argument".to_string()),
+    };
+    match handler {
+        Some(handler) => interpreter.call_value(&handler, vec![args[0].clone()]),
+        None => Err(format!("match_type has no handler for type '{}' and no '_' default", args[0].type_name())),
+    }
+}
+
+// error
+// Errors are plain strings everywhere, so the value given to `error` travels next to the
+// message and is picked up again by the `try` that catches that message.
+struct Thrown {
+    message: String,
+    value: Value,
+}
+
+thread_local! {
+    static THROWN: RefCell<Option<Thrown>> = const { RefCell::new(None) };
+}
+
+/// Fails with `value` as the payload, `try` hands the value itself to its handler.
+pub fn std_error_error(_interpreter: &Interpreter, args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("error function expects 1 argument".to_string());
+    }
+    let value = args.into_iter().next().unwrap();
+    let message = format!("Uncaught error: {}", value);
+    THROWN.with(|thrown| *thrown.borrow_mut() = Some(Thrown { message: message.clone(), value }));
+    Err(message)
+}
+
+/// Calls `body` and returns its result. If it fails, returns `handler(e)` instead, where `e` is the
+/// value passed to `error`, or the message string for errors raised by the interpreter itself.
+pub fn std_error_try(interpreter: &Interpreter, args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("try function expects 2 arguments".to_string());
+    }
+    // A value left over from an error nothing caught must not be mistaken for this body's
+    THROWN.with(|thrown| thrown.borrow_mut().take());
+    let message = match interpreter.call_value(&args[0], Vec::new()) {
+        Ok(value) => return Ok(value),
+        // `exit` ends the program, it is not an error to handle
+        Err(message) if std_functions::exit_requested().is_some() => return Err(message),
+        Err(message) => message,
+    };
+    let thrown = THROWN.with(|thrown| thrown.borrow_mut().take());
+    let caught = match thrown {
+        // Messages may have gained context on the way up, e.g. the file of an include
+        Some(thrown) if message.ends_with(&thrown.message) => thrown.value,
+        _ => Value::String(message),
+    };
+    interpreter.call_value(&args[1], vec![caught])
+}
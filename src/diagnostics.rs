@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+/// How the cells of a table column are padded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Formats tool output such as the profile report and the function list, one row per line
+/// under a header. Column widths only depend on the contents and rows keep the given order, so
+/// callers sort them and the same input always gives byte-identical output.
+pub fn table(columns: &[(&str, Align)], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = columns.iter().enumerate()
+        .map(|(i, (header, _))| {
+            rows.iter().map(|row| row[i].chars().count()).fold(header.chars().count(), usize::max)
+        })
+        .collect();
+
+    let mut output = String::new();
+    let header: Vec<String> = columns.iter().map(|(header, _)| header.to_string()).collect();
+    for cells in std::iter::once(&header).chain(rows) {
+        let line: Vec<String> = cells.iter().zip(columns).zip(&widths)
+            .map(|((cell, (_, align)), width)| match align {
+                Align::Right => format!("{:>width$}", cell, width = width),
+                Align::Left => format!("{:<width$}", cell, width = width),
+            })
+            .collect();
+        output.push_str(line.join("  ").trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+/// Milliseconds with a fixed three decimals, e.g. `1.500`.
+pub fn millis(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
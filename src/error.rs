@@ -0,0 +1,51 @@
+use std::fmt;
+
+/// Why a program could not be run, for embedders that need more than the message.
+/// `Display` gives the same text the `String` errors carried before.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PelinError {
+    /// The source could not be split into tokens. `message` is the complete text, position included.
+    Lex { message: String, line: usize, column: usize },
+    /// The tokens do not form a program. `message` is the complete text of every error found,
+    /// one per line, and `line` and `column` are the position of the first.
+    Parse { message: String, line: usize, column: usize },
+    /// The program failed while it ran, including failed imports and includes.
+    Runtime { message: String },
+    /// The program called `std_process.exit`. The `pelin` binary exits with `code`, other
+    /// embedders decide for themselves.
+    Exit { code: i32, message: String },
+}
+
+impl PelinError {
+    /// The full error text, the same as `to_string()`.
+    pub fn message(&self) -> &str {
+        match self {
+            PelinError::Lex { message, .. }
+            | PelinError::Parse { message, .. }
+            | PelinError::Runtime { message }
+            | PelinError::Exit { message, .. } => message,
+        }
+    }
+}
+
+impl fmt::Display for PelinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for PelinError {}
+
+// The interpreter's internals and the std functions report runtime errors as strings
+impl From<String> for PelinError {
+    fn from(message: String) -> Self {
+        PelinError::Runtime { message }
+    }
+}
+
+// Code that still reports errors as strings can use `?` on results with a `PelinError`
+impl From<PelinError> for String {
+    fn from(error: PelinError) -> Self {
+        error.to_string()
+    }
+}
@@ -1,19 +1,34 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 use libloading::{Library, Symbol};
-use log::{debug, error, info, trace};
-use crate::interpreter::Value;
+use log::{debug, error, info, trace, warn};
+use crate::interpreter::{Interpreter, Value};
 use crate::lexer::Lexer;
 use crate::parser::{Parser, Expr};
 use crate::std_functions::*;
 
-pub type FeatherFunction = Arc<dyn Fn(Vec<Value>) -> Result<Value, String> + Send + Sync>;
+pub type FeatherFunction = Rc<dyn Fn(Vec<Value>) -> Result<Value, String>>;
+
+/// Signature of a function exported from a library in `rust_libs`.
+///
+/// The library links against this crate and receives the arguments as a borrowed slice of `len`
+/// values. It returns a value allocated with `Box::into_raw`, or null on error. Any `Value` can
+/// cross the boundary, including arrays and maps, as long as the library was built with the same
+/// compiler and pelin version as the interpreter.
+pub type NativeFunction = unsafe fn(*const Value, usize) -> *mut Value;
 
 pub struct Feather {
     pub name: String,
     pub functions: HashMap<String, FeatherFunction>,
+    /// Source file the feather was imported from
+    pub path: PathBuf,
+    /// Modification time of the source file when it was imported, if the platform reports one
+    pub modified: Option<SystemTime>,
 }
 
 impl Clone for Feather {
@@ -21,71 +36,351 @@ impl Clone for Feather {
         Feather {
             name: self.name.clone(),
             functions: self.functions.clone(),
+            path: self.path.clone(),
+            modified: self.modified,
         }
     }
 }
 
+struct FeatherSource {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    expressions: Vec<Expr>,
+}
+
+/// How arithmetic std functions treat `nun` arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NunMode {
+    /// `nun` is a type error like any other non-number.
+    #[default]
+    Strict,
+    /// Any arithmetic involving `nun` yields `nun`.
+    Propagate,
+}
+
+/// Library name under which `RUST[...]` calls reach the built-in std functions, e.g. `RUST[std_func::add]`.
+pub const STD_NAMESPACE: &str = "std_func";
+
+/// Error for a `RUST[...]` path that is not of the form `library::function`.
+pub fn invalid_rust_path(path: &str) -> String {
+    format!("Invalid Rust function path '{}': expected the form library::function, e.g. {}::add", path, STD_NAMESPACE)
+}
+
+const ARITHMETIC_FUNCTIONS: &[&str] = &["add", "subtract", "multiply", "divide", "mod", "int_divide", "floor_divide", "sqrt"];
+
 pub struct FeatherManager {
     pub feathers: HashMap<String, Feather>,
     pub project_root: PathBuf,
     pub libraries: Mutex<HashMap<String, Arc<Library>>>,
     pub std_functions: HashMap<String, FeatherFunction>,
+    pub nun_mode: NunMode,
+    // Feathers whose import is in progress, outermost first, to detect circular imports
+    importing: Vec<String>,
+}
+
+/// Why `project_root` can't hold feathers, or `None` if it is an existing directory.
+fn project_root_problem(project_root: &Path) -> Option<String> {
+    if !project_root.exists() {
+        Some(format!("Project root {} does not exist", project_root.display()))
+    } else if !project_root.is_dir() {
+        Some(format!("Project root {} is not a directory", project_root.display()))
+    } else {
+        None
+    }
 }
 
 impl FeatherManager {
+    /// Accepts any root, a missing one is logged and reported again by `import`.
     pub fn new(project_root: PathBuf) -> Self {
         info!("Creating new FeatherManager with project root: {:?}", project_root);
+        if let Some(problem) = project_root_problem(&project_root) {
+            warn!("{}, feathers can't be imported", problem);
+        }
         let mut manager = FeatherManager {
             feathers: HashMap::new(),
             project_root,
             libraries: Mutex::new(HashMap::new()),
             std_functions: HashMap::new(),
+            nun_mode: NunMode::default(),
+            importing: Vec::new(),
         };
         manager.register_std_functions();
         manager
     }
 
+    /// Like `new`, but fails if `project_root` is not an existing directory.
+    pub fn try_new(project_root: PathBuf) -> Result<Self, String> {
+        match project_root_problem(&project_root) {
+            Some(problem) => Err(problem),
+            None => Ok(Self::new(project_root)),
+        }
+    }
+
+    pub fn set_nun_mode(&mut self, mode: NunMode) {
+        self.nun_mode = mode;
+    }
+
+    fn call_std_function(&self, function_name: &str, args: Vec<Value>) -> Result<Value, String> {
+        let func = self.std_functions.get(function_name).ok_or_else(|| {
+            error!("Standard function not found: {}", function_name);
+            format!("Standard function not found: {}", function_name)
+        })?;
+
+        if self.nun_mode == NunMode::Propagate
+            && ARITHMETIC_FUNCTIONS.contains(&function_name)
+            && args.contains(&Value::Nun)
+        {
+            debug!("Propagating nun through standard function: {}", function_name);
+            return Ok(Value::Nun);
+        }
+
+        debug!("Calling standard function: {}", function_name);
+        func(args)
+    }
+
     fn register_std_functions(&mut self) {
         debug!("Registering standard functions");
-        self.std_functions.insert("add".to_string(), Arc::new(std_num_add));
-        self.std_functions.insert("subtract".to_string(), Arc::new(std_num_subtract));
-        self.std_functions.insert("multiply".to_string(), Arc::new(std_num_multiply));
-        self.std_functions.insert("divide".to_string(), Arc::new(std_num_divide));
-        self.std_functions.insert("sqrt".to_string(), Arc::new(std_num_sqrt));
-        self.std_functions.insert("print".to_string(), Arc::new(std_io_print));
-        self.std_functions.insert("if".to_string(), Arc::new(std_control_if));
-        self.std_functions.insert("math_sin".to_string(), Arc::new(std_math_sin));
-        self.std_functions.insert("math_cos".to_string(), Arc::new(std_math_cos));
-        self.std_functions.insert("to_string".to_string(), Arc::new(std_convert_to_string));
-        self.std_functions.insert("to_number".to_string(), Arc::new(std_convert_to_number));
-        self.std_functions.insert("file_read".to_string(), Arc::new(std_file_read));
-        self.std_functions.insert("file_write".to_string(), Arc::new(std_file_write));
-        self.std_functions.insert("eq".to_string(), Arc::new(std_compare_eq));
-        self.std_functions.insert("neq".to_string(), Arc::new(std_compare_neq));
-        self.std_functions.insert("gt".to_string(), Arc::new(std_compare_gt));
-        self.std_functions.insert("gte".to_string(), Arc::new(std_compare_gte));
-        self.std_functions.insert("lt".to_string(), Arc::new(std_compare_lt));
-        self.std_functions.insert("lte".to_string(), Arc::new(std_compare_lte));
-        self.std_functions.insert("lt".to_string(), Arc::new(std_compare_lt));
-        self.std_functions.insert("lte".to_string(), Arc::new(std_compare_lte));
-        self.std_functions.insert("and".to_string(), Arc::new(std_logic_and));
-        self.std_functions.insert("not".to_string(), Arc::new(std_logic_not));
+        self.std_functions.insert("add".to_string(), Rc::new(std_num_add));
+        self.std_functions.insert("subtract".to_string(), Rc::new(std_num_subtract));
+        self.std_functions.insert("multiply".to_string(), Rc::new(std_num_multiply));
+        self.std_functions.insert("divide".to_string(), Rc::new(std_num_divide));
+        self.std_functions.insert("mod".to_string(), Rc::new(std_num_mod));
+        self.std_functions.insert("int_divide".to_string(), Rc::new(std_num_int_divide));
+        self.std_functions.insert("floor_divide".to_string(), Rc::new(std_num_floor_divide));
+        self.std_functions.insert("sqrt".to_string(), Rc::new(std_num_sqrt));
+        self.std_functions.insert("format_number".to_string(), Rc::new(std_num_format_number));
+        self.std_functions.insert("round_even".to_string(), Rc::new(std_num_round_even));
+        self.std_functions.insert("bit_count".to_string(), Rc::new(std_num_bit_count));
+        self.std_functions.insert("leading_zeros".to_string(), Rc::new(std_num_leading_zeros));
+        self.std_functions.insert("trailing_zeros".to_string(), Rc::new(std_num_trailing_zeros));
+        self.std_functions.insert("clamp_int".to_string(), Rc::new(std_num_clamp_int));
+        self.std_functions.insert("abs_int".to_string(), Rc::new(std_num_abs_int));
+        self.std_functions.insert("wrapping_add".to_string(), Rc::new(std_num_wrapping_add));
+        self.std_functions.insert("is_negative_zero".to_string(), Rc::new(std_num_is_negative_zero));
+        self.std_functions.insert("print".to_string(), Rc::new(std_io_print));
+        self.std_functions.insert("println".to_string(), Rc::new(std_io_println));
+        self.std_functions.insert("print_colored".to_string(), Rc::new(std_io_print_colored));
+        self.std_functions.insert("print_styled".to_string(), Rc::new(std_io_print_styled));
+        self.std_functions.insert("flush".to_string(), Rc::new(std_io_flush));
+        self.std_functions.insert("read_line".to_string(), Rc::new(std_io_read_line));
+        self.std_functions.insert("read_all".to_string(), Rc::new(std_io_read_all));
+        self.std_functions.insert("if".to_string(), Rc::new(std_control_if));
+        self.std_functions.insert("math_sin".to_string(), Rc::new(std_math_sin));
+        self.std_functions.insert("math_cos".to_string(), Rc::new(std_math_cos));
+        self.std_functions.insert("math_tan".to_string(), Rc::new(std_math_tan));
+        self.std_functions.insert("math_pow".to_string(), Rc::new(std_math_pow));
+        self.std_functions.insert("math_log".to_string(), Rc::new(std_math_log));
+        self.std_functions.insert("math_abs".to_string(), Rc::new(std_math_abs));
+        self.std_functions.insert("math_floor".to_string(), Rc::new(std_math_floor));
+        self.std_functions.insert("math_ceil".to_string(), Rc::new(std_math_ceil));
+        self.std_functions.insert("math_round".to_string(), Rc::new(std_math_round));
+        self.std_functions.insert("math_pi".to_string(), Rc::new(std_math_pi));
+        self.std_functions.insert("math_e".to_string(), Rc::new(std_math_e));
+        self.std_functions.insert("math_min".to_string(), Rc::new(std_math_min));
+        self.std_functions.insert("math_max".to_string(), Rc::new(std_math_max));
+        self.std_functions.insert("math_pow_int".to_string(), Rc::new(std_math_pow_int));
+        self.std_functions.insert("str_eq_ignore_case".to_string(), Rc::new(std_string_eq_ignore_case));
+        self.std_functions.insert("str_to_title_case".to_string(), Rc::new(std_string_to_title_case));
+        self.std_functions.insert("str_trim_start".to_string(), Rc::new(std_string_trim_start));
+        self.std_functions.insert("str_trim_end".to_string(), Rc::new(std_string_trim_end));
+        self.std_functions.insert("str_strip_prefix".to_string(), Rc::new(std_string_strip_prefix));
+        self.std_functions.insert("str_strip_suffix".to_string(), Rc::new(std_string_strip_suffix));
+        self.std_functions.insert("str_count".to_string(), Rc::new(std_string_count));
+        self.std_functions.insert("str_lines".to_string(), Rc::new(std_string_lines));
+        self.std_functions.insert("str_length".to_string(), Rc::new(std_string_length));
+        self.std_functions.insert("str_concat".to_string(), Rc::new(std_string_concat));
+        self.std_functions.insert("str_substring".to_string(), Rc::new(std_string_substring));
+        self.std_functions.insert("str_split".to_string(), Rc::new(std_string_split));
+        self.std_functions.insert("str_to_upper".to_string(), Rc::new(std_string_to_upper));
+        self.std_functions.insert("str_to_lower".to_string(), Rc::new(std_string_to_lower));
+        self.std_functions.insert("str_trim".to_string(), Rc::new(std_string_trim));
+        self.std_functions.insert("str_contains".to_string(), Rc::new(std_string_contains));
+        self.std_functions.insert("str_compare_unicode".to_string(), Rc::new(std_string_compare_unicode));
+        self.std_functions.insert("str_sort_unicode".to_string(), Rc::new(std_string_sort_unicode));
+        self.std_functions.insert("array_push".to_string(), Rc::new(std_array_push));
+        self.std_functions.insert("array_get".to_string(), Rc::new(std_array_get));
+        self.std_functions.insert("array_len".to_string(), Rc::new(std_array_len));
+        self.std_functions.insert("array_splice".to_string(), Rc::new(std_array_splice));
+        self.std_functions.insert("array_unique".to_string(), Rc::new(std_array_unique));
+        self.std_functions.insert("array_dedup".to_string(), Rc::new(std_array_dedup));
+        self.std_functions.insert("array_union".to_string(), Rc::new(std_array_union));
+        self.std_functions.insert("array_intersection".to_string(), Rc::new(std_array_intersection));
+        self.std_functions.insert("array_difference".to_string(), Rc::new(std_array_difference));
+        self.std_functions.insert("array_chunk".to_string(), Rc::new(std_array_chunk));
+        self.std_functions.insert("array_window".to_string(), Rc::new(std_array_window));
+        self.std_functions.insert("array_fill".to_string(), Rc::new(std_array_fill));
+        self.std_functions.insert("array_range_to_array".to_string(), Rc::new(std_array_range_to_array));
+        self.std_functions.insert("map_get".to_string(), Rc::new(std_map_get));
+        self.std_functions.insert("map_set".to_string(), Rc::new(std_map_set));
+        self.std_functions.insert("map_merge".to_string(), Rc::new(std_map_merge));
+        self.std_functions.insert("map_remove".to_string(), Rc::new(std_map_remove));
+        self.std_functions.insert("map_has".to_string(), Rc::new(std_map_has));
+        self.std_functions.insert("map_keys".to_string(), Rc::new(std_map_keys));
+        self.std_functions.insert("map_get_path".to_string(), Rc::new(std_map_get_path));
+        self.std_functions.insert("base64_encode".to_string(), Rc::new(std_encoding_base64_encode));
+        self.std_functions.insert("base64_decode".to_string(), Rc::new(std_encoding_base64_decode));
+        self.std_functions.insert("hex_encode".to_string(), Rc::new(std_encoding_hex_encode));
+        self.std_functions.insert("hex_decode".to_string(), Rc::new(std_encoding_hex_decode));
+        self.std_functions.insert("json_parse".to_string(), Rc::new(std_json_parse));
+        self.std_functions.insert("json_stringify".to_string(), Rc::new(std_json_stringify));
+        self.std_functions.insert("type_of".to_string(), Rc::new(std_type_of));
+        self.std_functions.insert("is_num".to_string(), Rc::new(std_type_is_num));
+        self.std_functions.insert("is_int".to_string(), Rc::new(std_type_is_int));
+        self.std_functions.insert("is_str".to_string(), Rc::new(std_type_is_str));
+        self.std_functions.insert("is_bool".to_string(), Rc::new(std_type_is_bool));
+        self.std_functions.insert("is_nun".to_string(), Rc::new(std_type_is_nun));
+        self.std_functions.insert("is_array".to_string(), Rc::new(std_type_is_array));
+        self.std_functions.insert("is_map".to_string(), Rc::new(std_type_is_map));
+        self.std_functions.insert("is_function".to_string(), Rc::new(std_type_is_function));
+        self.std_functions.insert("to_string".to_string(), Rc::new(std_convert_to_string));
+        self.std_functions.insert("to_number".to_string(), Rc::new(std_convert_to_number));
+        self.std_functions.insert("parse_int".to_string(), Rc::new(std_convert_parse_int));
+        self.std_functions.insert("parse_float".to_string(), Rc::new(std_convert_parse_float));
+        self.std_functions.insert("file_read".to_string(), Rc::new(std_file_read));
+        self.std_functions.insert("file_write".to_string(), Rc::new(std_file_write));
+        self.std_functions.insert("env_get".to_string(), Rc::new(std_env_get));
+        self.std_functions.insert("env_set".to_string(), Rc::new(std_env_set));
+        self.std_functions.insert("exit".to_string(), Rc::new(std_process_exit));
+        self.std_functions.insert("time_now".to_string(), Rc::new(std_time_now));
+        self.std_functions.insert("time_monotonic".to_string(), Rc::new(std_time_monotonic));
+        self.std_functions.insert("time_sleep".to_string(), Rc::new(std_time_sleep));
+        self.std_functions.insert("eq".to_string(), Rc::new(std_compare_eq));
+        self.std_functions.insert("neq".to_string(), Rc::new(std_compare_neq));
+        self.std_functions.insert("gt".to_string(), Rc::new(std_compare_gt));
+        self.std_functions.insert("gte".to_string(), Rc::new(std_compare_gte));
+        self.std_functions.insert("lt".to_string(), Rc::new(std_compare_lt));
+        self.std_functions.insert("lte".to_string(), Rc::new(std_compare_lte));
+        self.std_functions.insert("and".to_string(), Rc::new(std_logic_and));
+        self.std_functions.insert("or".to_string(), Rc::new(std_logic_or));
+        self.std_functions.insert("not".to_string(), Rc::new(std_logic_not));
         debug!("Standard functions registered: {:?}", self.std_functions.keys());
     }
 
-    pub fn import(&mut self, name: &str) -> Result<(), String> {
-        info!("Attempting to import feather: {}", name);
+    /// Lexes and parses every feather in the `feathers` directory without running anything.
+    /// Returns the path and error message of each feather that failed, sorted by path.
+    pub fn validate_all(&self) -> Result<Vec<(PathBuf, String)>, String> {
+        let feathers_dir = self.project_root.join("feathers");
+        let entries = fs::read_dir(&feathers_dir)
+            .map_err(|e| format!("Failed to read feathers directory {}: {}", feathers_dir.display(), e))?;
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "pl"))
+            .collect();
+        paths.sort();
+
+        let mut failures = Vec::new();
+        for path in paths {
+            debug!("Validating feather: {:?}", path);
+            let result = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read file: {}", e))
+                .and_then(|content| Ok(Lexer::new(&content).tokenize()?))
+                .and_then(|tokens| Ok(Parser::new(tokens).parse()?));
+            if let Err(err) = result {
+                error!("Feather {:?} failed validation: {}", path, err);
+                failures.push((path, err));
+            }
+        }
+        Ok(failures)
+    }
+
+    /// Source file `imp <name>` reads: `feathers/<name>.pl`, or `<name>.pl` in the project root
+    /// for relative names like `.local`.
+    pub fn feather_path(&self, name: &str) -> PathBuf {
         let path = if name.starts_with('.') {
             self.project_root.join(name.trim_start_matches('.'))
         } else {
             self.project_root.join("feathers").join(name)
         };
+        path.with_extension("pl")
+    }
+
+    /// File a `RUST[<name>::...]` call loads: `rust_libs/<name>` with the platform's library
+    /// prefix and extension, e.g. `libname.so` on Linux, `libname.dylib` on macOS and `name.dll`
+    /// on Windows.
+    pub fn library_path(&self, name: &str) -> PathBuf {
+        let file_name = format!("{}{}{}", std::env::consts::DLL_PREFIX, name, std::env::consts::DLL_SUFFIX);
+        self.project_root.join("rust_libs").join(file_name)
+    }
+
+    /// Imports a feather into `manager`. The feather's top level runs in an interpreter of its
+    /// own that refers back to `manager`, so the feathers it imports are loaded there as well.
+    pub fn import(manager: &Rc<RefCell<FeatherManager>>, name: &str) -> Result<(), String> {
+        info!("Attempting to import feather: {}", name);
+        {
+            let mut this = manager.borrow_mut();
+            if this.importing.iter().any(|importing| importing == name) {
+                let chain: Vec<&str> = this.importing.iter().map(String::as_str).chain([name]).collect();
+                error!("Circular import of feather: {}", name);
+                return Err(format!("Circular import detected: {}", chain.join(" -> ")));
+            }
+            this.importing.push(name.to_string());
+        }
+        let result = Self::import_feather(manager, name);
+        manager.borrow_mut().importing.pop();
+        result
+    }
+
+    // The manager is not borrowed while the feather's top level runs, it may import feathers itself
+    fn import_feather(manager: &Rc<RefCell<FeatherManager>>, name: &str) -> Result<(), String> {
+        let Some(FeatherSource { path, modified, expressions }) = manager.borrow().read_feather(name)? else {
+            return Ok(());
+        };
+
+        // Relative feathers are addressed by their bare name, so `imp .local` exposes `local.f()`
+        let feather_name = name.trim_start_matches('.');
+        let mut feather = Feather {
+            name: feather_name.to_string(),
+            functions: HashMap::new(),
+            path,
+            modified,
+        };
+
+        // The feather runs in its own interpreter, so its functions see each other while
+        // callers only see them through its namespace.
+        let interpreter = Rc::new(Interpreter::for_feather(manager));
+        interpreter.run_program(&expressions)?;
+
+        for expr in &expressions {
+            if let Expr::FunctionDefinition { name, .. } = expr {
+                debug!("Processing function definition: {}", name);
+                let function = interpreter.get_variable(name)
+                    .ok_or_else(|| format!("Function '{}' was not defined by feather '{}'", name, feather.name))?;
+                let interpreter = Rc::clone(&interpreter);
+                let func_name = name.clone();
+                let func: FeatherFunction = Rc::new(move |args: Vec<Value>| -> Result<Value, String> {
+                    trace!("Calling feather function: {} with args: {:?}", func_name, args);
+                    interpreter.call_value(&function, args)
+                });
+                feather.functions.insert(name.clone(), func);
+                debug!("Function '{}' added to feather", name);
+            }
+        }
 
-        let path = path.with_extension("pl");
+        manager.borrow_mut().feathers.insert(feather_name.to_string(), feather);
+        info!("Feather '{}' successfully imported", name);
+        Ok(())
+    }
+
+    // The feather's parsed file, or `None` when importing it again is a no-op because it is
+    // loaded and unchanged. If the file is gone, the loaded version stays in use.
+    fn read_feather(&self, name: &str) -> Result<Option<FeatherSource>, String> {
+        let path = self.feather_path(name);
         debug!("Full path for feather: {:?}", path);
 
+        let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+        if let Some(loaded) = self.feathers.get(name.trim_start_matches('.')) {
+            if loaded.path == path && (modified.is_none() || modified == loaded.modified) {
+                debug!("Feather '{}' is already loaded", name);
+                return Ok(None);
+            }
+        }
+
         if !path.exists() {
             error!("Feather file not found: {:?}", path);
+            if let Some(problem) = project_root_problem(&self.project_root) {
+                return Err(format!("Could not import feather '{}': {}", name, problem));
+            }
             return Err(format!("Could not find Feather file: {}", path.display()));
         }
 
@@ -104,60 +399,18 @@ impl FeatherManager {
         let mut parser = Parser::new(tokens);
         let expressions = parser.parse()?;
         debug!("Parsing successful. Expression count: {}", expressions.len());
-
-        let mut feather = Feather {
-            name: name.to_string(),
-            functions: HashMap::new(),
-        };
-
-        let feather_manager = Arc::new(self.clone());
-
-        for expr in expressions {
-            if let Expr::FunctionDefinition { name, parameters: _, body, .. } = expr {
-                debug!("Processing function definition: {}", name);
-                let feather_manager = Arc::clone(&feather_manager);
-                let func_name = name.clone(); // Clone the name for use in the closure
-                let func = Arc::new(move |args: Vec<Value>| -> Result<Value, String> {
-                    trace!("Calling feather function: {} with args: {:?}", func_name, args);
-                    if let Some(body_expr) = body.first() {
-                        if let Expr::RustFunctionCall { path, arguments: _ } = &**body_expr {
-                            feather_manager.call_rust_function(&path.join("::"), args)
-                        } else {
-                            error!("Function body of '{}' does not contain a Rust function call", func_name);
-                            Err("Function body of '{}' does not contain a Rust function call".to_string())
-                        }
-                    } else {
-                        error!("Function body is empty");
-                        Err("Function body is empty".to_string())
-                    }
-                });
-                feather.functions.insert(name.clone(), func);
-                debug!("Function '{}' added to feather", name);
-            }
-        }
-
-        self.feathers.insert(name.to_string(), feather);
-        info!("Feather '{}' successfully imported", name);
-        Ok(())
+        Ok(Some(FeatherSource { path, modified, expressions }))
     }
 
-    fn call_rust_function(&self, path: &str, args: Vec<Value>) -> Result<Value, String> {
-        if path.starts_with("std_func") {
-            let function_name = path.trim_start_matches("std_func::");
-            if let Some(func) = self.std_functions.get(function_name) {
-                debug!("Calling standard function: {}", function_name);
-                return func(args);
-            } else {
-                error!("Standard function not found: {}", function_name);
-                return Err(format!("Standard function not found: {}", function_name));
-            }
-        }
-        
+    pub fn call_rust_function(&self, path: &str, args: Vec<Value>) -> Result<Value, String> {
         debug!("Calling Rust function: {} with args: {:?}", path, args);
         let parts: Vec<&str> = path.split("::").collect();
-        if parts.len() < 2 {
+        if parts.len() < 2 || parts.iter().any(|part| part.is_empty()) {
             error!("Invalid Rust function path: {}", path);
-            return Err(format!("Invalid Rust function path: {}", path));
+            return Err(invalid_rust_path(path));
+        }
+        if parts[0] == STD_NAMESPACE {
+            return self.call_std_function(&parts[1..].join("::"), args);
         }
 
         let library_name = parts[0];
@@ -172,7 +425,7 @@ impl FeatherManager {
         };
 
         unsafe {
-            let func: Symbol<unsafe fn(*const Value, usize) -> *mut Value> = match library.get(function_name.as_bytes()) {
+            let func: Symbol<NativeFunction> = match library.get(function_name.as_bytes()) {
                 Ok(f) => f,
                 Err(e) => {
                     error!("Failed to load function '{}' from library '{}': {:?}", function_name, library_name, e);
@@ -197,14 +450,22 @@ impl FeatherManager {
         debug!("Calling function '{}' from feather '{}' with args: {:?}", function_name, feather_name, arguments);
 
         // First, check if it's a standard function
-        if feather_name == "std_func" {
-            if let Some(func) = self.std_functions.get(function_name) {
-                debug!("Calling standard function: {}", function_name);
-                return func(arguments);
-            }
+        if feather_name == STD_NAMESPACE && self.std_functions.contains_key(function_name) {
+            return self.call_std_function(function_name, arguments);
         }
 
         // If not a standard function, look for the feather
+        let function = self.get_function(feather_name, function_name)?;
+
+        // Call the feather function
+        debug!("Calling feather function '{}' from '{}'", function_name, feather_name);
+        let result = function(arguments);
+        debug!("Feather function call result: {:?}", result);
+        result
+    }
+
+    /// Looks up a feather function so it can be called without keeping the manager borrowed.
+    pub fn get_function(&self, feather_name: &str, function_name: &str) -> Result<FeatherFunction, String> {
         let feather = self.feathers.get(feather_name)
             .ok_or_else(|| {
                 error!("Feather '{}' not found", feather_name);
@@ -212,17 +473,12 @@ impl FeatherManager {
             })?;
 
         // Look for the function in the feather
-        let function = feather.functions.get(function_name)
+        feather.functions.get(function_name)
+            .cloned()
             .ok_or_else(|| {
                 error!("Function '{}' not found in feather '{}'", function_name, feather_name);
                 format!("Function '{}' not found in feather '{}'", function_name, feather_name)
-            })?;
-
-        // Call the feather function
-        debug!("Calling feather function '{}' from '{}'", function_name, feather_name);
-        let result = function(arguments);
-        debug!("Feather function call result: {:?}", result);
-        result
+            })
     }
 
     fn load_library(&self, name: &str) -> Result<Arc<Library>, String> {
@@ -232,12 +488,12 @@ impl FeatherManager {
             debug!("Library '{}' already loaded", name);
             Ok(lib.clone())
         } else {
-            let path = self.project_root.join("rust_libs").join(format!("lib{}.so", name));
+            let path = self.library_path(name);
             debug!("Loading library from path: {:?}", path);
             let library = Arc::new(unsafe {
                 Library::new(&path).map_err(|e| {
                     error!("Failed to load library '{}' from {:?}: {:?}", name, path, e);
-                    format!("Failed to load library: {:?}", e)
+                    format!("could not load {}: {}", path.display(), e)
                 })?
             });
             libraries.insert(name.to_string(), library.clone());
@@ -254,6 +510,8 @@ impl Clone for FeatherManager {
             project_root: self.project_root.clone(),
             libraries: Mutex::new(self.libraries.lock().unwrap().clone()),
             std_functions: self.std_functions.clone(),
+            nun_mode: self.nun_mode,
+            importing: self.importing.clone(),
         }
     }
 }
\ No newline at end of file
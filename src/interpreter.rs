@@ -1,31 +1,328 @@
-use std::rc::Rc;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use std::cell::{Cell, Ref, RefCell, RefMut};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use log::trace;
-use crate::feather::FeatherManager;
-use crate::parser::{Expr, Type};
+use crate::builtins::{self, InterpreterBuiltin};
+use crate::diagnostics::{self, Align};
+use crate::error::PelinError;
+use crate::feather::{self, FeatherManager, STD_NAMESPACE};
+use crate::lexer::Lexer;
+use crate::parser::{BinaryOperator, Expr, LogicalOperator, Parser, Type, UnaryOperator};
+use crate::std_functions;
 
+/// Handle to the elements of an array or map.
+///
+/// Cloning a handle shares the collection, which gives collections their reference semantics.
+/// `copy_on_write` instead creates a new handle that shares the elements only until either handle
+/// mutates them, at which point the mutated side gets its own copy.
+pub struct Shared<T: Clone>(Rc<RefCell<Rc<T>>>);
+
+impl<T: Clone> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Shared(Rc::new(RefCell::new(Rc::new(value))))
+    }
+
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref::map(self.0.borrow(), |value| &**value)
+    }
+
+    /// Mutable access, copying the elements first if another copy-on-write handle still shares them.
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        RefMut::map(self.0.borrow_mut(), Rc::make_mut)
+    }
+
+    pub fn copy_on_write(&self) -> Self {
+        Shared(Rc::new(RefCell::new(Rc::clone(&self.0.borrow()))))
+    }
+
+    /// Whether both handles currently read the same elements, without copying.
+    pub fn shares_elements_with(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0.borrow(), &other.0.borrow())
+    }
+}
+
+impl<T: Clone> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared(Rc::clone(&self.0))
+    }
+}
+
+impl<T: Clone + PartialEq> PartialEq for Shared<T> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.borrow() == *other.borrow()
+    }
+}
+
+impl<T: Clone + std::fmt::Debug> std::fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.borrow().fmt(f)
+    }
+}
+
+/// A runtime value.
+///
+/// Numbers are floats unless written as integer literals (`5i`, `0xff`); arithmetic on two
+/// integers stays integral, mixing an integer with a float yields a float.
+///
+/// Scalars (numbers, strings, booleans, `nun`) have value semantics: binding one to another
+/// name or passing it to a function copies it. Arrays and maps have reference semantics:
+/// cloning a `Value` shares the underlying collection, so a mutation is visible through every
+/// name bound to it, including the caller's binding when a collection is passed to a function.
+/// In copy-on-write mode bindings use `Value::copy_on_write` instead, see `Shared`.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Number(f64),
+    Integer(i64),
     String(String),
     Boolean(bool),
     Nun,
+    Array(Shared<Vec<Value>>),
+    // Keys are kept sorted so iteration order is deterministic
+    Map(Shared<BTreeMap<String, Value>>),
     Function(Rc<Function>),
     FeatherFunction(String, String),
 }
 
+impl Value {
+    /// Name of the value's runtime type as used in Pelikan source, e.g. `num` or `array`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "num",
+            Value::Integer(_) => "int",
+            Value::String(_) => "str",
+            Value::Boolean(_) => "bool",
+            Value::Nun => "nun",
+            Value::Array(_) => "array",
+            Value::Map(_) => "map",
+            Value::Function(_) | Value::FeatherFunction(_, _) => "function",
+        }
+    }
+
+    /// Truth value of a condition. Only booleans are conditions, there is no implicit truthiness.
+    pub fn as_condition(&self) -> Result<bool, String> {
+        match self {
+            Value::Boolean(b) => Ok(*b),
+            other => Err(format!("condition must be a boolean, got {} ({})", other.type_name(), ConditionValue(other))),
+        }
+    }
+
+    /// The number as a float, integers are converted. This and the other `as_` accessors are for
+    /// Rust functions reading their arguments; a wrong type is an error naming both types.
+    pub fn as_number(&self) -> Result<f64, String> {
+        match self {
+            Value::Number(n) => Ok(*n),
+            Value::Integer(n) => Ok(*n as f64),
+            other => Err(format!("expected num, got {}", other.type_name())),
+        }
+    }
+
+    /// The contents of a string, borrowed.
+    pub fn as_string(&self) -> Result<&str, String> {
+        match self {
+            Value::String(s) => Ok(s),
+            other => Err(format!("expected str, got {}", other.type_name())),
+        }
+    }
+
+    /// The boolean itself, unlike `as_condition` the error does not mention conditions.
+    pub fn as_bool(&self) -> Result<bool, String> {
+        match self {
+            Value::Boolean(b) => Ok(*b),
+            other => Err(format!("expected bool, got {}", other.type_name())),
+        }
+    }
+
+    /// Equality as `==` sees it: unlike `PartialEq`, an integer equals the float with the same
+    /// value, so `1i == 1`, also inside arrays and maps.
+    pub fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Integer(a), Value::Number(b)) | (Value::Number(b), Value::Integer(a)) => {
+                // Exact, a large integer is not equal to the nearest float
+                b.fract() == 0.0 && *b >= i64::MIN as f64 && *b < i64::MAX as f64 && *b as i64 == *a
+            }
+            (Value::Array(a), Value::Array(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.equals(b))
+            }
+            (Value::Map(a), Value::Map(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|((ka, va), (kb, vb))| ka == kb && va.equals(vb))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Whether the value is `nun`.
+    pub fn is_nun(&self) -> bool {
+        matches!(self, Value::Nun)
+    }
+
+    /// Follows a path like `a.b[0].c` through nested maps and arrays. Returns `nun` if a key or
+    /// index along the way is missing, and an error only if the path itself is malformed.
+    pub fn get_path(&self, path: &str) -> Result<Value, String> {
+        let mut current = self.clone();
+        for segment in parse_path(path)? {
+            let next = match (&current, segment) {
+                (Value::Map(entries), PathSegment::Key(key)) => entries.borrow().get(key).cloned(),
+                (Value::Array(elements), PathSegment::Index(index)) => elements.borrow().get(index).cloned(),
+                _ => None,
+            };
+            match next {
+                Some(value) => current = value,
+                None => return Ok(Value::Nun),
+            }
+        }
+        Ok(current)
+    }
+
+    /// The value to bind to a new name in copy-on-write mode: collections get a handle of their
+    /// own that shares the elements until it or the original is mutated.
+    pub fn copy_on_write(&self) -> Value {
+        match self {
+            Value::Array(elements) => Value::Array(elements.copy_on_write()),
+            Value::Map(entries) => Value::Map(entries.copy_on_write()),
+            other => other.clone(),
+        }
+    }
+}
+
+// Written the way the value would appear in source, strings inside collections are quoted
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Nun => write!(f, "nun"),
+            Value::Array(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_element(f, element)?;
+                }
+                write!(f, "]")
+            },
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{:?} = ", key)?;
+                    write_element(f, value)?;
+                }
+                write!(f, "}}")
+            },
+            Value::Function(function) => write!(f, "<fn {}>", function.name),
+            Value::FeatherFunction(feather, function) => write!(f, "<fn {}.{}>", feather, function),
+        }
+    }
+}
+
+enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+// `a.b[0]` is the key `a`, the key `b` and the index 0. Keys can't contain `.`, `[` or `]`.
+fn parse_path(path: &str) -> Result<Vec<PathSegment<'_>>, String> {
+    let malformed = |reason: &str| format!("Malformed path '{}': {}", path, reason);
+    let mut segments = Vec::new();
+    let mut rest = path;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let (index, after) = after.split_once(']').ok_or_else(|| malformed("missing ']'"))?;
+            let index = index.parse().map_err(|_| malformed(&format!("'{}' is not an array index", index)))?;
+            segments.push(PathSegment::Index(index));
+            rest = after;
+        } else {
+            // A key follows the start of the path or a '.'
+            if !segments.is_empty() {
+                rest = rest.strip_prefix('.').ok_or_else(|| malformed("expected '.' or '[' between segments"))?;
+            }
+            let end = rest.find(['.', '[', ']']).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(malformed("empty key"));
+            }
+            segments.push(PathSegment::Key(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+    Ok(segments)
+}
+
+// Quotes strings so `got str ("yes")` is not mistaken for a type or keyword
+struct ConditionValue<'a>(&'a Value);
+
+impl std::fmt::Display for ConditionValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_element(f, self.0)
+    }
+}
+
+fn write_element(f: &mut std::fmt::Formatter<'_>, value: &Value) -> std::fmt::Result {
+    match value {
+        Value::String(s) => write!(f, "{:?}", s),
+        other => write!(f, "{}", other),
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum InterpretResult {
     Value(Value),
     Return(Value),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Clone)]
 pub struct Function {
+    name: String,
     params: Vec<(Type, String)>,
+    return_type: Type,
     body: Vec<Rc<Expr>>,
     closure: Rc<RefCell<Environment>>,
+    // Feathers visible where the function was defined, even when it is called from inside another feather
+    feather_manager: ManagerRef,
+    aliases: Aliases,
+}
+
+// Names given with `imp name as alias` in one program or feather, mapped to the feather's own name
+type Aliases = Rc<RefCell<HashMap<String, String>>>;
+
+/// How an interpreter reaches its feather manager. The manager's feathers own the interpreters
+/// that ran them, so those only refer back to it instead of keeping it alive in a cycle.
+#[derive(Clone)]
+enum ManagerRef {
+    Owned(Rc<RefCell<FeatherManager>>),
+    Feather(Weak<RefCell<FeatherManager>>),
+}
+
+impl ManagerRef {
+    fn get(&self) -> Result<Rc<RefCell<FeatherManager>>, String> {
+        match self {
+            ManagerRef::Owned(manager) => Ok(Rc::clone(manager)),
+            ManagerRef::Feather(manager) => manager.upgrade()
+                .ok_or_else(|| "The feather manager of this feather no longer exists".to_string()),
+        }
+    }
+}
+
+// The closure usually contains the function itself, so compare and print functions by identity
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl std::fmt::Debug for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Function").field("name", &self.name).field("params", &self.params).finish_non_exhaustive()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -34,6 +331,12 @@ pub struct Environment {
     enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Environment {
     pub fn new() -> Self {
         Environment {
@@ -46,6 +349,11 @@ impl Environment {
         self.values.insert(name, value);
     }
 
+    /// Defines all bindings at once, a later binding of the same name wins like with `define`.
+    pub fn define_many(&mut self, bindings: Vec<(String, Value)>) {
+        self.values.extend(bindings);
+    }
+
     pub fn get(&self, name: &str) -> Option<Value> {
         self.values.get(name).cloned().or_else(|| {
             self.enclosing.as_ref().and_then(|env| env.borrow().get(name))
@@ -64,20 +372,184 @@ impl Environment {
     }
 }
 
+/// Number of calls and inclusive wall time of a function, collected when profiling.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CallStats {
+    pub calls: usize,
+    pub total_time: Duration,
+}
+
+/// Called with the function name and argument values when a user function is entered.
+pub type EnterHook = Rc<dyn Fn(&str, &[Value])>;
+/// Called with the function name and result when a user function returns.
+pub type ExitHook = Rc<dyn Fn(&str, &Value)>;
+
 pub struct Interpreter {
     environment: Rc<RefCell<Environment>>,
-    feather_manager: Rc<RefCell<FeatherManager>>,
+    feather_manager: ManagerRef,
+    // Shared with the interpreters of nested calls so every call lands in one report
+    profile: Option<Rc<RefCell<HashMap<String, CallStats>>>>,
+    on_enter: Option<EnterHook>,
+    on_exit: Option<ExitHook>,
+    // Files currently being included, innermost last, to reject include cycles
+    includes: Rc<RefCell<Vec<PathBuf>>>,
+    copy_on_write: bool,
+    builtins: Rc<HashMap<String, InterpreterBuiltin>>,
+    aliases: Aliases,
+    // The most function calls allowed on the thread's call stack, see `CALL_DEPTH`
+    max_depth: usize,
 }
 
+thread_local! {
+    // Function calls in progress on this thread and the lowest `max_depth` among them. Shared by
+    // every interpreter, feathers run in their own and recursion through a feather function
+    // still has to stay within the limit of the program that started it.
+    static CALL_DEPTH: Cell<(usize, usize)> = const { Cell::new((0, usize::MAX)) };
+}
+
+// Counts one call in `CALL_DEPTH` until dropped, also when the call fails
+struct CallDepthGuard {
+    enclosing_limit: usize,
+}
+
+impl CallDepthGuard {
+    fn enter(max_depth: usize) -> Result<Self, String> {
+        CALL_DEPTH.with(|depth| {
+            let (calls, enclosing_limit) = depth.get();
+            let limit = enclosing_limit.min(max_depth);
+            if calls >= limit {
+                return Err(format!("Maximum recursion depth ({}) exceeded", limit));
+            }
+            depth.set((calls + 1, limit));
+            Ok(CallDepthGuard { enclosing_limit })
+        })
+    }
+}
+
+impl Drop for CallDepthGuard {
+    fn drop(&mut self) {
+        CALL_DEPTH.with(|depth| depth.set((depth.get().0 - 1, self.enclosing_limit)));
+    }
+}
+
+/// Call depth at which `Interpreter::new` reports runaway recursion.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
 impl Interpreter {
     pub fn new(feather_manager: Rc<RefCell<FeatherManager>>) -> Self {
+        Self::with_max_depth(feather_manager, DEFAULT_MAX_DEPTH)
+    }
+
+    /// Like `new`, but a call nested more than `max_depth` function calls deep fails with
+    /// `Maximum recursion depth (<max_depth>) exceeded` instead of overflowing the stack.
+    pub fn with_max_depth(feather_manager: Rc<RefCell<FeatherManager>>, max_depth: usize) -> Self {
+        Self::with_manager_ref(ManagerRef::Owned(feather_manager), max_depth)
+    }
+
+    /// Interpreter for a feather imported by `feather_manager`, see `ManagerRef`.
+    pub(crate) fn for_feather(feather_manager: &Rc<RefCell<FeatherManager>>) -> Self {
+        Self::with_manager_ref(ManagerRef::Feather(Rc::downgrade(feather_manager)), DEFAULT_MAX_DEPTH)
+    }
+
+    fn with_manager_ref(feather_manager: ManagerRef, max_depth: usize) -> Self {
         Interpreter {
             environment: Rc::new(RefCell::new(Environment::new())),
             feather_manager,
+            profile: None,
+            on_enter: None,
+            on_exit: None,
+            includes: Default::default(),
+            copy_on_write: false,
+            builtins: Default::default(),
+            aliases: Default::default(),
+            max_depth,
         }
     }
 
-    pub fn interpret_program(&self, expressions: &[Expr]) -> Result<Value, String> {
+    /// Makes `builtin` callable as `RUST[std_func::<name>](...)`, ahead of the built-in std
+    /// functions of the same name. Unlike those it receives the interpreter, so it can call
+    /// function values with `call_value`. Feathers run in their own interpreters and don't see it.
+    pub fn register_builtin(&mut self, name: &str, builtin: impl Fn(&Interpreter, Vec<Value>) -> Result<Value, String> + 'static) {
+        Rc::make_mut(&mut self.builtins).insert(name.to_string(), Rc::new(builtin));
+    }
+
+    /// Gives arrays and maps value semantics: binding one to a parameter or variable shares it
+    /// only until either side mutates it. By default collections are shared by reference.
+    pub fn set_copy_on_write(&mut self, copy_on_write: bool) {
+        self.copy_on_write = copy_on_write;
+    }
+
+    fn bind(&self, value: Value) -> Value {
+        if self.copy_on_write { value.copy_on_write() } else { value }
+    }
+
+    /// Registers a callback fired on entry to every user function, e.g. for a debugger.
+    pub fn on_function_enter(&mut self, hook: impl Fn(&str, &[Value]) + 'static) {
+        self.on_enter = Some(Rc::new(hook));
+    }
+
+    /// Registers a callback fired when a user function returns. It is not fired when the call fails.
+    pub fn on_function_exit(&mut self, hook: impl Fn(&str, &Value) + 'static) {
+        self.on_exit = Some(Rc::new(hook));
+    }
+
+    /// Starts counting calls to user and feather functions.
+    pub fn enable_profiling(&mut self) {
+        self.profile.get_or_insert_with(Default::default);
+    }
+
+    /// Collected call statistics, most called first, or `None` if profiling is disabled.
+    pub fn profile(&self) -> Option<Vec<(String, CallStats)>> {
+        self.profile.as_ref().map(|profile| {
+            let mut entries: Vec<(String, CallStats)> = profile.borrow()
+                .iter()
+                .map(|(name, stats)| (name.clone(), *stats))
+                .collect();
+            entries.sort_by(|a, b| b.1.calls.cmp(&a.1.calls).then_with(|| a.0.cmp(&b.0)));
+            entries
+        })
+    }
+
+    /// Human readable profile, one function per line, most called first.
+    pub fn profile_report(&self) -> Option<String> {
+        self.profile().map(|entries| {
+            let rows: Vec<Vec<String>> = entries.iter()
+                .map(|(name, stats)| vec![stats.calls.to_string(), diagnostics::millis(stats.total_time), name.clone()])
+                .collect();
+            diagnostics::table(&[("calls", Align::Right), ("total ms", Align::Right), ("function", Align::Left)], &rows)
+        })
+    }
+
+    /// The profile without timings, which vary between runs, so it can be compared across runs.
+    pub fn profile_calls_report(&self) -> Option<String> {
+        self.profile().map(|entries| {
+            let rows: Vec<Vec<String>> = entries.iter()
+                .map(|(name, stats)| vec![stats.calls.to_string(), name.clone()])
+                .collect();
+            diagnostics::table(&[("calls", Align::Right), ("function", Align::Left)], &rows)
+        })
+    }
+
+    fn profiled<T>(&self, name: &str, call: impl FnOnce() -> T) -> T {
+        let Some(profile) = &self.profile else {
+            return call();
+        };
+        let start = Instant::now();
+        let result = call();
+        let mut profile = profile.borrow_mut();
+        let stats = profile.entry(name.to_string()).or_default();
+        stats.calls += 1;
+        stats.total_time += start.elapsed();
+        result
+    }
+
+    pub fn interpret_program(&self, expressions: &[Expr]) -> Result<Value, PelinError> {
+        self.run_program(expressions).map_err(program_error)
+    }
+
+    // `interpret_program` for programs run inside another one, such as includes and feathers,
+    // whose errors keep unwinding as strings
+    pub(crate) fn run_program(&self, expressions: &[Expr]) -> Result<Value, String> {
         let mut result = Value::Nun;
         for expr in expressions {
             match self.interpret(expr)? {
@@ -88,12 +560,29 @@ impl Interpreter {
         Ok(result)
     }
 
+    /// Runs the top-level expressions, then calls `main()` if the program defines it.
+    /// The result of `main` becomes the program result.
+    pub fn interpret_program_with_main(&self, expressions: &[Expr]) -> Result<Value, PelinError> {
+        let result = self.interpret_program(expressions)?;
+        match self.get_variable("main") {
+            Some(main @ Value::Function(_)) => {
+                trace!("Calling entry function main");
+                self.call_value(&main, Vec::new()).map_err(program_error)
+            },
+            _ => Ok(result),
+        }
+    }
+
     pub fn interpret(&self, expr: &Expr) -> Result<InterpretResult, String> {
         match expr {
             Expr::Number(n) => {
                 trace!("Interpreting number: {}", n);
                 Ok(InterpretResult::Value(Value::Number(*n)))
             },
+            Expr::Integer(n) => {
+                trace!("Interpreting integer: {}", n);
+                Ok(InterpretResult::Value(Value::Integer(*n)))
+            },
             Expr::String(s) => {
                 trace!("Interpreting string: {}", s);
                 Ok(InterpretResult::Value(Value::String(s.clone())))
@@ -112,6 +601,140 @@ impl Interpreter {
                     .map(InterpretResult::Value)
                     .ok_or_else(|| format!("Undefined variable '{}'.", name))
             },
+            Expr::Array(elements) => {
+                trace!("Interpreting array with {} elements", elements.len());
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    match self.interpret(element)? {
+                        InterpretResult::Value(v) => values.push(v),
+                        InterpretResult::Return(_) => return Err("Unexpected return".to_string()),
+                    }
+                }
+                Ok(InterpretResult::Value(Value::Array(Shared::new(values))))
+            },
+            Expr::Map(entries) => {
+                trace!("Interpreting map with {} entries", entries.len());
+                let mut map = BTreeMap::new();
+                for (key, value) in entries {
+                    match self.interpret(value)? {
+                        InterpretResult::Value(v) => { map.insert(key.clone(), v); },
+                        InterpretResult::Return(_) => return Err("Unexpected return".to_string()),
+                    }
+                }
+                Ok(InterpretResult::Value(Value::Map(Shared::new(map))))
+            },
+            Expr::Unary { operator, operand } => {
+                trace!("Interpreting unary {:?}", operator);
+                let value = match self.interpret(operand)? {
+                    InterpretResult::Value(v) => v,
+                    InterpretResult::Return(_) => return Err("Unexpected return".to_string()),
+                };
+                let result = match (operator, value) {
+                    (UnaryOperator::Negate, Value::Number(n)) => Value::Number(-n),
+                    (UnaryOperator::Negate, Value::Integer(n)) => {
+                        Value::Integer(n.checked_neg().ok_or_else(|| format!("Cannot negate {}, the result overflows", n))?)
+                    },
+                    (UnaryOperator::Not, Value::Boolean(b)) => Value::Boolean(!b),
+                    (UnaryOperator::Negate, other) => return Err(format!("'-' expects a number, got {}", other.type_name())),
+                    (UnaryOperator::Not, other) => return Err(format!("'!' expects a boolean, got {}", other.type_name())),
+                };
+                Ok(InterpretResult::Value(result))
+            },
+            Expr::Binary { operator, left, right } => {
+                trace!("Interpreting binary {:?}", operator);
+                let left = match self.interpret(left)? {
+                    InterpretResult::Value(v) => v,
+                    InterpretResult::Return(_) => return Err("Unexpected return".to_string()),
+                };
+                let right = match self.interpret(right)? {
+                    InterpretResult::Value(v) => v,
+                    InterpretResult::Return(_) => return Err("Unexpected return".to_string()),
+                };
+                binary_operation(*operator, &left, &right).map(InterpretResult::Value)
+            },
+            Expr::Logical { operator, left, right } => {
+                trace!("Interpreting logical {:?}", operator);
+                let operand = |expr: &Expr| match self.interpret(expr)? {
+                    InterpretResult::Value(Value::Boolean(b)) => Ok(b),
+                    InterpretResult::Value(other) => Err(format!("'{}' expects two booleans, got {}", operator, other.type_name())),
+                    InterpretResult::Return(_) => Err("Unexpected return".to_string()),
+                };
+                // The right side only runs when the left one doesn't decide the result
+                let result = match (operator, operand(left)?) {
+                    (LogicalOperator::And, false) => false,
+                    (LogicalOperator::Or, true) => true,
+                    _ => operand(right)?,
+                };
+                Ok(InterpretResult::Value(Value::Boolean(result)))
+            },
+            Expr::If { condition, then_branch, else_branch, line, column } => {
+                trace!("Interpreting if");
+                let condition = match self.interpret(condition)? {
+                    InterpretResult::Value(v) => v,
+                    InterpretResult::Return(_) => return Err("Unexpected return".to_string()),
+                };
+                // Only the taken branch runs, it evaluates to its last expression or `nun`
+                let branch = if condition_at(&condition, *line, *column)? { Some(then_branch) } else { else_branch.as_ref() };
+                let mut last_value = Value::Nun;
+                for expr in branch.into_iter().flatten() {
+                    match self.interpret(expr)? {
+                        InterpretResult::Return(value) => return Ok(InterpretResult::Return(value)),
+                        InterpretResult::Value(value) => last_value = value,
+                    }
+                }
+                Ok(InterpretResult::Value(last_value))
+            },
+            Expr::While { condition, body, line, column } => {
+                trace!("Interpreting while");
+                loop {
+                    let condition = match self.interpret(condition)? {
+                        InterpretResult::Value(v) => v,
+                        InterpretResult::Return(_) => return Err("Unexpected return".to_string()),
+                    };
+                    if !condition_at(&condition, *line, *column)? {
+                        return Ok(InterpretResult::Value(Value::Nun));
+                    }
+                    for expr in body {
+                        if let InterpretResult::Return(value) = self.interpret(expr)? {
+                            return Ok(InterpretResult::Return(value));
+                        }
+                    }
+                }
+            },
+            Expr::Block(body) => {
+                trace!("Interpreting block");
+                self.interpret_block(Environment::new(), body)
+            },
+            Expr::ForEach { variable, iterable, body } => {
+                trace!("Interpreting for {}", variable);
+                let elements = match self.interpret(iterable)? {
+                    // A snapshot, so the body may change the array without affecting the loop
+                    InterpretResult::Value(Value::Array(elements)) => elements.borrow().clone(),
+                    InterpretResult::Value(other) => return Err(format!("'for' expects an array to iterate over, got {}", other.type_name())),
+                    InterpretResult::Return(_) => return Err("Unexpected return".to_string()),
+                };
+                for element in elements {
+                    // Each element gets its own scope, so `let` in the body is local to one iteration
+                    let mut scope = Environment::new();
+                    scope.define(variable.clone(), self.bind(element));
+                    if let InterpretResult::Return(value) = self.interpret_block(scope, body)? {
+                        return Ok(InterpretResult::Return(value));
+                    }
+                }
+                Ok(InterpretResult::Value(Value::Nun))
+            },
+            Expr::Index { target, index } => {
+                trace!("Interpreting index");
+                let target = match self.interpret(target)? {
+                    InterpretResult::Value(v) => v,
+                    InterpretResult::Return(_) => return Err("Unexpected return".to_string()),
+                };
+                let index = match self.interpret(index)? {
+                    InterpretResult::Value(v) => v,
+                    InterpretResult::Return(_) => return Err("Unexpected return".to_string()),
+                };
+                index_value(&target, &index).map(InterpretResult::Value)
+            },
             Expr::Return(value) => {
                 trace!("Interpreting return");
                 let value = self.interpret(value)?;
@@ -124,24 +747,43 @@ impl Interpreter {
                 let value = self.interpret(value)?;
                 match value {
                     InterpretResult::Value(v) => {
-                        self.environment.borrow_mut().assign(&name, v.clone())?;
+                        self.environment.borrow_mut().assign(name, self.bind(v.clone()))?;
+                        Ok(InterpretResult::Value(v))
+                    },
+                    InterpretResult::Return(_) => Err("Cannot assign a return value".to_string()),
+                }
+            },
+            Expr::Let { name, value } => {
+                let value = self.interpret(value)?;
+                match value {
+                    InterpretResult::Value(v) => {
+                        self.environment.borrow_mut().define(name.clone(), self.bind(v.clone()));
                         Ok(InterpretResult::Value(v))
                     },
                     InterpretResult::Return(_) => Err("Cannot assign a return value".to_string()),
                 }
             },
-            Expr::FunctionDefinition { return_type: _, name, parameters, body } => {
+            Expr::FunctionDefinition { return_type, name, parameters, body } => {
                 let function = Function {
+                    name: name.clone(),
                     params: parameters.clone(),
+                    return_type: return_type.clone(),
                     body: body.iter().map(|expr| Rc::new((**expr).clone())).collect(),
                     closure: Rc::clone(&self.environment),
+                    feather_manager: self.feather_manager.clone(),
+                    aliases: Rc::clone(&self.aliases),
                 };
                 self.environment.borrow_mut().define(name.clone(), Value::Function(Rc::new(function)));
                 Ok(InterpretResult::Value(Value::Nun))
             },
-            Expr::Import(name) => {
+            Expr::Import { name, alias } => {
                 trace!("Interpreting import: {}", name);
-                self.feather_manager.borrow_mut().import(name)?;
+                self.import(name, alias.as_deref())?;
+                Ok(InterpretResult::Value(Value::Nun))
+            },
+            Expr::Include(path) => {
+                trace!("Interpreting include: {}", path);
+                self.include(path)?;
                 Ok(InterpretResult::Value(Value::Nun))
             },
             Expr::RustFunctionCall { path, arguments } => {
@@ -166,6 +808,10 @@ impl Interpreter {
                 }
                 self.call_function(callee_value, arg_values)
             },
+            Expr::FeatherFunction { feather, function } => {
+                trace!("Interpreting feather function reference: {}.{}", feather, function);
+                self.feather_function_reference(feather, function).map(InterpretResult::Value)
+            },
             Expr::FeatherFunctionCall { feather, function, arguments } => {
                 let mut arg_values = Vec::new();
                 for arg in arguments {
@@ -174,58 +820,326 @@ impl Interpreter {
                         InterpretResult::Return(_) => return Err("Unexpected return".to_string()),
                     }
                 }
-                let result = self.feather_manager.borrow().call_function(feather, function, arg_values)?;
+                let result = self.call_feather_function(&self.resolve_alias(feather), function, arg_values)?;
                 Ok(InterpretResult::Value(result))
             },
         }
     }
 
+    /// Runs the top level of another file in this interpreter's scope, so its definitions become
+    /// global. Paths are relative to the including file, or to the project root in the main program.
+    fn include(&self, path: &str) -> Result<(), String> {
+        let base = match self.includes.borrow().last() {
+            Some(including) => including.parent().map(PathBuf::from).unwrap_or_default(),
+            None => self.feather_manager.get()?.borrow().project_root.clone(),
+        };
+        let full_path = base.join(path);
+        let canonical = full_path.canonicalize()
+            .map_err(|e| format!("Cannot include '{}': {}", full_path.display(), e))?;
+        if self.includes.borrow().contains(&canonical) {
+            return Err(format!("Recursive include of '{}'", full_path.display()));
+        }
+
+        let in_file = |err: String| format!("In included file '{}': {}", full_path.display(), err);
+        let source = fs::read_to_string(&canonical).map_err(|e| in_file(e.to_string()))?;
+        let tokens = Lexer::new(&source).tokenize().map_err(|e| in_file(e.to_string()))?;
+        let expressions = Parser::new(tokens).parse().map_err(|e| in_file(e.to_string()))?;
+
+        self.includes.borrow_mut().push(canonical);
+        let result = self.run_program(&expressions);
+        self.includes.borrow_mut().pop();
+        result.map(|_| ()).map_err(in_file)
+    }
+
+    // Imports a feather, and with `alias` makes it available under that name in this program or
+    // feather only. The alias may not be the name of another imported feather or already stand for one.
+    fn import(&self, name: &str, alias: Option<&str>) -> Result<(), String> {
+        let manager = self.feather_manager.get()?;
+        let feather_name = name.trim_start_matches('.');
+        if let Some(target) = self.aliases.borrow().get(feather_name).filter(|target| *target != feather_name) {
+            return Err(format!("Cannot import feather '{}': '{}' is already an alias for feather '{}'", name, feather_name, target));
+        }
+        if let Some(alias) = alias {
+            if alias != feather_name && manager.borrow().feathers.contains_key(alias) {
+                return Err(format!("Cannot import feather '{}' as '{}': a feather named '{}' is already imported", name, alias, alias));
+            }
+            if let Some(target) = self.aliases.borrow().get(alias).filter(|target| *target != feather_name) {
+                return Err(format!("Cannot import feather '{}' as '{}': '{}' is already an alias for feather '{}'", name, alias, alias, target));
+            }
+        }
+        FeatherManager::import(&manager, name)?;
+        if let Some(alias) = alias {
+            self.aliases.borrow_mut().insert(alias.to_string(), feather_name.to_string());
+        }
+        Ok(())
+    }
+
+    // The feather `name` stands for here, itself unless it is an alias
+    fn resolve_alias(&self, name: &str) -> String {
+        self.aliases.borrow().get(name).cloned().unwrap_or_else(|| name.to_string())
+    }
+
+    // `feather.function` without a call. Aliases are resolved here, the value may be called where
+    // they are not known. Kept out of `interpret` so its stack frame stays small.
+    fn feather_function_reference(&self, feather: &str, function: &str) -> Result<Value, String> {
+        let feather = self.resolve_alias(feather);
+        // Fail at the reference rather than at a later call if the function does not exist
+        if feather != STD_NAMESPACE {
+            self.feather_manager.get()?.borrow().get_function(&feather, function)?;
+        }
+        Ok(Value::FeatherFunction(feather, function.to_string()))
+    }
+
     fn call_rust_function(&self, path: &[String], arguments: Vec<Value>) -> Result<InterpretResult, String> {
         if path.len() < 2 {
-            return Err("Invalid Rust function path".to_string());
+            return Err(feather::invalid_rust_path(&path.join("::")));
         }
         let feather_name = &path[0];
         let function_name = path[1..].join("::");
 
-        self.feather_manager.borrow().call_function(feather_name, &function_name, arguments)
+        if feather_name == STD_NAMESPACE {
+            if let Some(builtin) = self.builtins.get(&function_name) {
+                return builtin(self, arguments).map(InterpretResult::Value);
+            }
+            if let Some(builtin) = builtins::lookup(&function_name) {
+                return builtin(self, arguments).map(InterpretResult::Value);
+            }
+        }
+
+        self.feather_manager.get()?.borrow().call_rust_function(&path.join("::"), arguments)
             .map(InterpretResult::Value)
     }
 
+    fn call_feather_function(&self, feather_name: &str, function_name: &str, arguments: Vec<Value>) -> Result<Value, String> {
+        if feather_name == STD_NAMESPACE {
+            return self.feather_manager.get()?.borrow().call_function(feather_name, function_name, arguments);
+        }
+        // Release the manager before calling, the feather may call back into this interpreter
+        let function = self.feather_manager.get()?.borrow().get_function(feather_name, function_name)?;
+        self.profiled(&format!("{}.{}", feather_name, function_name), || function(arguments))
+    }
+
+    pub fn get_variable(&self, name: &str) -> Option<Value> {
+        self.environment.borrow().get(name)
+    }
+
+    /// Defines a variable in the global scope before the program runs, e.g. the program's `args`.
+    pub fn define_variable(&self, name: &str, value: Value) {
+        self.environment.borrow_mut().define(name.to_string(), value);
+    }
+
+    // Runs `body` in `scope`, nested in the current environment. Evaluates to the last value,
+    // or the value of a `return`. Kept out of `interpret` so its stack frame stays small.
+    fn interpret_block(&self, mut scope: Environment, body: &[Expr]) -> Result<InterpretResult, String> {
+        scope.enclosing = Some(Rc::clone(&self.environment));
+        let block = self.in_scope(scope);
+        let mut last_value = Value::Nun;
+        for expr in body {
+            match block.interpret(expr)? {
+                InterpretResult::Return(value) => return Ok(InterpretResult::Return(value)),
+                InterpretResult::Value(value) => last_value = value,
+            }
+        }
+        Ok(InterpretResult::Value(last_value))
+    }
+
+    // An interpreter with the same settings that evaluates in `environment`
+    fn in_scope(&self, environment: Environment) -> Interpreter {
+        Interpreter {
+            environment: Rc::new(RefCell::new(environment)),
+            feather_manager: self.feather_manager.clone(),
+            profile: self.profile.clone(),
+            on_enter: self.on_enter.clone(),
+            on_exit: self.on_exit.clone(),
+            includes: Rc::clone(&self.includes),
+            copy_on_write: self.copy_on_write,
+            builtins: Rc::clone(&self.builtins),
+            aliases: Rc::clone(&self.aliases),
+            max_depth: self.max_depth,
+        }
+    }
+
+    /// Calls a function value with already evaluated arguments, e.g. a predicate passed to a builtin.
+    pub fn call_value(&self, callee: &Value, arguments: Vec<Value>) -> Result<Value, String> {
+        match self.call_function(InterpretResult::Value(callee.clone()), arguments)? {
+            InterpretResult::Value(v) | InterpretResult::Return(v) => Ok(v),
+        }
+    }
+
     fn call_function(&self, callee: InterpretResult, arguments: Vec<Value>) -> Result<InterpretResult, String> {
         match callee {
             InterpretResult::Value(Value::Function(function)) => {
-                let new_env = Rc::new(RefCell::new(Environment::new()));
-                new_env.borrow_mut().enclosing = Some(Rc::clone(&function.closure));
-
+                let _depth = CallDepthGuard::enter(self.max_depth)?;
                 if function.params.len() != arguments.len() {
                     return Err(format!("Expected {} arguments but got {}.", function.params.len(), arguments.len()));
                 }
-
-                for ((_, param_name), arg_value) in function.params.iter().zip(arguments.iter()) {
-                    new_env.borrow_mut().define(param_name.clone(), arg_value.clone());
+                for ((param_type, param_name), argument) in function.params.iter().zip(&arguments) {
+                    if !has_type(argument, param_type) {
+                        return Err(format!("Argument '{}' expected {}, got {}", param_name, param_type, argument.type_name()));
+                    }
                 }
 
+                let mut env = Environment::new();
+                env.enclosing = Some(Rc::clone(&function.closure));
+                env.define_many(function.params.iter()
+                    .zip(arguments.iter())
+                    .map(|((_, param_name), arg_value)| (param_name.clone(), self.bind(arg_value.clone())))
+                    .collect());
                 let new_interpreter = Interpreter {
-                    environment: new_env,
-                    feather_manager: Rc::clone(&self.feather_manager),
+                    feather_manager: function.feather_manager.clone(),
+                    aliases: Rc::clone(&function.aliases),
+                    ..self.in_scope(env)
                 };
 
-                let mut last_value = Value::Nun;
-                for expr in &function.body {
+                if let Some(on_enter) = &self.on_enter {
+                    on_enter(&function.name, &arguments);
+                }
 
-                    match new_interpreter.interpret(expr)? {
-                        InterpretResult::Return(value) => return Ok(InterpretResult::Value(value)),
-                        InterpretResult::Value(value) => last_value = value,
+                // A function evaluates to its first `return`, otherwise to the value of its
+                // last expression, and to `nun` when the body is empty.
+                let result = self.profiled(&function.name, || -> Result<Value, String> {
+                    let mut last_value = Value::Nun;
+                    for expr in &function.body {
+                        match new_interpreter.interpret(expr)? {
+                            InterpretResult::Return(value) => return Ok(value),
+                            InterpretResult::Value(value) => last_value = value,
+                        }
                     }
+                    Ok(last_value)
+                })?;
+                // A `nun` function often ends in an assignment or call whose value is incidental
+                if function.return_type != Type::Nun && !has_type(&result, &function.return_type) {
+                    return Err(format!("Function '{}' expected to return {}, got {}", function.name, function.return_type, result.type_name()));
                 }
 
-                Ok(InterpretResult::Value(last_value))
+                if let Some(on_exit) = &self.on_exit {
+                    on_exit(&function.name, &result);
+                }
+                Ok(InterpretResult::Value(result))
             },
             InterpretResult::Value(Value::FeatherFunction(feather_name, function_name)) => {
-                self.feather_manager.borrow().call_function(&feather_name, &function_name, arguments)
+                self.call_feather_function(&feather_name, &function_name, arguments)
                     .map(InterpretResult::Value)
             },
             _ => Err("Can only call functions.".to_string()),
         }
     }
-}
\ No newline at end of file
+}
+
+// `Value::as_condition` for the condition of an `if` or `while` that starts at `line` and `column`
+fn condition_at(condition: &Value, line: usize, column: usize) -> Result<bool, String> {
+    condition.as_condition().map_err(|message| format!("[line {}, column {}] {}", line, column, message))
+}
+
+// An error that ended a program, which is an exit rather than a failure if it came from `exit`
+fn program_error(message: String) -> PelinError {
+    match std_functions::take_exit_status() {
+        Some(code) => PelinError::Exit { code, message },
+        None => PelinError::Runtime { message },
+    }
+}
+
+// Whether a value matches a declared parameter or return type. `num` covers integers as well as
+// floats. Custom types such as `any` or `array` are not checked and accept every value.
+fn has_type(value: &Value, expected: &Type) -> bool {
+    matches!(
+        (expected, value),
+        (Type::Num, Value::Number(_) | Value::Integer(_))
+            | (Type::Str, Value::String(_))
+            | (Type::Bool, Value::Boolean(_))
+            | (Type::Nun, Value::Nun)
+            | (Type::Custom(_), _)
+    )
+}
+
+// Two integers stay integral except for `/`, which like `divide` always yields a float. Mixing
+// an integer with a float yields a float. `==` and `!=` compare any two values, numbers by value
+// so that `1i == 1` agrees with `1i <= 1`.
+fn binary_operation(operator: BinaryOperator, left: &Value, right: &Value) -> Result<Value, String> {
+    let type_error = |expected: &str| {
+        format!("'{}' expects {}, got {} and {}", operator, expected, left.type_name(), right.type_name())
+    };
+    match operator {
+        BinaryOperator::Equal => Ok(Value::Boolean(left.equals(right))),
+        BinaryOperator::NotEqual => Ok(Value::Boolean(!left.equals(right))),
+        BinaryOperator::Less | BinaryOperator::LessEqual | BinaryOperator::Greater | BinaryOperator::GreaterEqual => {
+            let ordering = match (left, right) {
+                (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+                (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                // NaN is unordered, every comparison with it is false
+                _ => match (number_operand(left), number_operand(right)) {
+                    (Some(a), Some(b)) => a.partial_cmp(&b),
+                    _ => return Err(type_error("two numbers or two strings")),
+                },
+            };
+            let result = ordering.is_some_and(|ordering| match operator {
+                BinaryOperator::Less => ordering.is_lt(),
+                BinaryOperator::LessEqual => ordering.is_le(),
+                BinaryOperator::Greater => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            });
+            Ok(Value::Boolean(result))
+        },
+        BinaryOperator::Add if matches!((left, right), (Value::String(_), Value::String(_))) => {
+            Ok(Value::String(format!("{}{}", left, right)))
+        },
+        _ => {
+            let is_zero = number_operand(left).is_some() && number_operand(right) == Some(0.0);
+            match operator {
+                BinaryOperator::Divide if is_zero => return Err(format!("Cannot divide {} by zero", left)),
+                BinaryOperator::Remainder if is_zero => return Err(format!("Cannot take the remainder of {} divided by zero", left)),
+                _ => {},
+            }
+            if let (Value::Integer(a), Value::Integer(b)) = (left, right) {
+                let result = match operator {
+                    BinaryOperator::Add => a.checked_add(*b),
+                    BinaryOperator::Subtract => a.checked_sub(*b),
+                    BinaryOperator::Multiply => a.checked_mul(*b),
+                    BinaryOperator::Divide => return Ok(Value::Number(*a as f64 / *b as f64)),
+                    _ => a.checked_rem_euclid(*b),
+                };
+                return result.map(Value::Integer)
+                    .ok_or_else(|| format!("Cannot compute {} {} {}, the result overflows", left, operator, right));
+            }
+            let (a, b) = match (number_operand(left), number_operand(right)) {
+                (Some(a), Some(b)) => (a, b),
+                _ if operator == BinaryOperator::Add => return Err(type_error("two numbers or two strings")),
+                _ => return Err(type_error("two numbers")),
+            };
+            Ok(Value::Number(match operator {
+                BinaryOperator::Add => a + b,
+                BinaryOperator::Subtract => a - b,
+                BinaryOperator::Multiply => a * b,
+                BinaryOperator::Divide => a / b,
+                // Euclidean, never negative: `-7 % 3` is 2
+                _ => a.rem_euclid(b),
+            }))
+        },
+    }
+}
+
+// Arrays give the element, strings the character at a zero-based position
+fn index_value(target: &Value, index: &Value) -> Result<Value, String> {
+    let position = match index {
+        Value::Integer(n) if *n >= 0 => *n as usize,
+        Value::Number(n) if *n >= 0.0 && n.fract() == 0.0 => *n as usize,
+        other => return Err(format!("Index must be a non-negative whole number, got {} ({})", other.type_name(), ConditionValue(other))),
+    };
+    let (element, length, unit) = match target {
+        Value::Array(elements) => {
+            let elements = elements.borrow();
+            (elements.get(position).cloned(), elements.len(), "elements")
+        },
+        Value::String(s) => (s.chars().nth(position).map(|c| Value::String(c.to_string())), s.chars().count(), "characters"),
+        other => return Err(format!("Cannot index a {}, only arrays and strings", other.type_name())),
+    };
+    element.ok_or_else(|| format!("Index {} is out of bounds, the {} has {} {}", position, target.type_name(), length, unit))
+}
+
+fn number_operand(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(n) => Some(*n as f64),
+        Value::Number(n) => Some(*n),
+        _ => None,
+    }
+}
@@ -1,5 +1,10 @@
+use crate::error::PelinError;
 use crate::lexer::TokenType::Identifier;
 
+fn lex_error(line: usize, column: usize, message: String) -> PelinError {
+    PelinError::Lex { message, line, column }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Token {
     pub kind: TokenType,
@@ -11,6 +16,7 @@ pub struct Token {
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenType {
     Number(f64),
+    Integer(i64),
     String(String),
     Boolean(bool),
     Nun,
@@ -18,6 +24,13 @@ pub enum TokenType {
     // Function related
     Fn,
     Return,
+    Let,
+    // Control flow
+    If,
+    Else,
+    While,
+    For,
+    In,
     // Delimiters
     LeftParen,
     RightParen,
@@ -25,13 +38,34 @@ pub enum TokenType {
     RightBrace,
     Comma,
     Equal,
+    Minus,
+    Bang,
+    // Operators
+    Plus,
+    Star,
+    Slash,
+    Percent,
+    EqualEqual,
+    BangEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    AndAnd,
+    OrOr,
+    Question,
+    Colon,
     // Feathers
     Imp,
+    As,
+    Inc,
     Dot,
     LeftBracket,
     RightBracket,
     RustKeyword,
     DoubleColon,
+    // Only produced when the lexer keeps comments
+    Comment(String),
     // End of input
     EOF,
 }
@@ -41,6 +75,8 @@ pub struct Lexer {
     position: usize,
     line: usize,
     column: usize,
+    keep_comments: bool,
+    int_literals: bool,
 }
 
 pub fn tokens_to_token_types(tokens: Vec<Token>) -> Vec<TokenType> {
@@ -54,10 +90,23 @@ impl Lexer {
             position: 0,
             line: 1,
             column: 1,
+            keep_comments: false,
+            int_literals: false,
         }
     }
 
-    pub fn tokenize(&mut self) -> Result<Vec<Token>, String> {
+    /// Lexes bare whole literals like `5` as integers instead of floats. Literals with a decimal
+    /// point or a suffix are unaffected, `5.0` and `5f` stay floats.
+    pub fn set_int_literals(&mut self, int_literals: bool) {
+        self.int_literals = int_literals;
+    }
+
+    /// Emits `//` comments as `Comment` tokens instead of skipping them, for doc and formatting tools.
+    pub fn set_keep_comments(&mut self, keep_comments: bool) {
+        self.keep_comments = keep_comments;
+    }
+
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, PelinError> {
         let mut tokens = Vec::new();
 
         loop {
@@ -77,7 +126,7 @@ impl Lexer {
         Ok(tokens)
     }
 
-    fn next_token(&mut self) -> Result<Token, String> {
+    fn next_token(&mut self) -> Result<Token, PelinError> {
         self.skip_whitespace();
 
         if self.is_at_end() {
@@ -103,13 +152,33 @@ impl Lexer {
                     self.advance();
                     Ok(Token { kind: TokenType::DoubleColon, lexeme: "::".to_string(), line: self.line, column: start_column })
                 } else {
-                    Err(format!("Unexpected character: ':' at line {}, column {}", self.line, self.column))
+                    Ok(Token { kind: TokenType::Colon, lexeme: ":".to_string(), line: self.line, column: start_column })
                 }
             },
-            '=' => { self.advance(); Ok(Token { kind: TokenType::Equal, lexeme: "=".to_string(), line: self.line, column: start_column }) },
+            '?' => { self.advance(); Ok(Token { kind: TokenType::Question, lexeme: "?".to_string(), line: self.line, column: start_column }) },
+            '&' | '|' => {
+                self.advance();
+                if self.peek() == Some(c) {
+                    self.advance();
+                    let kind = if c == '&' { TokenType::AndAnd } else { TokenType::OrOr };
+                    Ok(Token { kind, lexeme: format!("{}{}", c, c), line: self.line, column: start_column })
+                } else {
+                    Err(lex_error(self.line, start_column, format!("Unexpected character: '{}' at line {}, column {}", c, self.line, start_column)))
+                }
+            },
+            '/' if self.peek_next() == Some('/') => Ok(self.comment()),
+            '/' => { self.advance(); Ok(Token { kind: TokenType::Slash, lexeme: "/".to_string(), line: self.line, column: start_column }) },
+            '+' => { self.advance(); Ok(Token { kind: TokenType::Plus, lexeme: "+".to_string(), line: self.line, column: start_column }) },
+            '*' => { self.advance(); Ok(Token { kind: TokenType::Star, lexeme: "*".to_string(), line: self.line, column: start_column }) },
+            '%' => { self.advance(); Ok(Token { kind: TokenType::Percent, lexeme: "%".to_string(), line: self.line, column: start_column }) },
+            '-' => { self.advance(); Ok(Token { kind: TokenType::Minus, lexeme: "-".to_string(), line: self.line, column: start_column }) },
+            '=' => Ok(self.operator(TokenType::Equal, TokenType::EqualEqual)),
+            '!' => Ok(self.operator(TokenType::Bang, TokenType::BangEqual)),
+            '<' => Ok(self.operator(TokenType::Less, TokenType::LessEqual)),
+            '>' => Ok(self.operator(TokenType::Greater, TokenType::GreaterEqual)),
             '0'..='9' => self.number(),
             'a'..='z' | 'A'..='Z' | '_' => self.identifier_or_keyword(),
-            _ => Err(format!("Unexpected character: '{}' at line {}, column {}", c, self.line, self.column)),
+            _ => Err(lex_error(self.line, self.column, format!("Unexpected character: '{}' at line {}, column {}", c, self.line, self.column))),
         }?;
 
         Ok(token)
@@ -119,12 +188,50 @@ impl Lexer {
         while let Some(c) = self.peek() {
             if c.is_whitespace() {
                 self.advance();
+            } else if c == '/' && self.peek_next() == Some('/') && !self.keep_comments {
+                self.comment();
             } else {
                 break;
             }
         }
     }
 
+    // Consumes a `//` comment up to the end of the line, the text excludes the slashes
+    fn comment(&mut self) -> Token {
+        let start_column = self.column;
+        let line = self.line;
+        self.advance();
+        self.advance();
+
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+            text.push(self.advance());
+        }
+
+        Token {
+            kind: TokenType::Comment(text.clone()),
+            lexeme: format!("//{}", text),
+            line,
+            column: start_column,
+        }
+    }
+
+    // A one character operator, or its two character form when followed by `=`, e.g. `<` and `<=`
+    fn operator(&mut self, single: TokenType, with_equal: TokenType) -> Token {
+        let start_column = self.column;
+        let mut lexeme = self.advance().to_string();
+        let kind = if self.peek() == Some('=') {
+            lexeme.push(self.advance());
+            with_equal
+        } else {
+            single
+        };
+        Token { kind, lexeme, line: self.line, column: start_column }
+    }
+
     fn is_at_end(&self) -> bool {
         self.position >= self.input.len()
     }
@@ -151,68 +258,188 @@ impl Lexer {
         self.input.get(self.position + 1).copied()
     }
 
-    fn string(&mut self) -> Result<Token, String> {
+    // The position is the opening quote's, even when the string spans several lines
+    fn string(&mut self) -> Result<Token, PelinError> {
+        let (start_line, start_column) = (self.line, self.column);
         self.advance();
         let mut value = String::new();
-        let start_column = self.column;
+        // The lexeme keeps escapes as written in source
+        let mut lexeme = String::new();
 
         while let Some(c) = self.peek() {
             if c == '"' {
                 self.advance();
                 return Ok(Token {
-                    kind: TokenType::String(value.clone()),
-                    lexeme: value,
-                    line: self.line,
+                    kind: TokenType::String(value),
+                    lexeme,
+                    line: start_line,
                     column: start_column,
                 });
             }
-            value.push(self.advance());
+            if c == '\\' {
+                let (line, column) = (self.line, self.column);
+                lexeme.push(self.advance());
+                let escaped = match self.peek() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some('0') => '\0',
+                    Some('\\') => '\\',
+                    Some('"') => '"',
+                    Some(other) => return Err(lex_error(line, column, format!("Unknown escape sequence '\\{}' at line {}, column {}", other, line, column))),
+                    None => break,
+                };
+                lexeme.push(self.advance());
+                value.push(escaped);
+                continue;
+            }
+            let c = self.advance();
+            lexeme.push(c);
+            value.push(c);
         }
-        Err(format!("Unterminated string at line {}, column {}", self.line, self.column))
+        Err(lex_error(start_line, start_column, format!("Unterminated string at line {}, column {}", start_line, start_column)))
     }
 
-    fn number(&mut self) -> Result<Token, String> {
+    // Bare literals are floats unless `int_literals` is set. An `i` suffix or a `0x` prefix makes
+    // an integer, an `f` suffix makes the float explicit. Digits can be grouped with single
+    // underscores (`1_000`) and floats can have an exponent (`2.5e-3`).
+    fn number(&mut self) -> Result<Token, PelinError> {
         let mut value = String::new();
+        let mut lexeme = String::new();
         let mut has_decimal = false;
+        let mut has_exponent = false;
         let start_column = self.column;
 
+        if self.peek() == Some('0') && matches!(self.peek_next(), Some('x') | Some('X')) {
+            return self.hex_number();
+        }
+
+        self.digits(&mut value, &mut lexeme)?;
+        if self.peek() == Some('.') {
+            if !self.peek_next().is_some_and(|next| next.is_ascii_digit()) {
+                return Ok(Token {
+                    kind: Identifier(value),
+                    lexeme,
+                    line: self.line,
+                    column: start_column,
+                })
+            }
+            lexeme.push(self.advance());
+            value.push('.');
+            has_decimal = true;
+            self.digits(&mut value, &mut lexeme)?;
+            if self.peek() == Some('.') {
+                return Err(lex_error(self.line, self.column, format!("Invalid number format at line {}, column {}: multiple decimal points", self.line, self.column)));
+            }
+        }
+
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let signed = matches!(self.peek_next(), Some('+' | '-'));
+            let offset = if signed { 2 } else { 1 };
+            if self.input.get(self.position + offset).is_some_and(|c| c.is_ascii_digit()) {
+                for _ in 0..offset {
+                    let c = self.advance();
+                    lexeme.push(c);
+                    value.push(c);
+                }
+                has_exponent = true;
+                self.digits(&mut value, &mut lexeme)?;
+            }
+        }
+
+        let suffix = match self.peek() {
+            Some(c @ ('i' | 'f')) => {
+                lexeme.push(self.advance());
+                Some(c)
+            },
+            _ => None,
+        };
+        self.expect_number_end()?;
+
+        let integer = match suffix {
+            Some('i') if has_decimal => {
+                return Err(lex_error(self.line, start_column, format!("Invalid integer literal at line {}, column {}: integers cannot have a decimal point", self.line, start_column)));
+            },
+            Some('i') if has_exponent => {
+                return Err(lex_error(self.line, start_column, format!("Invalid integer literal at line {}, column {}: integers cannot have an exponent", self.line, start_column)));
+            },
+            Some(c) => c == 'i',
+            None => self.int_literals && !has_decimal && !has_exponent,
+        };
+        let kind = if integer {
+            value.parse::<i64>()
+                .map(TokenType::Integer)
+                .map_err(|_| lex_error(self.line, start_column, format!("Integer literal out of range at line {}, column {}", self.line, start_column)))?
+        } else {
+            value.parse::<f64>()
+                .map(TokenType::Number)
+                .map_err(|_| lex_error(self.line, self.column, format!("Invalid number format at line {}, column {}", self.line, self.column)))?
+        };
+
+        Ok(Token { kind, lexeme, line: self.line, column: start_column })
+    }
+
+    // Reads a run of digits into `value`, dropping the underscores that group them from the value
+    // but keeping them in the lexeme
+    fn digits(&mut self, value: &mut String, lexeme: &mut String) -> Result<(), PelinError> {
         while let Some(c) = self.peek() {
-            if c.is_digit(10) {
-                value.push(self.advance());
-            } else if c == '.' {
-                if has_decimal {
-                    return Err(format!("Invalid number format at line {}, column {}: multiple decimal points", self.line, self.column));
-                } else if self.peek_next().map_or(false, |next| next.is_digit(10)) {
-                    value.push(self.advance());
-                    has_decimal = true;
-                } else {
-                    break;
+            if c.is_ascii_digit() {
+                value.push(c);
+            } else if c == '_' {
+                let between_digits = lexeme.ends_with(|previous: char| previous.is_ascii_digit())
+                    && self.peek_next().is_some_and(|next| next.is_ascii_digit());
+                if !between_digits {
+                    return Err(lex_error(self.line, self.column, format!("Invalid number format at line {}, column {}: '_' must be between two digits", self.line, self.column)));
                 }
             } else {
                 break;
             }
+            lexeme.push(self.advance());
         }
+        Ok(())
+    }
 
-        if self.peek() == Some('.') && !has_decimal {
-            return Ok(Token {
-                kind: Identifier(value.clone()),
-                lexeme: value,
-                line: self.line,
-                column: start_column,
-            })
+    fn hex_number(&mut self) -> Result<Token, PelinError> {
+        let start_column = self.column;
+        let mut lexeme = String::new();
+        lexeme.push(self.advance());
+        lexeme.push(self.advance());
+
+        let mut digits = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_hexdigit() {
+                digits.push(self.advance());
+            } else {
+                break;
+            }
+        }
+        if self.peek() == Some('i') {
+            self.advance();
         }
+        self.expect_number_end()?;
+        lexeme.push_str(&digits);
 
-        value.parse::<f64>()
+        i64::from_str_radix(&digits, 16)
             .map(|n| Token {
-                kind: TokenType::Number(n),
-                lexeme: value.clone(),
+                kind: TokenType::Integer(n),
+                lexeme,
                 line: self.line,
                 column: start_column,
             })
-            .map_err(|_| format!("Invalid number format at line {}, column {}", self.line, self.column))
+            .map_err(|_| lex_error(self.line, start_column, format!("Invalid hex literal at line {}, column {}", self.line, start_column)))
+    }
+
+    // A literal must not run into a name or another literal, e.g. `5i.5` or `5abc`
+    fn expect_number_end(&self) -> Result<(), PelinError> {
+        match self.peek() {
+            Some(c) if c.is_alphanumeric() || c == '_' || (c == '.' && self.peek_next().is_some_and(|next| next.is_ascii_digit())) => {
+                Err(lex_error(self.line, self.column, format!("Invalid number format at line {}, column {}: unexpected '{}'", self.line, self.column, c)))
+            },
+            _ => Ok(()),
+        }
     }
 
-    fn identifier_or_keyword(&mut self) -> Result<Token, String> {
+    fn identifier_or_keyword(&mut self) -> Result<Token, PelinError> {
         let mut value = String::new();
         let start_column = self.column;
 
@@ -230,7 +457,15 @@ impl Lexer {
             "false" => TokenType::Boolean(false),
             "nun" => TokenType::Nun,
             "return" => TokenType::Return,
+            "let" => TokenType::Let,
+            "if" => TokenType::If,
+            "else" => TokenType::Else,
+            "while" => TokenType::While,
+            "for" => TokenType::For,
+            "in" => TokenType::In,
             "imp" => TokenType::Imp,
+            "as" => TokenType::As,
+            "inc" => TokenType::Inc,
             "RUST" => TokenType::RustKeyword,
             _ => Identifier(value.clone()),
         };
@@ -2,4 +2,34 @@ pub mod interpreter;
 pub mod lexer;
 pub mod parser;
 pub mod feather;
-pub mod std_functions;
\ No newline at end of file
+pub mod std_functions;
+pub mod builtins;
+pub mod typecheck;
+pub mod diagnostics;
+pub mod error;
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use error::PelinError;
+use feather::FeatherManager;
+use interpreter::{Interpreter, Value};
+use lexer::Lexer;
+use parser::Parser;
+
+/// Runs a program from source and returns the value of its last expression, or the value of a
+/// top-level `return`. Feathers are imported from `project_root`, the same way the `pelin`
+/// binary imports them from the current directory.
+pub fn run_source(source: &str, project_root: PathBuf) -> Result<Value, PelinError> {
+    let tokens = Lexer::new(source).tokenize()?;
+    let expressions = Parser::new(tokens).parse()?;
+    let feather_manager = Rc::new(RefCell::new(FeatherManager::new(project_root)));
+    Interpreter::new(feather_manager).interpret_program(&expressions)
+}
+
+/// Like [`run_source`], with the current directory as the project root.
+pub fn eval(source: &str) -> Result<Value, PelinError> {
+    let project_root = std::env::current_dir().map_err(|e| format!("Could not read the current directory: {}", e))?;
+    run_source(source, project_root)
+}
@@ -1,61 +1,489 @@
 use std::env;
 use std::fs;
 use std::process;
-use pelin::lexer::Lexer;
-use pelin::parser::Parser;
-use pelin::interpreter::Interpreter;
+use pelin::lexer::{Lexer, TokenType};
+use pelin::parser::{Expr, Parser};
+use pelin::interpreter::{Interpreter, Shared, Value};
 use pelin::feather::FeatherManager;
+use pelin::std_functions;
+use pelin::typecheck;
+use pelin::diagnostics::{self, Align};
+use pelin::error::PelinError;
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::path::PathBuf;
+use std::io::{self, IsTerminal, Write};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Program run when pelin is started without arguments in a directory containing it
+const DEFAULT_PROGRAM: &str = "main.pl";
+/// REPL history file in the home directory, `PELIN_HISTORY` overrides the full path
+const HISTORY_FILE: &str = ".pelin_history";
+
+#[derive(Default)]
+struct RunOptions {
+    call_main: bool,
+    debug_ast: bool,
+    profile: bool,
+    profile_calls: bool,
+    print_result: bool,
+    int_literals: bool,
+    buffered_output: bool,
+    copy_on_write: bool,
+    typecheck: bool,
+    watch: bool,
+    list_functions: bool,
+    /// Feathers imported before the file runs, as if by `imp` lines at its top
+    imports: Vec<String>,
+    /// Arguments after the file name, given to the program as the array `args`
+    program_args: Vec<String>,
+}
+
+/// Stack of the thread programs run on. Each nested Pelikan call takes a few dozen kilobytes of
+/// native stack in debug builds, this leaves room for `DEFAULT_MAX_DEPTH` calls.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let runner = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .expect("could not start the interpreter thread");
+    if runner.join().is_err() {
+        process::exit(101);
+    }
+}
+
+fn run() {
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    match args.get(1).map(String::as_str) {
+    match args.first().map(String::as_str) {
         Some("--version") => {
             println!("pelin version {}", VERSION);
-            return;
         }
-        Some(filename) => {
-            if let Err(err) = run_file(filename) {
+        Some("--repl") => {
+            if let Err(err) = run_repl() {
                 eprintln!("Error: {}", err);
                 process::exit(1);
             }
         }
+        Some("--validate-feathers") => {
+            if let Err(err) = validate_feathers() {
+                eprintln!("Error: {}", err);
+                process::exit(1);
+            }
+        }
+        Some(_) => {
+            let (options, filename) = match parse_run_args(&args) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    print_usage();
+                    process::exit(1);
+                }
+            };
+            if options.list_functions {
+                match list_functions(filename) {
+                    Ok(list) => print!("{}", list),
+                    Err(err) => {
+                        eprintln!("Error: {}", err);
+                        process::exit(1);
+                    }
+                }
+                return;
+            }
+            if options.watch {
+                if let Err(err) = watch_file(filename, &options) {
+                    eprintln!("Error: {}", err);
+                    process::exit(1);
+                }
+                return;
+            }
+            match run_file(filename, &options) {
+                Ok(result) => {
+                    if options.print_result {
+                        println!("{}", result);
+                    }
+                    // With an entry function, a numeric result of main is the exit code
+                    if options.call_main && matches!(result, Value::Number(_) | Value::Integer(_)) {
+                        match std_functions::status_code(&result) {
+                            Some(code) => process::exit(code),
+                            None => fail(PelinError::from(format!("main must return a whole number exit code, got {}", result))),
+                        }
+                    }
+                }
+                Err(err) => fail(err),
+            }
+        }
         None => {
-            print_usage();
-            process::exit(1);
+            let result = if std::path::Path::new(DEFAULT_PROGRAM).is_file() {
+                run_file(DEFAULT_PROGRAM, &RunOptions::default()).map(|_| ())
+            } else {
+                run_repl().map_err(PelinError::from)
+            };
+            if let Err(err) = result {
+                fail(err);
+            }
+        }
+    }
+}
+
+// Ends the process after a failed run, with the program's own status if it called `exit`
+fn fail(err: PelinError) -> ! {
+    if let PelinError::Exit { code, .. } = err {
+        process::exit(code);
+    }
+    eprintln!("Error: {}", err);
+    process::exit(1);
+}
+
+fn parse_run_args(args: &[String]) -> Result<(RunOptions, &str), String> {
+    let mut options = RunOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--call-main" => options.call_main = true,
+            "--debug-ast" => options.debug_ast = true,
+            "--profile" => options.profile = true,
+            "--profile-calls" => options.profile_calls = true,
+            "--print-result" => options.print_result = true,
+            "--int-literals" => options.int_literals = true,
+            "--buffered-output" => options.buffered_output = true,
+            "--copy-on-write" => options.copy_on_write = true,
+            "--typecheck" => options.typecheck = true,
+            "--watch" => options.watch = true,
+            "--list-functions" => options.list_functions = true,
+            "--import" => {
+                let feather = args.next().ok_or("Missing feather name after --import")?;
+                options.imports.push(feather.clone());
+            }
+            flag if flag.starts_with("--") => return Err(format!("Unknown option: {}", flag)),
+            filename => {
+                options.program_args = args.cloned().collect();
+                return Ok((options, filename));
+            }
         }
     }
+    Err("Missing file to run".to_string())
 }
 
 fn print_usage() {
-    println!("Usage: pelin <file.pl>");
+    println!("Usage: pelin [options] <file.pl> [args...]");
     println!("       pelin --version");
-    println!("\nRuns Pelikan programs or displays the version of pelin.");
+    println!("       pelin --validate-feathers");
+    println!("       pelin --repl");
+    println!("\nRuns Pelikan programs, starts an interactive session or displays the version of pelin.");
+    println!("Without arguments, runs {} from the current directory if it exists and starts", DEFAULT_PROGRAM);
+    println!("an interactive session otherwise.");
+    println!("Arguments after the file are available to the program as the array `args`.");
+    println!("\nOptions:");
+    println!("  --call-main        Call the program's main() after the top level has run");
+    println!("  --debug-ast        Validate the parsed syntax tree before running");
+    println!("  --profile          Print call counts and time per function to stderr");
+    println!("  --profile-calls    Like --profile, without the timings that vary between runs");
+    println!("  --print-result     Print the value the program evaluates to");
+    println!("  --int-literals     Read whole literals like 5 as integers instead of floats");
+    println!("  --buffered-output  Buffer printed output and write it at exit or on std_io.flush()");
+    println!("  --copy-on-write    Copy arrays and maps bound to a new name on their first change");
+    println!("  --typecheck        Check literal arguments and returns against declared types first");
+    println!("  --import <feather> Import a feather before running, like an imp line (repeatable)");
+    println!("  --watch            Run again whenever the file or a feather it imports changes");
+    println!("  --list-functions   List the functions the file defines and imports instead of running it");
 }
 
-fn run_file(filename: &str) -> Result<(), String> {
+fn validate_feathers() -> Result<(), String> {
+    let project_root = std::env::current_dir().unwrap();
+    let feather_manager = FeatherManager::new(project_root);
+    let failures = feather_manager.validate_all()?;
+
+    if failures.is_empty() {
+        println!("All feathers are valid");
+        return Ok(());
+    }
+    for (path, err) in &failures {
+        eprintln!("{}: {}", path.display(), err);
+    }
+    Err(format!("{} feather(s) failed to parse", failures.len()))
+}
+
+fn read_program(filename: &str, int_literals: bool) -> Result<Vec<Expr>, String> {
     if !filename.ends_with(".pl") {
         return Err(format!("Invalid file extension. Expected a .pl file, got: {}", filename));
     }
-
     let content = fs::read_to_string(filename)
         .map_err(|e| format!("Error reading file '{}': {}", filename, e))?;
 
     let mut lexer = Lexer::new(&content);
+    lexer.set_int_literals(int_literals);
     let tokens = lexer.tokenize()?;
 
     let mut parser = Parser::new(tokens);
-    let expressions = parser.parse()?;
+    Ok(parser.parse()?)
+}
+
+fn function_rows(prefix: &str, expressions: &[Expr]) -> Vec<Vec<String>> {
+    expressions.iter()
+        .filter_map(|expr| match expr {
+            Expr::FunctionDefinition { return_type, name, parameters, .. } => {
+                let parameters: Vec<String> = parameters.iter().map(|(ty, name)| format!("{} {}", ty, name)).collect();
+                Some(vec![format!("{}{}", prefix, name), return_type.to_string(), parameters.join(", ")])
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Signatures of the functions the file defines and of those in the feathers it imports,
+/// sorted by name. Nothing is run, feathers are only parsed.
+fn list_functions(filename: &str) -> Result<String, String> {
+    let expressions = read_program(filename, false)?;
+    let feather_manager = new_feather_manager();
+    let mut rows = function_rows("", &expressions);
+    for expr in &expressions {
+        if let Expr::Import { name, alias } = expr {
+            let path = feather_manager.borrow().feather_path(name);
+            let feather = read_program(&path.to_string_lossy(), false)
+                .map_err(|e| format!("Could not read feather '{}': {}", name, e))?;
+            let prefix = alias.as_deref().unwrap_or(name.trim_start_matches('.'));
+            rows.extend(function_rows(&format!("{}.", prefix), &feather));
+        }
+    }
+    rows.sort();
+    rows.dedup();
+    Ok(diagnostics::table(&[("function", Align::Left), ("returns", Align::Left), ("parameters", Align::Left)], &rows))
+}
+
+fn new_feather_manager() -> Rc<RefCell<FeatherManager>> {
+    let project_root = std::env::current_dir().unwrap();
+    Rc::new(RefCell::new(FeatherManager::new(project_root)))
+}
+
+fn run_file(filename: &str, options: &RunOptions) -> Result<Value, PelinError> {
+    run_file_with(filename, options, &new_feather_manager())
+}
+
+fn run_file_with(filename: &str, options: &RunOptions, feather_manager: &Rc<RefCell<FeatherManager>>) -> Result<Value, PelinError> {
+    let expressions = read_program(filename, options.int_literals)?;
+
+    if options.debug_ast {
+        Expr::validate_program(&expressions)
+            .map_err(|errors| format!("Invalid syntax tree:\n  {}", errors.join("\n  ")))?;
+    }
+    if options.typecheck {
+        typecheck::check_program(&expressions)
+            .map_err(|errors| format!("Type check failed:\n  {}", errors.join("\n  ")))?;
+    }
+
+    for feather in &options.imports {
+        FeatherManager::import(feather_manager, feather)?;
+    }
+    let mut interpreter = Interpreter::new(Rc::clone(feather_manager));
+    if options.profile || options.profile_calls {
+        interpreter.enable_profiling();
+    }
+    interpreter.set_copy_on_write(options.copy_on_write);
+    let args = options.program_args.iter().map(|arg| Value::String(arg.clone())).collect();
+    interpreter.define_variable("args", Value::Array(Shared::new(args)));
+    if options.buffered_output {
+        std_functions::set_buffered_output(true)?;
+    }
+
+    let result = if options.call_main {
+        interpreter.interpret_program_with_main(&expressions)
+    } else {
+        interpreter.interpret_program(&expressions)
+    };
+
+    // Also on error, so output printed before the failure is not lost
+    std_functions::flush_output()?;
+    let report = if options.profile_calls { interpreter.profile_calls_report() } else { interpreter.profile_report() };
+    if let Some(report) = report {
+        eprint!("{}", report);
+    }
+    result
+}
+
+/// Runs the file, then again after every change to it or to a feather it imported, until killed.
+/// A failing run is reported and the file stays watched.
+fn watch_file(filename: &str, options: &RunOptions) -> Result<(), String> {
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(|e| format!("Could not watch files: {}", e))?;
+    let mut watched_dirs: Vec<PathBuf> = Vec::new();
+    // Kept across runs, so a feather that broke and failed to import is still watched
+    let mut files = vec![std::env::current_dir().unwrap().join(filename)];
+
+    loop {
+        if io::stdout().is_terminal() {
+            print!("\x1B[2J\x1B[H");
+        }
+        let feather_manager = new_feather_manager();
+        match run_file_with(filename, options, &feather_manager) {
+            Ok(result) if options.print_result => println!("{}", result),
+            // Ending with `exit` is a normal end of the run, the watch goes on
+            Ok(_) | Err(PelinError::Exit { .. }) => {}
+            Err(err) => eprintln!("Error: {}", err),
+        }
+        // Flushed now, a piped stdout would otherwise hold the output until the process ends
+        let _ = io::stdout().flush();
+
+        for feather in feather_manager.borrow().feathers.values() {
+            if !files.contains(&feather.path) {
+                files.push(feather.path.clone());
+            }
+        }
+        // Directories rather than the files, editors often save by replacing the file
+        for dir in files.iter().filter_map(|file| file.parent()) {
+            if !watched_dirs.iter().any(|watched| watched == dir) {
+                watcher.watch(dir, RecursiveMode::NonRecursive)
+                    .map_err(|e| format!("Could not watch {}: {}", dir.display(), e))?;
+                watched_dirs.push(dir.to_path_buf());
+            }
+        }
+        eprintln!("Watching {} for changes, press Ctrl-C to stop", filename);
+        wait_for_change(&events, &files)?;
+    }
+}
+
+fn wait_for_change(events: &mpsc::Receiver<notify::Result<Event>>, files: &[PathBuf]) -> Result<(), String> {
+    loop {
+        let event = events.recv()
+            .map_err(|_| "File watcher stopped".to_string())?
+            .map_err(|e| format!("File watcher failed: {}", e))?;
+        let changed = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_))
+            && event.paths.iter().any(|path| files.contains(path));
+        if changed {
+            // A save often arrives as several events, run once for all of them
+            thread::sleep(Duration::from_millis(50));
+            while events.try_recv().is_ok() {}
+            return Ok(());
+        }
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("PELIN_HISTORY") {
+        return Some(PathBuf::from(path));
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+/// Interactive session: every entry runs in the same interpreter, so definitions and imports
+/// persist. `:paste` collects lines until `:end` and runs them as one entry.
+fn run_repl() -> Result<(), String> {
+    let mut editor = DefaultEditor::new().map_err(|e| format!("Could not start the REPL: {}", e))?;
+    let history = history_path();
+    if let Some(path) = &history {
+        // A missing history file just means this is the first session
+        let _ = editor.load_history(path);
+    }
 
     let project_root = std::env::current_dir().unwrap();
     let feather_manager = Rc::new(RefCell::new(FeatherManager::new(project_root)));
-    let interpreter = Interpreter::new(Rc::clone(&feather_manager));
+    let interpreter = Interpreter::new(feather_manager);
+
+    println!("pelin {} REPL, :paste for multi-line input, exit or Ctrl-D to quit", VERSION);
+    // Set when an entry calls `std_process.exit`, which ends the session with that status
+    let mut exit_status = None;
+    loop {
+        let line = match editor.readline(">> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(format!("Could not read input: {}", err)),
+        };
+        let source = match line.trim() {
+            "" => continue,
+            "exit" => break,
+            ":paste" => match read_paste(&mut editor)? {
+                Some(block) => block,
+                None => break,
+            },
+            _ => match read_continuation(&mut editor, line)? {
+                Some(entry) => entry,
+                None => break,
+            },
+        };
+        // Ctrl-C in a continued entry drops it
+        if source.trim().is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(source.as_str());
 
-    interpreter.interpret_program(&expressions)?;
+        match eval_line(&interpreter, &source) {
+            Ok(Value::Nun) => {}
+            Ok(value) => println!("{}", value),
+            Err(PelinError::Exit { code, .. }) => {
+                exit_status = Some(code);
+                break;
+            }
+            Err(err) => eprintln!("Error: {}", err),
+        }
+        std_functions::flush_output()?;
+    }
 
+    if let Some(path) = &history {
+        editor.save_history(path).map_err(|e| format!("Could not save history to {}: {}", path.display(), e))?;
+    }
+    if let Some(code) = exit_status {
+        std_functions::flush_output()?;
+        process::exit(code);
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Lines up to `:end`, or `None` if the input ends first.
+fn read_paste(editor: &mut DefaultEditor) -> Result<Option<String>, String> {
+    println!("Paste mode, finish with :end");
+    let mut lines = Vec::new();
+    loop {
+        match editor.readline(".. ") {
+            Ok(line) if line.trim() == ":end" => return Ok(Some(lines.join("\n"))),
+            Ok(line) => lines.push(line),
+            Err(ReadlineError::Interrupted) => return Ok(Some(String::new())),
+            Err(ReadlineError::Eof) => return Ok(None),
+            Err(err) => return Err(format!("Could not read input: {}", err)),
+        }
+    }
+}
+
+/// Keeps reading lines while the entry has unclosed braces, e.g. a function definition typed
+/// over several lines. `None` if the input ends first.
+fn read_continuation(editor: &mut DefaultEditor, line: String) -> Result<Option<String>, String> {
+    let mut entry = line;
+    while open_braces(&entry) > 0 {
+        match editor.readline(".. ") {
+            Ok(line) => {
+                entry.push('\n');
+                entry.push_str(&line);
+            },
+            Err(ReadlineError::Interrupted) => return Ok(Some(String::new())),
+            Err(ReadlineError::Eof) => return Ok(None),
+            Err(err) => return Err(format!("Could not read input: {}", err)),
+        }
+    }
+    Ok(Some(entry))
+}
+
+// Braces in strings and comments don't count. Input that does not lex is complete, running it
+// reports the error.
+fn open_braces(source: &str) -> i64 {
+    let Ok(tokens) = Lexer::new(source).tokenize() else {
+        return 0;
+    };
+    tokens.iter().fold(0, |depth, token| match token.kind {
+        TokenType::LeftBrace => depth + 1,
+        TokenType::RightBrace => depth - 1,
+        _ => depth,
+    })
+}
+
+fn eval_line(interpreter: &Interpreter, source: &str) -> Result<Value, PelinError> {
+    let tokens = Lexer::new(source).tokenize()?;
+    let expressions = Parser::new(tokens).parse()?;
+    interpreter.interpret_program(&expressions)
+}
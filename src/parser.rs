@@ -1,3 +1,4 @@
+use crate::error::PelinError;
 use crate::lexer::{Token, TokenType};
 
 #[derive(Debug, PartialEq, Clone)]
@@ -9,18 +10,114 @@ pub enum Type {
     Custom(String),
 }
 
+// Written as in source, e.g. `num` or a custom type's name
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Num => write!(f, "num"),
+            Type::Str => write!(f, "str"),
+            Type::Bool => write!(f, "bool"),
+            Type::Nun => write!(f, "nun"),
+            Type::Custom(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum UnaryOperator {
+    /// `-x`, numbers only
+    Negate,
+    /// `!x`, booleans only
+    Not,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+/// Operators that only evaluate their right operand when the left one doesn't decide the result.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LogicalOperator {
+    /// `a && b`, skips `b` when `a` is false
+    And,
+    /// `a || b`, skips `b` when `a` is true
+    Or,
+}
+
+impl std::fmt::Display for LogicalOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogicalOperator::And => write!(f, "&&"),
+            LogicalOperator::Or => write!(f, "||"),
+        }
+    }
+}
+
+// Written as in source, e.g. `+` or `<=`
+impl std::fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Remainder => "%",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEqual => ">=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expr {
     Number(f64),
+    Integer(i64),
     String(String),
     Boolean(bool),
     Nun,
     Return(Box<Expr>),
     Identifier(String),
+    Array(Vec<Expr>),
+    Map(Vec<(String, Expr)>),
+    Unary {
+        operator: UnaryOperator,
+        operand: Box<Expr>,
+    },
+    Binary {
+        operator: BinaryOperator,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Logical {
+        operator: LogicalOperator,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
     FunctionCall {
         callee: Box<Expr>,
         arguments: Vec<Expr>,
     },
+    /// `target[index]`
+    Index {
+        target: Box<Expr>,
+        index: Box<Expr>,
+    },
     FunctionDefinition {
         return_type: Type,
         name: String,
@@ -31,7 +128,44 @@ pub enum Expr {
         name: String,
         value: Box<Expr>,
     },
-    Import(String),
+    /// `let name = value`, declares a variable in the current scope
+    Let {
+        name: String,
+        value: Box<Expr>,
+    },
+    /// `if (condition) { ... } else { ... }`, an `else if` is an else branch holding another `If`.
+    /// `condition ? a : b` is an `If` with one expression in each branch.
+    /// `line` and `column` are where the condition starts, for the error if it is not a boolean.
+    If {
+        condition: Box<Expr>,
+        then_branch: Vec<Expr>,
+        else_branch: Option<Vec<Expr>>,
+        line: usize,
+        column: usize,
+    },
+    /// `while (condition) { ... }`, evaluates to `nun`. `line` and `column` are as for `If`.
+    While {
+        condition: Box<Expr>,
+        body: Vec<Expr>,
+        line: usize,
+        column: usize,
+    },
+    /// `{ ... }` in expression position, runs in its own scope and evaluates to its last
+    /// expression. `{}` is an empty map, not a block.
+    Block(Vec<Expr>),
+    /// `for (variable in iterable) { ... }`, evaluates to `nun`
+    ForEach {
+        variable: String,
+        iterable: Box<Expr>,
+        body: Vec<Expr>,
+    },
+    /// `imp name` or `imp name as alias`
+    Import {
+        name: String,
+        alias: Option<String>,
+    },
+    /// `inc "path.pl"`, runs another file's top level in the current scope
+    Include(String),
     RustFunctionCall {
         path: Vec<String>,
         arguments: Vec<Expr>,
@@ -41,6 +175,134 @@ pub enum Expr {
         function: String,
         arguments: Vec<Expr>,
     },
+    /// `feather.function` without a call, evaluates to the function itself
+    FeatherFunction {
+        feather: String,
+        function: String,
+    },
+}
+
+impl Expr {
+    /// Checks the invariants the interpreter relies on for a parsed program and returns every violation.
+    /// The parser should never produce an invalid tree, so this is meant for debugging (`--debug-ast`).
+    pub fn validate_program(expressions: &[Expr]) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for expr in expressions {
+            expr.validate_in(true, &mut errors);
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Validates a single expression as if it appeared at the top level of a program.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        Self::validate_program(std::slice::from_ref(self))
+    }
+
+    fn validate_in(&self, top_level: bool, errors: &mut Vec<String>) {
+        match self {
+            Expr::Number(_) | Expr::Integer(_) | Expr::String(_) | Expr::Boolean(_) | Expr::Nun => {},
+            Expr::Identifier(name) => {
+                if name.is_empty() {
+                    errors.push("Identifier with an empty name".to_string());
+                }
+            },
+            Expr::Array(elements) | Expr::Block(elements) => {
+                for element in elements {
+                    element.validate_in(false, errors);
+                }
+            },
+            Expr::Map(entries) => {
+                for (_, value) in entries {
+                    value.validate_in(false, errors);
+                }
+            },
+            Expr::Unary { operand, .. } => operand.validate_in(false, errors),
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                left.validate_in(false, errors);
+                right.validate_in(false, errors);
+            },
+            // Outside a function, `return` ends the program with its value
+            Expr::Return(value) => value.validate_in(false, errors),
+            Expr::FunctionCall { callee, arguments } => {
+                callee.validate_in(false, errors);
+                for argument in arguments {
+                    argument.validate_in(false, errors);
+                }
+            },
+            Expr::Index { target, index } => {
+                target.validate_in(false, errors);
+                index.validate_in(false, errors);
+            },
+            Expr::FunctionDefinition { name, parameters, body, .. } => {
+                if name.is_empty() {
+                    errors.push("Function definition with an empty name".to_string());
+                }
+                for (i, (_, param)) in parameters.iter().enumerate() {
+                    if parameters[..i].iter().any(|(_, other)| other == param) {
+                        errors.push(format!("Duplicate parameter '{}' in function '{}'", param, name));
+                    }
+                }
+                for expr in body {
+                    expr.validate_in(false, errors);
+                }
+            },
+            Expr::Assignment { name, value } => {
+                if name.is_empty() {
+                    errors.push("Assignment to an empty name".to_string());
+                }
+                value.validate_in(false, errors);
+            },
+            Expr::Let { name, value } => {
+                if name.is_empty() {
+                    errors.push("Declaration of an empty name".to_string());
+                }
+                value.validate_in(false, errors);
+            },
+            Expr::If { condition, then_branch, else_branch, .. } => {
+                condition.validate_in(false, errors);
+                for expr in then_branch.iter().chain(else_branch.iter().flatten()) {
+                    expr.validate_in(false, errors);
+                }
+            },
+            Expr::While { condition, body, .. } | Expr::ForEach { iterable: condition, body, .. } => {
+                condition.validate_in(false, errors);
+                for expr in body {
+                    expr.validate_in(false, errors);
+                }
+            },
+            Expr::Import { name, .. } => {
+                if !top_level {
+                    errors.push(format!("Import of '{}' is only allowed at the top level", name));
+                }
+            },
+            Expr::Include(path) => {
+                if !top_level {
+                    errors.push(format!("Include of '{}' is only allowed at the top level", path));
+                }
+            },
+            Expr::RustFunctionCall { path, arguments } => {
+                if path.len() < 2 {
+                    errors.push(format!("Rust function path '{}' needs at least two segments", path.join("::")));
+                }
+                for argument in arguments {
+                    argument.validate_in(false, errors);
+                }
+            },
+            Expr::FeatherFunction { feather, function } => {
+                if feather.is_empty() || function.is_empty() {
+                    errors.push("Feather function reference with an empty name".to_string());
+                }
+            },
+            Expr::FeatherFunctionCall { feather, function, arguments } => {
+                if feather.is_empty() || function.is_empty() {
+                    errors.push("Feather function call with an empty name".to_string());
+                }
+                for argument in arguments {
+                    argument.validate_in(false, errors);
+                }
+            },
+        }
+    }
 }
 
 pub struct Parser {
@@ -53,42 +315,224 @@ impl Parser {
         Parser { tokens, current: 0 }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Expr>, String> {
+    /// Parses the whole program. On failure, every error found is reported, one per line.
+    pub fn parse(&mut self) -> Result<Vec<Expr>, PelinError> {
+        self.parse_all().map_err(|errors| {
+            let mut errors = errors.into_iter();
+            let mut first = errors.next().expect("parsing fails with at least one error");
+            if let PelinError::Parse { message, .. } = &mut first {
+                for error in errors {
+                    message.push('\n');
+                    message.push_str(error.message());
+                }
+            }
+            first
+        })
+    }
+
+    /// Like `parse`, but returns the errors separately. After an error the parser skips to the
+    /// next statement and continues, so independent mistakes are all reported.
+    pub fn parse_all(&mut self) -> Result<Vec<Expr>, Vec<PelinError>> {
         let mut expressions = Vec::new();
+        let mut errors = Vec::new();
         while !self.is_at_end() {
-            if self.match_token(&[TokenType::Imp]) {
-                expressions.push(self.import_statement()?);
-            } else {
-                expressions.push(self.expression()?);
+            let start = self.current;
+            match self.statement() {
+                Ok(expr) => expressions.push(expr),
+                Err(err) => {
+                    errors.push(err);
+                    self.synchronize(start);
+                }
             }
         }
-        Ok(expressions)
+        if errors.is_empty() { Ok(expressions) } else { Err(errors) }
+    }
+
+    fn statement(&mut self) -> Result<Expr, PelinError> {
+        if self.match_token(&[TokenType::Imp]) {
+            self.import_statement()
+        } else if self.match_token(&[TokenType::Inc]) {
+            self.include_statement()
+        } else {
+            self.expression()
+        }
     }
 
-    fn import_statement(&mut self) -> Result<Expr, String> {
+    // Skips the rest of a failed statement that began at token `start`: up to the first token
+    // that starts a line outside of any block opened since `start`.
+    fn synchronize(&mut self, start: usize) {
+        let mut depth = 0usize;
+        for token in &self.tokens[start..self.current] {
+            match token.kind {
+                TokenType::LeftBrace => depth += 1,
+                TokenType::RightBrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        if self.current == start {
+            self.advance();
+        }
+        while !self.is_at_end() {
+            if depth == 0 && !self.continues_line() {
+                return;
+            }
+            match self.advance().map(|token| &token.kind) {
+                Some(TokenType::LeftBrace) => depth += 1,
+                Some(TokenType::RightBrace) => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+
+    fn import_statement(&mut self) -> Result<Expr, PelinError> {
+        // A leading dot marks a feather relative to the project root, e.g. `imp .local`
+        let relative = self.match_token(&[TokenType::Dot]);
         let name = self.consume_identifier("Expected feather name after 'imp'")?;
-        Ok(Expr::Import(name))
+        let name = if relative { format!(".{}", name) } else { name };
+        let alias = if self.match_token(&[TokenType::As]) {
+            Some(self.consume_identifier("Expected alias name after 'as'")?)
+        } else {
+            None
+        };
+        Ok(Expr::Import { name, alias })
+    }
+
+    fn include_statement(&mut self) -> Result<Expr, PelinError> {
+        match self.advance().map(|token| token.kind.clone()) {
+            Some(TokenType::String(path)) => Ok(Expr::Include(path)),
+            Some(_) => Err(self.error_at_previous("Expected file path string after 'inc'")),
+            None => Err(self.error_at_end("Expected file path string after 'inc'")),
+        }
     }
 
-    fn expression(&mut self) -> Result<Expr, String> {
-        if self.match_token(&[TokenType::RustKeyword]) {
-            self.rust_function_call()
-        } else if self.match_token(&[TokenType::Return]) {
+    fn expression(&mut self) -> Result<Expr, PelinError> {
+        if self.match_token(&[TokenType::Return]) {
             let value = self.expression()?;
             Ok(Expr::Return(Box::new(value)))
         } else if self.match_token(&[TokenType::Fn]) {
             self.function_definition()
+        } else if self.match_token(&[TokenType::Let]) {
+            let name = self.consume_identifier("Expected variable name after 'let'")?;
+            self.consume(TokenType::Equal, "Expected '=' after variable name")?;
+            let value = self.expression()?;
+            Ok(Expr::Let { name, value: Box::new(value) })
+        } else if let Some(name) = self.assignment_target() {
+            self.advance();
+            self.advance();
+            let value = self.expression()?;
+            Ok(Expr::Assignment { name, value: Box::new(value) })
+        } else if self.match_token(&[TokenType::If]) {
+            self.if_expression()
+        } else if self.match_token(&[TokenType::While]) {
+            self.while_expression()
+        } else if self.match_token(&[TokenType::For]) {
+            self.for_expression()
         } else {
-            self.function_call()
+            self.conditional()
         }
     }
 
-    fn rust_function_call(&mut self) -> Result<Expr, String> {
+    // `condition ? a : b`, looser than `||` and right associative, so `a ? b : c ? d : e` is
+    // `a ? b : (c ? d : e)`
+    fn conditional(&mut self) -> Result<Expr, PelinError> {
+        let (line, column) = self.position();
+        let condition = self.or()?;
+        if !self.check(&TokenType::Question) {
+            return Ok(condition);
+        }
+        self.advance();
+        let then_value = self.expression()?;
+        self.consume(TokenType::Colon, "Expected ':' in conditional expression")?;
+        let else_value = self.conditional()?;
+        Ok(Expr::If { condition: Box::new(condition), then_branch: vec![then_value], else_branch: Some(vec![else_value]), line, column })
+    }
+
+    // Binary operators from loosest to tightest: `||`, `&&`, equality, comparison, term, factor.
+    // Each level is left associative, `1 - 2 - 3` is `(1 - 2) - 3`.
+    fn or(&mut self) -> Result<Expr, PelinError> {
+        self.logical(TokenType::OrOr, LogicalOperator::Or, Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, PelinError> {
+        self.logical(TokenType::AndAnd, LogicalOperator::And, Self::equality)
+    }
+
+    fn logical(&mut self, token: TokenType, operator: LogicalOperator, operand: fn(&mut Self) -> Result<Expr, PelinError>) -> Result<Expr, PelinError> {
+        let mut expr = operand(self)?;
+        while self.check(&token) {
+            self.advance();
+            let right = operand(self)?;
+            expr = Expr::Logical { operator, left: Box::new(expr), right: Box::new(right) };
+        }
+        Ok(expr)
+    }
+
+    fn equality(&mut self) -> Result<Expr, PelinError> {
+        self.binary(&[
+            (TokenType::EqualEqual, BinaryOperator::Equal),
+            (TokenType::BangEqual, BinaryOperator::NotEqual),
+        ], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, PelinError> {
+        self.binary(&[
+            (TokenType::Less, BinaryOperator::Less),
+            (TokenType::LessEqual, BinaryOperator::LessEqual),
+            (TokenType::Greater, BinaryOperator::Greater),
+            (TokenType::GreaterEqual, BinaryOperator::GreaterEqual),
+        ], Self::term)
+    }
+
+    fn term(&mut self) -> Result<Expr, PelinError> {
+        self.binary(&[
+            (TokenType::Plus, BinaryOperator::Add),
+            (TokenType::Minus, BinaryOperator::Subtract),
+        ], Self::factor)
+    }
+
+    fn factor(&mut self) -> Result<Expr, PelinError> {
+        self.binary(&[
+            (TokenType::Star, BinaryOperator::Multiply),
+            (TokenType::Slash, BinaryOperator::Divide),
+            (TokenType::Percent, BinaryOperator::Remainder),
+        ], Self::unary)
+    }
+
+    fn binary(&mut self, operators: &[(TokenType, BinaryOperator)], operand: fn(&mut Self) -> Result<Expr, PelinError>) -> Result<Expr, PelinError> {
+        let mut expr = operand(self)?;
+        // A `-` at the start of a line negates a new expression rather than subtracting from this one
+        while let Some(operator) = operators.iter()
+            .find(|(token, _)| self.check(token) && (*token != TokenType::Minus || self.continues_line()))
+            .map(|(_, operator)| *operator)
+        {
+            self.advance();
+            let right = operand(self)?;
+            expr = Expr::Binary { operator, left: Box::new(expr), right: Box::new(right) };
+        }
+        Ok(expr)
+    }
+
+    // Prefix operators nest to the right, `-!x` is `-(!x)`
+    fn unary(&mut self) -> Result<Expr, PelinError> {
+        let operator = if self.match_token(&[TokenType::Minus]) {
+            UnaryOperator::Negate
+        } else if self.match_token(&[TokenType::Bang]) {
+            UnaryOperator::Not
+        } else if self.match_token(&[TokenType::RustKeyword]) {
+            return self.rust_function_call();
+        } else {
+            return self.function_call();
+        };
+        let operand = self.unary()?;
+        Ok(Expr::Unary { operator, operand: Box::new(operand) })
+    }
+
+    fn rust_function_call(&mut self) -> Result<Expr, PelinError> {
         self.consume(TokenType::LeftBracket, "Expected '[' after 'RUST'")?;
         let mut path = Vec::new();
 
         loop {
-            path.push(self.consume_identifier("Expected identifier in Rust function path")?);
+            path.push(self.consume_path_segment()?);
             if !self.match_token(&[TokenType::DoubleColon]) {
                 break;
             }
@@ -107,7 +551,7 @@ impl Parser {
         Ok(Expr::RustFunctionCall { path, arguments })
     }
 
-    fn parse_arguments(&mut self) -> Result<Vec<Expr>, String> {
+    fn parse_arguments(&mut self) -> Result<Vec<Expr>, PelinError> {
         let mut args = Vec::new();
         loop {
             args.push(self.expression()?);
@@ -118,7 +562,7 @@ impl Parser {
         Ok(args)
     }
 
-    fn function_definition(&mut self) -> Result<Expr, String> {
+    fn function_definition(&mut self) -> Result<Expr, PelinError> {
         let return_type = self.parse_type()?;
         let name = self.consume_identifier("Expected function name")?;
         self.consume(TokenType::LeftParen, "Expected '(' after function name")?;
@@ -137,11 +581,7 @@ impl Parser {
         self.consume(TokenType::RightParen, "Expected ')' after parameters")?;
 
         self.consume(TokenType::LeftBrace, "Expected '{' before function body")?;
-        let mut body = Vec::new();
-        while !self.check(&TokenType::RightBrace) {
-            body.push(Box::new(self.expression()?));
-        }
-        self.consume(TokenType::RightBrace, "Expected '}' after function body")?;
+        let body = self.block("Expected '}' after function body")?.into_iter().map(Box::new).collect();
 
         Ok(Expr::FunctionDefinition {
             return_type,
@@ -151,7 +591,66 @@ impl Parser {
         })
     }
 
-    fn parse_type(&mut self) -> Result<Type, String> {
+    // The name in `name = value`, assignments nest to the right so `a = b = 1` assigns both
+    fn assignment_target(&self) -> Option<String> {
+        match (self.peek().map(|token| &token.kind), self.tokens.get(self.current + 1).map(|token| &token.kind)) {
+            (Some(TokenType::Identifier(name)), Some(TokenType::Equal)) => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    fn if_expression(&mut self) -> Result<Expr, PelinError> {
+        self.consume(TokenType::LeftParen, "Expected '(' after 'if'")?;
+        let (line, column) = self.position();
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expected ')' after if condition")?;
+        self.consume(TokenType::LeftBrace, "Expected '{' before if branch")?;
+        let then_branch = self.block("Expected '}' after if branch")?;
+
+        let else_branch = if !self.match_token(&[TokenType::Else]) {
+            None
+        } else if self.match_token(&[TokenType::If]) {
+            Some(vec![self.if_expression()?])
+        } else {
+            self.consume(TokenType::LeftBrace, "Expected '{' or 'if' after 'else'")?;
+            Some(self.block("Expected '}' after else branch")?)
+        };
+
+        Ok(Expr::If { condition: Box::new(condition), then_branch, else_branch, line, column })
+    }
+
+    fn while_expression(&mut self) -> Result<Expr, PelinError> {
+        self.consume(TokenType::LeftParen, "Expected '(' after 'while'")?;
+        let (line, column) = self.position();
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expected ')' after while condition")?;
+        self.consume(TokenType::LeftBrace, "Expected '{' before loop body")?;
+        let body = self.block("Expected '}' after loop body")?;
+        Ok(Expr::While { condition: Box::new(condition), body, line, column })
+    }
+
+    fn for_expression(&mut self) -> Result<Expr, PelinError> {
+        self.consume(TokenType::LeftParen, "Expected '(' after 'for'")?;
+        let variable = self.consume_identifier("Expected loop variable name after 'for ('")?;
+        self.consume(TokenType::In, "Expected 'in' after loop variable")?;
+        let iterable = self.expression()?;
+        self.consume(TokenType::RightParen, "Expected ')' after for iterable")?;
+        self.consume(TokenType::LeftBrace, "Expected '{' before loop body")?;
+        let body = self.block("Expected '}' after loop body")?;
+        Ok(Expr::ForEach { variable, iterable: Box::new(iterable), body })
+    }
+
+    // The expressions up to the closing brace, the opening brace is already consumed
+    fn block(&mut self, message: &str) -> Result<Vec<Expr>, PelinError> {
+        let mut expressions = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            expressions.push(self.expression()?);
+        }
+        self.consume(TokenType::RightBrace, message)?;
+        Ok(expressions)
+    }
+
+    fn parse_type(&mut self) -> Result<Type, PelinError> {
         if let Some(token) = self.advance() {
             match &token.kind {
                 TokenType::Identifier(name) => Ok(match name.as_str() {
@@ -169,31 +668,30 @@ impl Parser {
         }
     }
 
-    fn function_call(&mut self) -> Result<Expr, String> {
+    fn function_call(&mut self) -> Result<Expr, PelinError> {
         let mut expr = self.primary()?;
 
         loop {
-            if self.match_token(&[TokenType::LeftParen]) {
+            // A parenthesized expression or array literal on the next line starts a new expression
+            if self.check(&TokenType::LeftParen) && self.continues_line() {
+                self.advance();
                 expr = self.finish_call(expr)?;
+            } else if self.check(&TokenType::LeftBracket) && self.continues_line() {
+                self.advance();
+                let index = self.expression()?;
+                self.consume(TokenType::RightBracket, "Expected ']' after index")?;
+                expr = Expr::Index { target: Box::new(expr), index: Box::new(index) };
             } else if self.match_token(&[TokenType::Dot]) {
-                let name = self.consume_identifier("Expected property name after '.'")?;
-                if self.match_token(&[TokenType::LeftParen]) {
-                    let arguments = self.arguments()?;
-                    if let Expr::Identifier(feather) = expr {
-                        expr = Expr::FeatherFunctionCall {
-                            feather,
-                            function: name,
-                            arguments,
-                        };
-                    } else {
-                        return Err("Expected feather name before '.'".to_string());
-                    }
+                // Only `feather.function` has a dot, e.g. `"a".x` or `f().g` is an error
+                let Expr::Identifier(feather) = expr else {
+                    return Err(self.error_at_previous("Expected feather name before '.'"));
+                };
+                let function = self.consume_identifier("Expected property name after '.'")?;
+                expr = if self.match_token(&[TokenType::LeftParen]) {
+                    Expr::FeatherFunctionCall { feather, function, arguments: self.arguments()? }
                 } else {
-                    expr = Expr::FunctionCall {
-                        callee: Box::new(expr),
-                        arguments: vec![Expr::Identifier(name)],
-                    };
-                }
+                    Expr::FeatherFunction { feather, function }
+                };
             } else {
                 break;
             }
@@ -202,7 +700,7 @@ impl Parser {
         Ok(expr)
     }
 
-    fn finish_call(&mut self, callee: Expr) -> Result<Expr, String> {
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, PelinError> {
         let arguments = self.arguments()?;
         Ok(Expr::FunctionCall {
             callee: Box::new(callee),
@@ -210,7 +708,7 @@ impl Parser {
         })
     }
 
-    fn arguments(&mut self) -> Result<Vec<Expr>, String> {
+    fn arguments(&mut self) -> Result<Vec<Expr>, PelinError> {
         let mut args = Vec::new();
 
         if !self.check(&TokenType::RightParen) {
@@ -226,14 +724,22 @@ impl Parser {
         Ok(args)
     }
 
-    fn primary(&mut self) -> Result<Expr, String> {
+    fn primary(&mut self) -> Result<Expr, PelinError> {
         if let Some(token) = self.advance() {
             match &token.kind {
                 TokenType::Number(n) => Ok(Expr::Number(*n)),
+                TokenType::Integer(n) => Ok(Expr::Integer(*n)),
                 TokenType::String(s) => Ok(Expr::String(s.clone())),
                 TokenType::Boolean(b) => Ok(Expr::Boolean(*b)),
                 TokenType::Nun => Ok(Expr::Nun),
                 TokenType::Identifier(name) => Ok(Expr::Identifier(name.clone())),
+                TokenType::LeftBracket => self.array_literal(),
+                TokenType::LeftBrace => self.map_or_block(),
+                TokenType::LeftParen => {
+                    let expr = self.expression()?;
+                    self.consume(TokenType::RightParen, "Expected ')' after expression")?;
+                    Ok(expr)
+                },
                 _ => Err(self.error_at_previous("Unexpected token")),
             }
         } else {
@@ -241,7 +747,47 @@ impl Parser {
         }
     }
 
-    fn consume_identifier(&mut self, message: &str) -> Result<String, String> {
+    fn array_literal(&mut self) -> Result<Expr, PelinError> {
+        let elements = if self.check(&TokenType::RightBracket) {
+            Vec::new()
+        } else {
+            self.parse_arguments()?
+        };
+        self.consume(TokenType::RightBracket, "Expected ']' after array elements")?;
+        Ok(Expr::Array(elements))
+    }
+
+    // After a `{`: `{}`, `{ "key" = ...` and `{ true = ...` are maps, anything else is a block
+    fn map_or_block(&mut self) -> Result<Expr, PelinError> {
+        match (self.peek().map(|token| &token.kind), self.tokens.get(self.current + 1).map(|token| &token.kind)) {
+            (Some(TokenType::RightBrace), _)
+            | (Some(TokenType::String(_) | TokenType::Boolean(_)), Some(TokenType::Equal)) => self.map_literal(),
+            _ => Ok(Expr::Block(self.block("Expected '}' after block")?)),
+        }
+    }
+
+    fn map_literal(&mut self) -> Result<Expr, PelinError> {
+        let mut entries = Vec::new();
+        if !self.check(&TokenType::RightBrace) {
+            loop {
+                // Keys are strings, a boolean key is stored as "true" or "false"
+                let key = match self.advance().map(|token| token.kind.clone()) {
+                    Some(TokenType::String(key)) => key,
+                    Some(TokenType::Boolean(key)) => key.to_string(),
+                    _ => return Err(self.error_at_previous("Expected string or boolean key in map literal")),
+                };
+                self.consume(TokenType::Equal, "Expected '=' after map key")?;
+                entries.push((key, self.expression()?));
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after map entries")?;
+        Ok(Expr::Map(entries))
+    }
+
+    fn consume_identifier(&mut self, message: &str) -> Result<String, PelinError> {
         if let Some(token) = self.advance() {
             match &token.kind {
                 TokenType::Identifier(name) => Ok(name.clone()),
@@ -252,6 +798,31 @@ impl Parser {
         }
     }
 
+    // Keywords are plain names inside a Rust path, e.g. `RUST[std_func::if]`
+    fn consume_path_segment(&mut self) -> Result<String, PelinError> {
+        let message = "Expected identifier in Rust function path";
+        match self.advance().cloned() {
+            Some(Token { kind: TokenType::Identifier(name), .. }) => Ok(name),
+            Some(Token { kind: TokenType::String(_), .. }) => Err(self.error_at_previous(message)),
+            Some(token) if token.lexeme.chars().all(|c| c.is_alphabetic() || c == '_') => Ok(token.lexeme),
+            Some(_) => Err(self.error_at_previous(message)),
+            None => Err(self.error_at_end(message)),
+        }
+    }
+
+    // Line and column of the current token, where the expression parsed next starts
+    fn position(&self) -> (usize, usize) {
+        self.peek().map_or((0, 0), |token| (token.line, token.column))
+    }
+
+    // Whether the current token is on the same line as the one before it
+    fn continues_line(&self) -> bool {
+        match (self.peek(), self.previous()) {
+            (Some(current), Some(previous)) => current.line == previous.line,
+            _ => false,
+        }
+    }
+
     fn match_token(&mut self, types: &[TokenType]) -> bool {
         for t in types {
             if self.check(t) {
@@ -263,7 +834,7 @@ impl Parser {
     }
 
     fn check(&self, t: &TokenType) -> bool {
-        self.peek().map_or(false, |token| &token.kind == t)
+        self.peek().is_some_and(|token| &token.kind == t)
     }
 
     fn advance(&mut self) -> Option<&Token> {
@@ -274,7 +845,7 @@ impl Parser {
     }
 
     fn is_at_end(&self) -> bool {
-        self.peek().map_or(true, |t| matches!(t.kind, TokenType::EOF))
+        self.peek().is_none_or(|t| matches!(t.kind, TokenType::EOF))
     }
 
     fn peek(&self) -> Option<&Token> {
@@ -285,7 +856,7 @@ impl Parser {
         self.tokens.get(self.current - 1)
     }
 
-    fn consume(&mut self, t: TokenType, message: &str) -> Result<&Token, String> {
+    fn consume(&mut self, t: TokenType, message: &str) -> Result<&Token, PelinError> {
         if self.check(&t) {
             Ok(self.advance().unwrap())
         } else {
@@ -297,23 +868,29 @@ impl Parser {
         }
     }
 
-    fn error_at_current(&self, message: &str) -> String {
+    fn error_at_current(&self, message: &str) -> PelinError {
         self.error_at(self.current, message)
     }
 
-    fn error_at_previous(&self, message: &str) -> String {
+    fn error_at_previous(&self, message: &str) -> PelinError {
         self.error_at(self.current - 1, message)
     }
 
-    fn error_at_end(&self, message: &str) -> String {
+    fn error_at_end(&self, message: &str) -> PelinError {
         let last_token = self.tokens.last().unwrap();
-        format!("[line {}, column {}] Error at end: {}",
-                last_token.line, last_token.column, message)
+        PelinError::Parse {
+            message: format!("[line {}, column {}] Error at end: {}", last_token.line, last_token.column, message),
+            line: last_token.line,
+            column: last_token.column,
+        }
     }
 
-    fn error_at(&self, index: usize, message: &str) -> String {
+    fn error_at(&self, index: usize, message: &str) -> PelinError {
         let token = &self.tokens[index];
-        format!("[line {}, column {}] Error at '{}': {}",
-                token.line, token.column, token.lexeme, message)
+        PelinError::Parse {
+            message: format!("[line {}, column {}] Error at '{}': {}", token.line, token.column, token.lexeme, message),
+            line: token.line,
+            column: token.column,
+        }
     }
 }
\ No newline at end of file
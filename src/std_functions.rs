@@ -1,91 +1,988 @@
+use std::cell::{Cell, RefCell};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use feruca::Collator;
+use std::env::VarError;
 use std::fs::File;
-use std::io::{Read, Write};
-use crate::interpreter::Value;
+use std::io::{BufRead, BufWriter, IsTerminal, Read, Stdout, Write};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::interpreter::{Shared, Value};
+
+fn new_array(values: Vec<Value>) -> Value {
+    Value::Array(Shared::new(values))
+}
+
+fn array_elements(function: &str, value: &Value) -> Result<Vec<Value>, String> {
+    match value {
+        Value::Array(elements) => Ok(elements.borrow().clone()),
+        _ => Err(format!("{} function expects array arguments", function)),
+    }
+}
+
+// Indices may be integers or whole, non-negative floats
+fn index_argument(function: &str, value: &Value) -> Result<usize, String> {
+    match value {
+        Value::Integer(n) if *n >= 0 => Ok(*n as usize),
+        // `as usize` saturates, so anything from usize::MAX up is rejected rather than clamped
+        Value::Number(n) if *n >= 0.0 && *n < usize::MAX as f64 && n.fract() == 0.0 => Ok(*n as usize),
+        _ => Err(format!("{} function expects a non-negative whole number, got {}", function, value)),
+    }
+}
+
+// Values can't be hashed (numbers are floats), so the set operations compare with `==`
+// and are quadratic in the array length.
+fn contains_value(values: &[Value], value: &Value) -> bool {
+    values.iter().any(|element| element.equals(value))
+}
+
+fn unique_values(values: Vec<Value>) -> Vec<Value> {
+    let mut result: Vec<Value> = Vec::with_capacity(values.len());
+    for value in values {
+        if !contains_value(&result, &value) {
+            result.push(value);
+        }
+    }
+    result
+}
 
 // num
-pub fn std_num_add(args: Vec<Value>) -> Result<Value, String> {
+/// Operands of a binary numeric function: two integers stay integers, anything else is a float.
+enum Operands {
+    Integers(i64, i64),
+    Floats(f64, f64),
+}
+
+fn operands(function: &str, args: &[Value]) -> Result<Operands, String> {
     if args.len() != 2 {
-        return Err("add function expects 2 arguments".to_string());
+        return Err(format!("{} function expects 2 arguments", function));
     }
     match (&args[0], &args[1]) {
-        (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
-        _ => Err("add function expects number arguments".to_string()),
+        (Value::Integer(a), Value::Integer(b)) => Ok(Operands::Integers(*a, *b)),
+        (a, b) => match (a.as_number(), b.as_number()) {
+            (Ok(a), Ok(b)) => Ok(Operands::Floats(a, b)),
+            _ => Err(format!("{} function expects number arguments", function)),
+        },
+    }
+}
+
+fn float_argument(function: &str, args: &[Value]) -> Result<f64, String> {
+    if args.len() != 1 {
+        return Err(format!("{} function expects 1 argument", function));
+    }
+    args[0].as_number().map_err(|_| format!("{} function expects a number argument", function))
+}
+
+fn integer_result(function: &str, result: Option<i64>) -> Result<Value, String> {
+    result.map(Value::Integer).ok_or_else(|| format!("{} function overflowed", function))
+}
+
+pub fn std_num_add(args: Vec<Value>) -> Result<Value, String> {
+    match operands("add", &args)? {
+        Operands::Integers(a, b) => integer_result("add", a.checked_add(b)),
+        Operands::Floats(a, b) => Ok(Value::Number(a + b)),
     }
 }
 
 pub fn std_num_subtract(args: Vec<Value>) -> Result<Value, String> {
+    match operands("subtract", &args)? {
+        Operands::Integers(a, b) => integer_result("subtract", a.checked_sub(b)),
+        Operands::Floats(a, b) => Ok(Value::Number(a - b)),
+    }
+}
+
+pub fn std_num_multiply(args: Vec<Value>) -> Result<Value, String> {
+    match operands("multiply", &args)? {
+        Operands::Integers(a, b) => integer_result("multiply", a.checked_mul(b)),
+        Operands::Floats(a, b) => Ok(Value::Number(a * b)),
+    }
+}
+
+// Division always yields a float, `divide(7i, 2i)` is 3.5
+pub fn std_num_divide(args: Vec<Value>) -> Result<Value, String> {
+    let (a, b) = match operands("divide", &args)? {
+        Operands::Integers(a, b) => (a as f64, b as f64),
+        Operands::Floats(a, b) => (a, b),
+    };
+    if b == 0.0 {
+        Err("division by zero".to_string())
+    } else {
+        Ok(Value::Number(a / b))
+    }
+}
+
+// Floored modulo, the result has the sign of the divisor: `mod(-1, 3)` is 2
+pub fn std_num_mod(args: Vec<Value>) -> Result<Value, String> {
+    match operands("mod", &args)? {
+        Operands::Integers(_, 0) => Err("division by zero".to_string()),
+        Operands::Integers(a, b) => integer_result("mod", a.checked_rem(b).map(|r| {
+            if r != 0 && (r < 0) != (b < 0) { r + b } else { r }
+        })),
+        Operands::Floats(_, 0.0) => Err("division by zero".to_string()),
+        Operands::Floats(a, b) => Ok(Value::Number(a - (a / b).floor() * b)),
+    }
+}
+
+// Division rounded towards zero, integers stay integral: `int_divide(-7i, 2i)` is -3
+pub fn std_num_int_divide(args: Vec<Value>) -> Result<Value, String> {
+    match operands("int_divide", &args)? {
+        Operands::Integers(_, 0) => Err("division by zero".to_string()),
+        Operands::Integers(a, b) => integer_result("int_divide", a.checked_div(b)),
+        Operands::Floats(_, 0.0) => Err("division by zero".to_string()),
+        Operands::Floats(a, b) => Ok(Value::Number((a / b).trunc())),
+    }
+}
+
+// Division rounded down, integers stay integral: `floor_divide(-7i, 2i)` is -4
+pub fn std_num_floor_divide(args: Vec<Value>) -> Result<Value, String> {
+    match operands("floor_divide", &args)? {
+        Operands::Integers(_, 0) => Err("division by zero".to_string()),
+        Operands::Integers(a, b) => integer_result("floor_divide", a.checked_div(b).map(|q| {
+            if a % b != 0 && (a < 0) != (b < 0) { q - 1 } else { q }
+        })),
+        Operands::Floats(_, 0.0) => Err("division by zero".to_string()),
+        Operands::Floats(a, b) => Ok(Value::Number((a / b).floor())),
+    }
+}
+
+pub fn std_num_sqrt(args: Vec<Value>) -> Result<Value, String> {
+    let a = float_argument("sqrt", &args)?;
+    if a < 0.0 {
+        Err("cannot compute square root of negative number".to_string())
+    } else {
+        Ok(Value::Number(a.sqrt()))
+    }
+}
+
+pub fn std_num_format_number(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 && args.len() != 3 {
+        return Err("format_number function expects 2 or 3 arguments".to_string());
+    }
+    let decimal = match args.get(2) {
+        Some(Value::String(d)) => d.as_str(),
+        Some(_) => return Err("format_number function expects a string decimal separator".to_string()),
+        None => ".",
+    };
+    match (args[0].as_number().ok(), &args[1]) {
+        (Some(n), Value::String(thousands)) => Ok(Value::String(format_number(n, thousands, decimal))),
+        _ => Err("format_number function expects a number and a string separator".to_string()),
+    }
+}
+
+// Rounds halves to the nearest even number (banker's rounding), so 2.5 becomes 2 and 3.5 becomes 4.
+// Unlike rounding halves away from zero, this does not bias sums of rounded values upwards.
+pub fn std_num_round_even(args: Vec<Value>) -> Result<Value, String> {
+    if let [Value::Integer(n)] = args.as_slice() {
+        return Ok(Value::Integer(*n));
+    }
+    float_argument("round_even", &args).map(|n| Value::Number(n.round_ties_even()))
+}
+
+fn integer_argument(function: &str, args: &[Value]) -> Result<i64, String> {
+    if args.len() != 1 {
+        return Err(format!("{} function expects 1 argument", function));
+    }
+    match &args[0] {
+        Value::Integer(n) => Ok(*n),
+        _ => Err(format!("{} function expects an integer argument", function)),
+    }
+}
+
+// Bit helpers work on the two's complement i64 representation
+pub fn std_num_bit_count(args: Vec<Value>) -> Result<Value, String> {
+    integer_argument("bit_count", &args).map(|n| Value::Integer(n.count_ones() as i64))
+}
+
+pub fn std_num_leading_zeros(args: Vec<Value>) -> Result<Value, String> {
+    integer_argument("leading_zeros", &args).map(|n| Value::Integer(n.leading_zeros() as i64))
+}
+
+pub fn std_num_trailing_zeros(args: Vec<Value>) -> Result<Value, String> {
+    integer_argument("trailing_zeros", &args).map(|n| Value::Integer(n.trailing_zeros() as i64))
+}
+
+fn integer_arguments(function: &str, args: &[Value], count: usize) -> Result<Vec<i64>, String> {
+    if args.len() != count {
+        return Err(format!("{} function expects {} arguments", function, count));
+    }
+    args.iter().map(|arg| match arg {
+        Value::Integer(n) => Ok(*n),
+        _ => Err(format!("{} function expects integer arguments", function)),
+    }).collect()
+}
+
+pub fn std_num_clamp_int(args: Vec<Value>) -> Result<Value, String> {
+    let values = integer_arguments("clamp_int", &args, 3)?;
+    let (n, lo, hi) = (values[0], values[1], values[2]);
+    if lo > hi {
+        return Err(format!("clamp_int function expects lo <= hi, got {} and {}", lo, hi));
+    }
+    Ok(Value::Integer(n.clamp(lo, hi)))
+}
+
+pub fn std_num_abs_int(args: Vec<Value>) -> Result<Value, String> {
+    let n = integer_argument("abs_int", &args)?;
+    integer_result("abs_int", n.checked_abs())
+}
+
+// Wraps around on overflow instead of failing like add
+pub fn std_num_wrapping_add(args: Vec<Value>) -> Result<Value, String> {
+    let values = integer_arguments("wrapping_add", &args, 2)?;
+    Ok(Value::Integer(values[0].wrapping_add(values[1])))
+}
+
+// `-0.0 == 0.0`, so the sign bit is the only way to tell them apart
+pub fn std_num_is_negative_zero(args: Vec<Value>) -> Result<Value, String> {
+    float_argument("is_negative_zero", &args).map(|n| Value::Boolean(n == 0.0 && n.is_sign_negative()))
+}
+
+fn format_number(n: f64, thousands: &str, decimal: &str) -> String {
+    if !n.is_finite() {
+        return n.to_string();
+    }
+    let plain = n.abs().to_string();
+    let (int_part, frac_part) = match plain.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (plain.as_str(), None),
+    };
+
+    let mut grouped = String::new();
+    for (i, digit) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            grouped.push_str(thousands);
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if n < 0.0 { "-" } else { "" };
+    match frac_part {
+        Some(frac) => format!("{}{}{}{}", sign, grouped, decimal, frac),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+// math
+pub fn std_math_sin(args: Vec<Value>) -> Result<Value, String> {
+    float_argument("sin", &args).map(|n| Value::Number(n.sin()))
+}
+
+pub fn std_math_cos(args: Vec<Value>) -> Result<Value, String> {
+    float_argument("cos", &args).map(|n| Value::Number(n.cos()))
+}
+
+pub fn std_math_tan(args: Vec<Value>) -> Result<Value, String> {
+    float_argument("tan", &args).map(|n| Value::Number(n.tan()))
+}
+
+pub fn std_math_pow(args: Vec<Value>) -> Result<Value, String> {
+    match operands("pow", &args)? {
+        Operands::Integers(a, b) => Ok(Value::Number((a as f64).powf(b as f64))),
+        Operands::Floats(a, b) => Ok(Value::Number(a.powf(b))),
+    }
+}
+
+// Natural logarithm
+pub fn std_math_log(args: Vec<Value>) -> Result<Value, String> {
+    let a = float_argument("log", &args)?;
+    if a <= 0.0 {
+        Err("log function expects a positive number".to_string())
+    } else {
+        Ok(Value::Number(a.ln()))
+    }
+}
+
+pub fn std_math_abs(args: Vec<Value>) -> Result<Value, String> {
+    match args.first() {
+        Some(Value::Integer(n)) if args.len() == 1 => integer_result("abs", n.checked_abs()),
+        _ => float_argument("abs", &args).map(|n| Value::Number(n.abs())),
+    }
+}
+
+// Rounding leaves integers unchanged, floats stay floats
+fn rounded(function: &str, args: &[Value], round: fn(f64) -> f64) -> Result<Value, String> {
+    match args.first() {
+        Some(Value::Integer(n)) if args.len() == 1 => Ok(Value::Integer(*n)),
+        _ => float_argument(function, args).map(|n| Value::Number(round(n))),
+    }
+}
+
+pub fn std_math_floor(args: Vec<Value>) -> Result<Value, String> {
+    rounded("floor", &args, f64::floor)
+}
+
+pub fn std_math_ceil(args: Vec<Value>) -> Result<Value, String> {
+    rounded("ceil", &args, f64::ceil)
+}
+
+// Halves round away from zero, `round_even` rounds them to the even neighbour instead
+pub fn std_math_round(args: Vec<Value>) -> Result<Value, String> {
+    rounded("round", &args, f64::round)
+}
+
+pub fn std_math_pi(args: Vec<Value>) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("pi function expects no arguments".to_string());
+    }
+    Ok(Value::Number(std::f64::consts::PI))
+}
+
+pub fn std_math_e(args: Vec<Value>) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("e function expects no arguments".to_string());
+    }
+    Ok(Value::Number(std::f64::consts::E))
+}
+
+pub fn std_math_min(args: Vec<Value>) -> Result<Value, String> {
+    match operands("min", &args)? {
+        Operands::Integers(a, b) => Ok(Value::Integer(a.min(b))),
+        Operands::Floats(a, b) => Ok(Value::Number(a.min(b))),
+    }
+}
+
+pub fn std_math_max(args: Vec<Value>) -> Result<Value, String> {
+    match operands("max", &args)? {
+        Operands::Integers(a, b) => Ok(Value::Integer(a.max(b))),
+        Operands::Floats(a, b) => Ok(Value::Number(a.max(b))),
+    }
+}
+
+/// `base` to the power of `exp` in integer arithmetic, an error instead of a float on overflow.
+pub fn std_math_pow_int(args: Vec<Value>) -> Result<Value, String> {
+    let values = integer_arguments("pow_int", &args, 2)?;
+    let (base, exp) = (values[0], values[1]);
+    if exp < 0 {
+        return Err(format!("pow_int function expects a non-negative exponent, got {}", exp));
+    }
+    let result = match base {
+        // Exact for any exponent, even ones too large for `checked_pow`
+        0 | 1 => Some(if exp == 0 { 1 } else { base }),
+        -1 => Some(if exp % 2 == 0 { 1 } else { -1 }),
+        _ => u32::try_from(exp).ok().and_then(|exp| base.checked_pow(exp)),
+    };
+    integer_result("pow_int", result)
+}
+
+// string
+// Case mapping uses Rust's Unicode-aware `to_lowercase`/`to_uppercase`. These are not
+// locale-aware (e.g. Turkish dotted i) and may change length ("ß" uppercases to "SS").
+pub fn std_string_eq_ignore_case(args: Vec<Value>) -> Result<Value, String> {
     if args.len() != 2 {
-        return Err("subtract function expects 2 arguments".to_string());
+        return Err("eq_ignore_case function expects 2 arguments".to_string());
     }
     match (&args[0], &args[1]) {
-        (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a - b)),
-        _ => Err("subtract function expects number arguments".to_string()),
+        (Value::String(a), Value::String(b)) => Ok(Value::Boolean(a.to_lowercase() == b.to_lowercase())),
+        _ => Err("eq_ignore_case function expects string arguments".to_string()),
     }
 }
 
-pub fn std_num_multiply(args: Vec<Value>) -> Result<Value, String> {
+pub fn std_string_to_title_case(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("to_title_case function expects 1 argument".to_string());
+    }
+    match &args[0] {
+        Value::String(s) => {
+            let mut result = String::with_capacity(s.len());
+            let mut word_start = true;
+            for c in s.chars() {
+                if c.is_whitespace() {
+                    result.push(c);
+                    word_start = true;
+                } else if word_start {
+                    result.extend(c.to_uppercase());
+                    word_start = false;
+                } else {
+                    result.extend(c.to_lowercase());
+                }
+            }
+            Ok(Value::String(result))
+        }
+        _ => Err("to_title_case function expects a string argument".to_string()),
+    }
+}
+
+// array
+pub fn std_array_push(args: Vec<Value>) -> Result<Value, String> {
     if args.len() != 2 {
-        return Err("multiply function expects 2 arguments".to_string());
+        return Err("push function expects 2 arguments".to_string());
     }
-    match (&args[0], &args[1]) {
-        (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a * b)),
-        _ => Err("multiply function expects number arguments".to_string()),
+    match &args[0] {
+        Value::Array(elements) => {
+            elements.borrow_mut().push(args[1].clone());
+            Ok(Value::Nun)
+        }
+        _ => Err("push function expects an array as first argument".to_string()),
     }
 }
 
-pub fn std_num_divide(args: Vec<Value>) -> Result<Value, String> {
+// Like `get` on a map, an index past the end gives `nun`
+pub fn std_array_get(args: Vec<Value>) -> Result<Value, String> {
     if args.len() != 2 {
-        return Err("divide function expects 2 arguments".to_string());
+        return Err("get function expects 2 arguments".to_string());
     }
-    match (&args[0], &args[1]) {
-        (Value::Number(a), Value::Number(b)) => {
-            if *b == 0.0 {
-                Err("division by zero".to_string())
-            } else {
-                Ok(Value::Number(a / b))
+    let index = index_argument("get", &args[1])?;
+    match &args[0] {
+        Value::Array(elements) => Ok(elements.borrow().get(index).cloned().unwrap_or(Value::Nun)),
+        _ => Err("get function expects an array as first argument".to_string()),
+    }
+}
+
+pub fn std_array_len(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("len function expects 1 argument".to_string());
+    }
+    match &args[0] {
+        Value::Array(elements) => Ok(Value::Number(elements.borrow().len() as f64)),
+        _ => Err("len function expects an array argument".to_string()),
+    }
+}
+
+/// Removes `delete_count` elements at `start`, inserts `items` there and returns the removed elements.
+pub fn std_array_splice(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 4 {
+        return Err("splice function expects 4 arguments".to_string());
+    }
+    let start = index_argument("splice", &args[1])?;
+    let delete_count = index_argument("splice", &args[2])?;
+    // Copied first, `items` may be the array being spliced
+    let items = array_elements("splice", &args[3])?;
+    match &args[0] {
+        Value::Array(elements) => {
+            let mut elements = elements.borrow_mut();
+            if start > elements.len() || delete_count > elements.len() - start {
+                return Err(format!(
+                    "splice range {}..{} is out of bounds for an array of length {}",
+                    start, start.saturating_add(delete_count), elements.len()
+                ));
             }
+            let removed = elements.splice(start..start + delete_count, items).collect();
+            Ok(new_array(removed))
         }
-        _ => Err("divide function expects number arguments".to_string()),
+        _ => Err("splice function expects an array as first argument".to_string()),
     }
 }
 
-pub fn std_num_sqrt(args: Vec<Value>) -> Result<Value, String> {
+pub fn std_array_unique(args: Vec<Value>) -> Result<Value, String> {
     if args.len() != 1 {
-        return Err("sqrt function expects 1 argument".to_string());
+        return Err("unique function expects 1 argument".to_string());
+    }
+    Ok(new_array(unique_values(array_elements("unique", &args[0])?)))
+}
+
+pub fn std_array_dedup(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("dedup function expects 1 argument".to_string());
+    }
+    let mut elements = array_elements("dedup", &args[0])?;
+    elements.dedup_by(|a, b| a.equals(b));
+    Ok(new_array(elements))
+}
+
+pub fn std_array_union(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("union function expects 2 arguments".to_string());
+    }
+    let mut elements = array_elements("union", &args[0])?;
+    elements.extend(array_elements("union", &args[1])?);
+    Ok(new_array(unique_values(elements)))
+}
+
+pub fn std_array_intersection(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("intersection function expects 2 arguments".to_string());
+    }
+    let other = array_elements("intersection", &args[1])?;
+    let elements = array_elements("intersection", &args[0])?
+        .into_iter()
+        .filter(|value| contains_value(&other, value))
+        .collect();
+    Ok(new_array(unique_values(elements)))
+}
+
+pub fn std_array_difference(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("difference function expects 2 arguments".to_string());
+    }
+    let other = array_elements("difference", &args[1])?;
+    let elements = array_elements("difference", &args[0])?
+        .into_iter()
+        .filter(|value| !contains_value(&other, value))
+        .collect();
+    Ok(new_array(unique_values(elements)))
+}
+
+/// An array of `count` copies of `value`. Arrays and maps are shared between the copies.
+pub fn std_array_fill(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("fill function expects 2 arguments".to_string());
+    }
+    let count = index_argument("fill", &args[1])?;
+    let mut elements = reserve_elements("fill", count as u64)?;
+    elements.resize(count, args[0].clone());
+    Ok(new_array(elements))
+}
+
+// An empty vector with room for `count` elements, or an error if that much memory is not available
+fn reserve_elements(function: &str, count: u64) -> Result<Vec<Value>, String> {
+    let mut elements = Vec::new();
+    usize::try_from(count)
+        .ok()
+        .and_then(|count| elements.try_reserve_exact(count).ok())
+        .ok_or_else(|| format!("{} function cannot create an array of {} elements", function, count))?;
+    Ok(elements)
+}
+
+/// The whole numbers from `start` up to but excluding `end`, integers if both bounds are.
+pub fn std_array_range_to_array(args: Vec<Value>) -> Result<Value, String> {
+    let (start, end, integers) = match operands("range_to_array", &args)? {
+        Operands::Integers(start, end) => (start, end, true),
+        Operands::Floats(start, end) if start.fract() == 0.0 && end.fract() == 0.0 => (start as i64, end as i64, false),
+        Operands::Floats(_, _) => return Err("range_to_array function expects whole numbers".to_string()),
+    };
+    let count = if end > start { end.abs_diff(start) } else { 0 };
+    let mut elements = reserve_elements("range_to_array", count)?;
+    elements.extend((start..end).map(|n| if integers { Value::Integer(n) } else { Value::Number(n as f64) }));
+    Ok(new_array(elements))
+}
+
+fn size_argument(function: &str, value: &Value) -> Result<usize, String> {
+    match index_argument(function, value)? {
+        0 => Err(format!("{} function expects a size of at least 1", function)),
+        size => Ok(size),
+    }
+}
+
+/// Splits the array into consecutive sub-arrays of `size` elements, the last one may be shorter.
+pub fn std_array_chunk(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("chunk function expects 2 arguments".to_string());
+    }
+    let size = size_argument("chunk", &args[1])?;
+    let chunks = array_elements("chunk", &args[0])?
+        .chunks(size)
+        .map(|chunk| new_array(chunk.to_vec()))
+        .collect();
+    Ok(new_array(chunks))
+}
+
+/// Every run of `size` consecutive elements, empty if the array is shorter than `size`.
+pub fn std_array_window(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("window function expects 2 arguments".to_string());
+    }
+    let size = size_argument("window", &args[1])?;
+    let windows = array_elements("window", &args[0])?
+        .windows(size)
+        .map(|window| new_array(window.to_vec()))
+        .collect();
+    Ok(new_array(windows))
+}
+
+pub fn std_string_trim_start(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("trim_start function expects 1 argument".to_string());
     }
     match &args[0] {
-        Value::Number(a) => {
-            if *a < 0.0 {
-                Err("cannot compute square root of negative number".to_string())
-            } else {
-                Ok(Value::Number(a.sqrt()))
-            }
+        Value::String(s) => Ok(Value::String(s.trim_start().to_string())),
+        _ => Err("trim_start function expects a string argument".to_string()),
+    }
+}
+
+pub fn std_string_trim_end(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("trim_end function expects 1 argument".to_string());
+    }
+    match &args[0] {
+        Value::String(s) => Ok(Value::String(s.trim_end().to_string())),
+        _ => Err("trim_end function expects a string argument".to_string()),
+    }
+}
+
+// When the affix is absent the string is returned unchanged, so the result is always a string
+pub fn std_string_strip_prefix(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("strip_prefix function expects 2 arguments".to_string());
+    }
+    match (&args[0], &args[1]) {
+        (Value::String(s), Value::String(prefix)) => {
+            Ok(Value::String(s.strip_prefix(prefix.as_str()).unwrap_or(s).to_string()))
         }
-        _ => Err("sqrt function expects a number argument".to_string()),
+        _ => Err("strip_prefix function expects string arguments".to_string()),
     }
 }
 
-// math
-pub fn std_math_sin(args: Vec<Value>) -> Result<Value, String> {
+pub fn std_string_strip_suffix(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("strip_suffix function expects 2 arguments".to_string());
+    }
+    match (&args[0], &args[1]) {
+        (Value::String(s), Value::String(suffix)) => {
+            Ok(Value::String(s.strip_suffix(suffix.as_str()).unwrap_or(s).to_string()))
+        }
+        _ => Err("strip_suffix function expects string arguments".to_string()),
+    }
+}
+
+// Counts non-overlapping occurrences, so "aa" occurs once in "aaa"
+pub fn std_string_count(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("count function expects 2 arguments".to_string());
+    }
+    match (&args[0], &args[1]) {
+        (Value::String(_), Value::String(needle)) if needle.is_empty() => {
+            Err("count function expects a non-empty needle".to_string())
+        }
+        (Value::String(s), Value::String(needle)) => Ok(Value::Number(s.matches(needle.as_str()).count() as f64)),
+        _ => Err("count function expects string arguments".to_string()),
+    }
+}
+
+// Splits on "\n" and "\r\n", a trailing line break does not add an empty line
+pub fn std_string_lines(args: Vec<Value>) -> Result<Value, String> {
     if args.len() != 1 {
-        return Err("sin function expects 1 argument".to_string());
+        return Err("lines function expects 1 argument".to_string());
     }
     match &args[0] {
-        Value::Number(n) => Ok(Value::Number(n.sin())),
-        _ => Err("sin function expects a number argument".to_string()),
+        Value::String(s) => Ok(new_array(s.lines().map(|line| Value::String(line.to_string())).collect())),
+        _ => Err("lines function expects a string argument".to_string()),
     }
 }
 
-pub fn std_math_cos(args: Vec<Value>) -> Result<Value, String> {
+// Lengths and positions count characters, not bytes, so "héllo" has length 5
+pub fn std_string_length(args: Vec<Value>) -> Result<Value, String> {
+    let s = string_argument("length", &args)?;
+    Ok(Value::Number(s.chars().count() as f64))
+}
+
+pub fn std_string_concat(args: Vec<Value>) -> Result<Value, String> {
+    let mut result = String::new();
+    for arg in &args {
+        match arg {
+            Value::String(s) => result.push_str(s),
+            _ => return Err("concat function expects string arguments".to_string()),
+        }
+    }
+    Ok(Value::String(result))
+}
+
+/// Up to `len` characters starting at character `start`, shorter if the string ends first.
+pub fn std_string_substring(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 3 {
+        return Err("substring function expects 3 arguments".to_string());
+    }
+    let start = index_argument("substring", &args[1])?;
+    let len = index_argument("substring", &args[2])?;
+    match &args[0] {
+        Value::String(s) => Ok(Value::String(s.chars().skip(start).take(len).collect())),
+        _ => Err("substring function expects a string as first argument".to_string()),
+    }
+}
+
+pub fn std_string_split(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("split function expects 2 arguments".to_string());
+    }
+    match (&args[0], &args[1]) {
+        (Value::String(_), Value::String(delimiter)) if delimiter.is_empty() => {
+            Err("split function expects a non-empty delimiter".to_string())
+        }
+        (Value::String(s), Value::String(delimiter)) => {
+            Ok(new_array(s.split(delimiter.as_str()).map(|part| Value::String(part.to_string())).collect()))
+        }
+        _ => Err("split function expects string arguments".to_string()),
+    }
+}
+
+pub fn std_string_to_upper(args: Vec<Value>) -> Result<Value, String> {
+    string_argument("to_upper", &args).map(|s| Value::String(s.to_uppercase()))
+}
+
+pub fn std_string_to_lower(args: Vec<Value>) -> Result<Value, String> {
+    string_argument("to_lower", &args).map(|s| Value::String(s.to_lowercase()))
+}
+
+pub fn std_string_trim(args: Vec<Value>) -> Result<Value, String> {
+    string_argument("trim", &args).map(|s| Value::String(s.trim().to_string()))
+}
+
+pub fn std_string_contains(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("contains function expects 2 arguments".to_string());
+    }
+    match (&args[0], &args[1]) {
+        (Value::String(s), Value::String(needle)) => Ok(Value::Boolean(s.contains(needle.as_str()))),
+        _ => Err("contains function expects string arguments".to_string()),
+    }
+}
+
+// Collation uses the CLDR root order, so accented letters sort next to their base letter
+// instead of after `z` as in code point order. There is no per-language tailoring.
+pub fn std_string_compare_unicode(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("compare_unicode function expects 2 arguments".to_string());
+    }
+    match (&args[0], &args[1]) {
+        (Value::String(a), Value::String(b)) => {
+            let ordering = Collator::default().collate(a.as_str(), b.as_str());
+            Ok(Value::Number(ordering as i8 as f64))
+        }
+        _ => Err("compare_unicode function expects string arguments".to_string()),
+    }
+}
+
+/// Returns the strings of the array sorted by Unicode collation, the array itself is unchanged.
+pub fn std_string_sort_unicode(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("sort_unicode function expects 1 argument".to_string());
+    }
+    let mut strings = array_elements("sort_unicode", &args[0])?
+        .into_iter()
+        .map(|value| match value {
+            Value::String(s) => Ok(s),
+            other => Err(format!("sort_unicode function expects an array of strings, found {}", other.type_name())),
+        })
+        .collect::<Result<Vec<String>, String>>()?;
+    let mut collator = Collator::default();
+    strings.sort_by(|a, b| collator.collate(a.as_str(), b.as_str()));
+    Ok(new_array(strings.into_iter().map(Value::String).collect()))
+}
+
+// map
+// Map keys are strings, `true` and `false` stand for the keys "true" and "false"
+fn map_key(value: &Value) -> Option<String> {
+    match value {
+        Value::String(key) => Some(key.clone()),
+        Value::Boolean(key) => Some(key.to_string()),
+        _ => None,
+    }
+}
+
+pub fn std_map_get(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("map_get function expects 2 arguments".to_string());
+    }
+    match (&args[0], map_key(&args[1])) {
+        (Value::Map(map), Some(key)) => Ok(map.borrow().get(&key).cloned().unwrap_or(Value::Nun)),
+        _ => Err("map_get function expects a map and a string or boolean key".to_string()),
+    }
+}
+
+pub fn std_map_set(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 3 {
+        return Err("map_set function expects 3 arguments".to_string());
+    }
+    match (&args[0], map_key(&args[1])) {
+        (Value::Map(map), Some(key)) => {
+            map.borrow_mut().insert(key, args[2].clone());
+            Ok(Value::Nun)
+        }
+        _ => Err("map_set function expects a map and a string or boolean key".to_string()),
+    }
+}
+
+// The merge is shallow: nested arrays and maps are shared with the inputs, not copied
+pub fn std_map_merge(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("map_merge function expects 2 arguments".to_string());
+    }
+    match (&args[0], &args[1]) {
+        (Value::Map(a), Value::Map(b)) => {
+            let mut merged = a.borrow().clone();
+            merged.extend(b.borrow().iter().map(|(key, value)| (key.clone(), value.clone())));
+            Ok(Value::Map(Shared::new(merged)))
+        }
+        _ => Err("map_merge function expects two maps".to_string()),
+    }
+}
+
+pub fn std_map_remove(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("map_remove function expects 2 arguments".to_string());
+    }
+    match (&args[0], map_key(&args[1])) {
+        (Value::Map(map), Some(key)) => Ok(map.borrow_mut().remove(&key).unwrap_or(Value::Nun)),
+        _ => Err("map_remove function expects a map and a string or boolean key".to_string()),
+    }
+}
+
+pub fn std_map_has(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("map_has function expects 2 arguments".to_string());
+    }
+    match (&args[0], map_key(&args[1])) {
+        (Value::Map(map), Some(key)) => Ok(Value::Boolean(map.borrow().contains_key(&key))),
+        _ => Err("map_has function expects a map and a string or boolean key".to_string()),
+    }
+}
+
+// Keys are stored sorted, so they come back in sorted order
+pub fn std_map_keys(args: Vec<Value>) -> Result<Value, String> {
     if args.len() != 1 {
-        return Err("cos function expects 1 argument".to_string());
+        return Err("map_keys function expects 1 argument".to_string());
     }
     match &args[0] {
-        Value::Number(n) => Ok(Value::Number(n.cos())),
-        _ => Err("cos function expects a number argument".to_string()),
+        Value::Map(map) => Ok(new_array(map.borrow().keys().cloned().map(Value::String).collect())),
+        _ => Err("map_keys function expects a map argument".to_string()),
     }
 }
 
+// Works on arrays too, e.g. `map_get_path(list, "[0].name")`
+pub fn std_map_get_path(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("map_get_path function expects 2 arguments".to_string());
+    }
+    match &args[1] {
+        Value::String(path) => args[0].get_path(path),
+        _ => Err("map_get_path function expects a string path".to_string()),
+    }
+}
+
+// encoding
+// Strings are encoded as their UTF-8 bytes, and decoding fails if the bytes are not valid UTF-8
+fn string_argument<'a>(function: &str, args: &'a [Value]) -> Result<&'a str, String> {
+    if args.len() != 1 {
+        return Err(format!("{} function expects 1 argument", function));
+    }
+    args[0].as_string().map_err(|_| format!("{} function expects a string argument", function))
+}
+
+fn decoded_string(function: &str, bytes: Vec<u8>) -> Result<Value, String> {
+    String::from_utf8(bytes)
+        .map(Value::String)
+        .map_err(|_| format!("{} function decoded bytes that are not valid UTF-8", function))
+}
+
+pub fn std_encoding_base64_encode(args: Vec<Value>) -> Result<Value, String> {
+    let s = string_argument("base64_encode", &args)?;
+    Ok(Value::String(BASE64.encode(s)))
+}
+
+pub fn std_encoding_base64_decode(args: Vec<Value>) -> Result<Value, String> {
+    let s = string_argument("base64_decode", &args)?;
+    let bytes = BASE64.decode(s).map_err(|e| format!("base64_decode function got invalid base64: {}", e))?;
+    decoded_string("base64_decode", bytes)
+}
+
+pub fn std_encoding_hex_encode(args: Vec<Value>) -> Result<Value, String> {
+    let s = string_argument("hex_encode", &args)?;
+    Ok(Value::String(s.bytes().map(|byte| format!("{:02x}", byte)).collect()))
+}
+
+pub fn std_encoding_hex_decode(args: Vec<Value>) -> Result<Value, String> {
+    let s = string_argument("hex_decode", &args)?;
+    if s.len() % 2 != 0 {
+        return Err("hex_decode function got invalid hex: odd number of digits".to_string());
+    }
+    let bytes = (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| format!("hex_decode function got invalid hex digits at position {}", i))
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+    decoded_string("hex_decode", bytes)
+}
+
+// json
+// Numbers parse as `num`. Whole numbers are written without a fraction, so they read back the same.
+fn from_json(json: serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::Nun,
+        serde_json::Value::Bool(b) => Value::Boolean(b),
+        serde_json::Value::Number(n) => Value::Number(n.as_f64().unwrap_or(f64::NAN)),
+        serde_json::Value::String(s) => Value::String(s),
+        serde_json::Value::Array(elements) => new_array(elements.into_iter().map(from_json).collect()),
+        serde_json::Value::Object(entries) => Value::Map(Shared::new(
+            entries.into_iter().map(|(key, value)| (key, from_json(value))).collect(),
+        )),
+    }
+}
+
+fn to_json(value: &Value) -> Result<serde_json::Value, String> {
+    Ok(match value {
+        Value::Nun => serde_json::Value::Null,
+        Value::Boolean(b) => serde_json::Value::Bool(*b),
+        Value::Integer(n) => serde_json::Value::from(*n),
+        Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => serde_json::Value::from(*n as i64),
+        Value::Number(n) => serde_json::Number::from_f64(*n)
+            .map(serde_json::Value::Number)
+            .ok_or_else(|| format!("json_stringify function cannot encode {}", n))?,
+        Value::String(s) => serde_json::Value::String(s.clone()),
+        Value::Array(elements) => serde_json::Value::Array(elements.borrow().iter().map(to_json).collect::<Result<_, _>>()?),
+        Value::Map(entries) => serde_json::Value::Object(entries.borrow().iter()
+            .map(|(key, value)| Ok((key.clone(), to_json(value)?)))
+            .collect::<Result<_, String>>()?),
+        Value::Function(_) | Value::FeatherFunction(_, _) => return Err("json_stringify function cannot encode a function".to_string()),
+    })
+}
+
+pub fn std_json_parse(args: Vec<Value>) -> Result<Value, String> {
+    let s = string_argument("json_parse", &args)?;
+    serde_json::from_str(s)
+        .map(from_json)
+        .map_err(|e| format!("json_parse function got invalid JSON: {}", e))
+}
+
+pub fn std_json_stringify(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("json_stringify function expects 1 argument".to_string());
+    }
+    Ok(Value::String(to_json(&args[0])?.to_string()))
+}
+
+// type
+fn is_type(function: &str, args: &[Value], type_name: &str) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err(format!("{} function expects 1 argument", function));
+    }
+    Ok(Value::Boolean(args[0].type_name() == type_name))
+}
+
+pub fn std_type_of(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("type_of function expects 1 argument".to_string());
+    }
+    Ok(Value::String(args[0].type_name().to_string()))
+}
+
+// Integers are numbers too, `is_int` tells them apart
+pub fn std_type_is_num(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("is_num function expects 1 argument".to_string());
+    }
+    Ok(Value::Boolean(matches!(args[0], Value::Number(_) | Value::Integer(_))))
+}
+
+pub fn std_type_is_int(args: Vec<Value>) -> Result<Value, String> {
+    is_type("is_int", &args, "int")
+}
+
+pub fn std_type_is_str(args: Vec<Value>) -> Result<Value, String> {
+    is_type("is_str", &args, "str")
+}
+
+pub fn std_type_is_bool(args: Vec<Value>) -> Result<Value, String> {
+    is_type("is_bool", &args, "bool")
+}
+
+pub fn std_type_is_nun(args: Vec<Value>) -> Result<Value, String> {
+    is_type("is_nun", &args, "nun")
+}
+
+pub fn std_type_is_array(args: Vec<Value>) -> Result<Value, String> {
+    is_type("is_array", &args, "array")
+}
+
+pub fn std_type_is_map(args: Vec<Value>) -> Result<Value, String> {
+    is_type("is_map", &args, "map")
+}
+
+pub fn std_type_is_function(args: Vec<Value>) -> Result<Value, String> {
+    is_type("is_function", &args, "function")
+}
+
 // conversion
 pub fn std_convert_to_string(args: Vec<Value>) -> Result<Value, String> {
     if args.len() != 1 {
@@ -93,6 +990,7 @@ pub fn std_convert_to_string(args: Vec<Value>) -> Result<Value, String> {
     }
     match &args[0] {
         Value::Number(n) => Ok(Value::String(n.to_string())),
+        Value::Integer(n) => Ok(Value::String(n.to_string())),
         Value::Boolean(b) => Ok(Value::String(b.to_string())),
         Value::String(s) => Ok(Value::String(s.clone())),
         Value::Nun => Ok(Value::String("nun".to_string())),
@@ -111,18 +1009,261 @@ pub fn std_convert_to_number(args: Vec<Value>) -> Result<Value, String> {
                 .map_err(|_| "Failed to convert string to number".to_string())
         }
         Value::Number(n) => Ok(Value::Number(*n)),
+        Value::Integer(n) => Ok(Value::Number(*n as f64)),
         _ => Err("to_number function can only convert strings or numbers".to_string()),
     }
 }
 
-// io TODO: fix this
+fn invalid_character(function: &str, s: &str, index: usize, c: char) -> String {
+    format!("{} function found invalid character '{}' at index {} in \"{}\"", function, c, index, s)
+}
+
+/// Parses a whole string as an integer in base `radix` (2 to 36), e.g. `parse_int("ff", 16)`.
+pub fn std_convert_parse_int(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("parse_int function expects 2 arguments".to_string());
+    }
+    let s = match &args[0] {
+        Value::String(s) => s,
+        _ => return Err("parse_int function expects a string as first argument".to_string()),
+    };
+    let radix = index_argument("parse_int", &args[1])?;
+    if !(2..=36).contains(&radix) {
+        return Err(format!("parse_int function expects a radix between 2 and 36, got {}", radix));
+    }
+
+    let digits_start = usize::from(s.starts_with(['+', '-']));
+    if s.len() == digits_start {
+        return Err(format!("parse_int function expects digits, got \"{}\"", s));
+    }
+    if let Some((index, c)) = s.chars().enumerate().skip(digits_start).find(|(_, c)| !c.is_digit(radix as u32)) {
+        return Err(invalid_character("parse_int", s, index, c));
+    }
+    i64::from_str_radix(s, radix as u32)
+        .map(Value::Integer)
+        .map_err(|_| format!("parse_int function cannot fit \"{}\" in a 64-bit integer", s))
+}
+
+/// Parses a decimal float with an optional sign, fraction and exponent, e.g. `-1.5e3`.
+pub fn std_convert_parse_float(args: Vec<Value>) -> Result<Value, String> {
+    let s = string_argument("parse_float", &args)?;
+    // Checked by hand, `str::parse` does not say where the input went wrong
+    let mut seen_digit = false;
+    let mut seen_dot = false;
+    let mut exponent_at = None;
+    for (index, c) in s.chars().enumerate() {
+        let valid = match c {
+            '0'..='9' => {
+                seen_digit = true;
+                true
+            }
+            '+' | '-' => index == 0 || exponent_at == Some(index - 1),
+            '.' if !seen_dot && exponent_at.is_none() => {
+                seen_dot = true;
+                true
+            }
+            'e' | 'E' if seen_digit && exponent_at.is_none() => {
+                exponent_at = Some(index);
+                // The exponent needs digits of its own
+                seen_digit = false;
+                true
+            }
+            _ => false,
+        };
+        if !valid {
+            return Err(invalid_character("parse_float", s, index, c));
+        }
+    }
+    if !seen_digit {
+        return Err(format!("parse_float function expects digits, got \"{}\"", s));
+    }
+    s.parse::<f64>()
+        .map(Value::Number)
+        .map_err(|_| format!("parse_float function cannot parse \"{}\"", s))
+}
+
+// io
+thread_local! {
+    // Set while output is buffered, otherwise std_io writes straight to stdout
+    static OUTPUT_BUFFER: RefCell<Option<BufWriter<Stdout>>> = const { RefCell::new(None) };
+}
+
+/// Collects everything std_io prints in a buffer until `flush_output` is called, instead of
+/// writing to stdout on every call. Turning buffering off flushes the buffer.
+pub fn set_buffered_output(buffered: bool) -> Result<(), String> {
+    OUTPUT_BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        if buffered {
+            buffer.get_or_insert_with(|| BufWriter::new(std::io::stdout()));
+            Ok(())
+        } else if let Some(mut writer) = buffer.take() {
+            writer.flush().map_err(|e| format!("Failed to flush output: {}", e))
+        } else {
+            Ok(())
+        }
+    })
+}
+
+/// Writes out any buffered output. The process exit does not do this, so callers must.
+pub fn flush_output() -> Result<(), String> {
+    OUTPUT_BUFFER.with(|buffer| match buffer.borrow_mut().as_mut() {
+        Some(writer) => writer.flush(),
+        None => std::io::stdout().flush(),
+    }).map_err(|e| format!("Failed to flush output: {}", e))
+}
+
+fn write_output(text: &str) -> Result<(), String> {
+    OUTPUT_BUFFER.with(|buffer| match buffer.borrow_mut().as_mut() {
+        Some(writer) => writer.write_all(text.as_bytes()),
+        None => std::io::stdout().write_all(text.as_bytes()),
+    }).map_err(|e| format!("Failed to write output: {}", e))
+}
+
+// Values are written as `Display` shows them, strings without quotes and `nun` as `nun`
 pub fn std_io_print(args: Vec<Value>) -> Result<Value, String> {
     for arg in args {
-        print!("{:?}", arg);
+        write_output(&arg.to_string())?;
     }
     Ok(Value::Nun)
 }
 
+pub fn std_io_println(args: Vec<Value>) -> Result<Value, String> {
+    std_io_print(args)?;
+    write_output("\n")?;
+    Ok(Value::Nun)
+}
+
+pub fn std_io_flush(args: Vec<Value>) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("flush function expects no arguments".to_string());
+    }
+    flush_output()?;
+    Ok(Value::Nun)
+}
+
+/// Reads one line without its `\n` or `\r\n` ending, or `nun` at the end of the input.
+pub fn read_line(reader: &mut impl BufRead) -> Result<Value, String> {
+    let mut line = String::new();
+    let read = reader.read_line(&mut line).map_err(|e| format!("read_line function could not read input: {}", e))?;
+    if read == 0 {
+        return Ok(Value::Nun);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(Value::String(line))
+}
+
+/// Reads the rest of the input, or returns `nun` if nothing is left.
+pub fn read_all(reader: &mut impl Read) -> Result<Value, String> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text).map_err(|e| format!("read_all function could not read input: {}", e))?;
+    Ok(if text.is_empty() { Value::Nun } else { Value::String(text) })
+}
+
+// Both flush pending output first, so a prompt printed without a newline shows before waiting
+pub fn std_io_read_line(args: Vec<Value>) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("read_line function expects no arguments".to_string());
+    }
+    flush_output()?;
+    read_line(&mut std::io::stdin().lock())
+}
+
+pub fn std_io_read_all(args: Vec<Value>) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("read_all function expects no arguments".to_string());
+    }
+    flush_output()?;
+    read_all(&mut std::io::stdin().lock())
+}
+
+fn ansi_color_code(color: &str) -> Option<u8> {
+    let code = match color {
+        "black" => 30,
+        "red" => 31,
+        "green" => 32,
+        "yellow" => 33,
+        "blue" => 34,
+        "magenta" => 35,
+        "cyan" => 36,
+        "white" => 37,
+        _ => return None,
+    };
+    Some(code)
+}
+
+fn ansi_style_code(style: &str) -> Option<u8> {
+    let code = match style {
+        "bold" => 1,
+        "dim" => 2,
+        "italic" => 3,
+        "underline" => 4,
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Wraps `text` in the given ANSI SGR codes, or returns it unchanged when `enabled` is false.
+pub fn ansi_wrap(text: &str, codes: &[u8], enabled: bool) -> String {
+    if !enabled || codes.is_empty() {
+        return text.to_string();
+    }
+    let codes: Vec<String> = codes.iter().map(|code| code.to_string()).collect();
+    format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text)
+}
+
+// Escape codes are only written when stdout is a terminal, so piped output stays plain
+fn print_ansi(text: &str, codes: &[u8]) -> Result<(), String> {
+    write_output(&ansi_wrap(text, codes, std::io::stdout().is_terminal()))
+}
+
+pub fn std_io_print_colored(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("print_colored function expects 2 arguments".to_string());
+    }
+    match (&args[0], &args[1]) {
+        (Value::String(text), Value::String(color)) => {
+            let code = ansi_color_code(color).ok_or_else(|| format!("print_colored function got unknown color '{}'", color))?;
+            print_ansi(text, &[code])?;
+            Ok(Value::Nun)
+        }
+        _ => Err("print_colored function expects string arguments".to_string()),
+    }
+}
+
+/// Options map: `bold`, `dim`, `italic` and `underline` take booleans, `color` a color name.
+pub fn std_io_print_styled(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("styled function expects 2 arguments".to_string());
+    }
+    match (&args[0], &args[1]) {
+        (Value::String(text), Value::Map(options)) => {
+            let mut codes = Vec::new();
+            for (key, value) in options.borrow().iter() {
+                match (key.as_str(), value) {
+                    ("color", Value::String(color)) => {
+                        codes.push(ansi_color_code(color).ok_or_else(|| format!("styled function got unknown color '{}'", color))?);
+                    }
+                    (style, Value::Boolean(enabled)) => {
+                        let code = ansi_style_code(style).ok_or_else(|| format!("styled function got unknown option '{}'", style))?;
+                        if *enabled {
+                            codes.push(code);
+                        }
+                    }
+                    (option, _) => return Err(format!("styled function got an invalid value for option '{}'", option)),
+                }
+            }
+            print_ansi(text, &codes)?;
+            Ok(Value::Nun)
+        }
+        _ => Err("styled function expects a string and an options map".to_string()),
+    }
+}
+
 // control TODO: implement this
 pub fn std_control_if(args: Vec<Value>) -> Result<Value, String> {
     if args.len() != 3 {
@@ -145,53 +1286,41 @@ pub fn std_compare_eq(args: Vec<Value>) -> Result<Value, String> {
     if args.len() != 2 {
         return Err("eq function expects 2 arguments".to_string());
     }
-    Ok(Value::Boolean(args[0] == args[1]))
+    Ok(Value::Boolean(args[0].equals(&args[1])))
 }
 
 pub fn std_compare_neq(args: Vec<Value>) -> Result<Value, String> {
     if args.len() != 2 {
-        return Err("ne function expects 2 arguments".to_string());
+        return Err("neq function expects 2 arguments".to_string());
     }
-    Ok(Value::Boolean(args[0] != args[1]))
+    Ok(Value::Boolean(!args[0].equals(&args[1])))
 }
 
 pub fn std_compare_lt(args: Vec<Value>) -> Result<Value, String> {
-    if args.len() != 2 {
-        return Err("lt function expects 2 arguments".to_string());
-    }
-    match (&args[0], &args[1]) {
-        (Value::Number(a), Value::Number(b)) => Ok(Value::Boolean(a < b)),
-        _ => Err("lt function expects number arguments".to_string()),
+    match operands("lt", &args)? {
+        Operands::Integers(a, b) => Ok(Value::Boolean(a < b)),
+        Operands::Floats(a, b) => Ok(Value::Boolean(a < b)),
     }
 }
 
 pub fn std_compare_gt(args: Vec<Value>) -> Result<Value, String> {
-    if args.len() != 2 {
-        return Err("gt function expects 2 arguments".to_string());
-    }
-    match (&args[0], &args[1]) {
-        (Value::Number(a), Value::Number(b)) => Ok(Value::Boolean(a > b)),
-        _ => Err("gt function expects number arguments".to_string()),
+    match operands("gt", &args)? {
+        Operands::Integers(a, b) => Ok(Value::Boolean(a > b)),
+        Operands::Floats(a, b) => Ok(Value::Boolean(a > b)),
     }
 }
 
 pub fn std_compare_lte(args: Vec<Value>) -> Result<Value, String> {
-    if args.len() != 2 {
-        return Err("le function expects 2 arguments".to_string());
-    }
-    match (&args[0], &args[1]) {
-        (Value::Number(a), Value::Number(b)) => Ok(Value::Boolean(a <= b)),
-        _ => Err("le function expects number arguments".to_string()),
+    match operands("lte", &args)? {
+        Operands::Integers(a, b) => Ok(Value::Boolean(a <= b)),
+        Operands::Floats(a, b) => Ok(Value::Boolean(a <= b)),
     }
 }
 
 pub fn std_compare_gte(args: Vec<Value>) -> Result<Value, String> {
-    if args.len() != 2 {
-        return Err("ge function expects 2 arguments".to_string());
-    }
-    match (&args[0], &args[1]) {
-        (Value::Number(a), Value::Number(b)) => Ok(Value::Boolean(a >= b)),
-        _ => Err("ge function expects number arguments".to_string()),
+    match operands("gte", &args)? {
+        Operands::Integers(a, b) => Ok(Value::Boolean(a >= b)),
+        Operands::Floats(a, b) => Ok(Value::Boolean(a >= b)),
     }
 }
 
@@ -207,6 +1336,16 @@ pub fn std_logic_and(args: Vec<Value>) -> Result<Value, String> {
     }
 }
 
+pub fn std_logic_or(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("or function expects 2 arguments".to_string());
+    }
+    match (&args[0], &args[1]) {
+        (Value::Boolean(a), Value::Boolean(b)) => Ok(Value::Boolean(*a || *b)),
+        _ => Err("or function expects boolean arguments".to_string()),
+    }
+}
+
 pub fn std_logic_not(args: Vec<Value>) -> Result<Value, String> {
     if args.len() != 1 {
         return Err("not function expects 1 argument".to_string());
@@ -249,4 +1388,104 @@ pub fn std_file_write(args: Vec<Value>) -> Result<Value, String> {
         }
         _ => Err("file_write function expects two string arguments".to_string()),
     }
-}
\ No newline at end of file
+}
+
+// env
+pub fn std_env_get(args: Vec<Value>) -> Result<Value, String> {
+    let name = string_argument("env_get", &args)?;
+    match std::env::var(name) {
+        Ok(value) => Ok(Value::String(value)),
+        Err(VarError::NotPresent) => Ok(Value::Nun),
+        Err(VarError::NotUnicode(_)) => Err(format!("env_get function found a value for '{}' that is not valid UTF-8", name)),
+    }
+}
+
+pub fn std_env_set(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 2 {
+        return Err("env_set function expects 2 arguments".to_string());
+    }
+    match (&args[0], &args[1]) {
+        // `set_var` panics on these instead of returning an error
+        (Value::String(name), Value::String(_)) if name.is_empty() || name.contains(['=', '\0']) => {
+            Err(format!("env_set function expects a variable name without '=' or NUL, got {:?}", name))
+        },
+        (Value::String(_), Value::String(value)) if value.contains('\0') => {
+            Err("env_set function expects a value without NUL".to_string())
+        },
+        (Value::String(name), Value::String(value)) => {
+            std::env::set_var(name, value);
+            Ok(Value::Nun)
+        },
+        _ => Err("env_set function expects two string arguments".to_string()),
+    }
+}
+
+// process
+thread_local! {
+    // The status passed to `exit` while its error unwinds to the program's entry point
+    static EXIT_STATUS: Cell<Option<i32>> = const { Cell::new(None) };
+}
+
+/// The status passed to `exit`, if its error is on the way out of the program.
+pub fn exit_requested() -> Option<i32> {
+    EXIT_STATUS.with(Cell::get)
+}
+
+// Called where a program's error leaves the interpreter, see `PelinError::Exit`
+pub(crate) fn take_exit_status() -> Option<i32> {
+    EXIT_STATUS.with(Cell::take)
+}
+
+/// Ends the program with `status`: fails with an error that nothing inside the program
+/// catches and that reaches the embedder as `PelinError::Exit`.
+/// The exit status a value stands for, `None` unless it is a whole number that fits an `i32`.
+pub fn status_code(value: &Value) -> Option<i32> {
+    match value {
+        Value::Integer(n) => i32::try_from(*n).ok(),
+        Value::Number(n) if n.fract() == 0.0 && *n >= i32::MIN as f64 && *n <= i32::MAX as f64 => Some(*n as i32),
+        _ => None,
+    }
+}
+
+pub fn std_process_exit(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("exit function expects 1 argument".to_string());
+    }
+    let code = status_code(&args[0])
+        .ok_or_else(|| format!("exit function expects a whole number status code, got {}", args[0]))?;
+    EXIT_STATUS.with(|status| status.set(Some(code)));
+    Err(format!("Program exited with status {}", code))
+}
+
+// time
+// Both clocks count seconds. `monotonic` starts at an arbitrary point, it is only for durations.
+pub fn std_time_now(args: Vec<Value>) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("time_now function expects no arguments".to_string());
+    }
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("time_now function found the system clock before 1970: {}", e))?;
+    Ok(Value::Number(since_epoch.as_secs_f64()))
+}
+
+pub fn std_time_monotonic(args: Vec<Value>) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("time_monotonic function expects no arguments".to_string());
+    }
+    static START: OnceLock<Instant> = OnceLock::new();
+    Ok(Value::Number(START.get_or_init(Instant::now).elapsed().as_secs_f64()))
+}
+
+pub fn std_time_sleep(args: Vec<Value>) -> Result<Value, String> {
+    if args.len() != 1 {
+        return Err("time_sleep function expects 1 argument".to_string());
+    }
+    // Negative, non-finite and too long durations all fail the conversion
+    match args[0].as_number().ok().and_then(|ms| Duration::try_from_secs_f64(ms / 1000.0).ok()) {
+        Some(duration) => {
+            thread::sleep(duration);
+            Ok(Value::Nun)
+        },
+        _ => Err(format!("time_sleep function expects a non-negative number of milliseconds, got {}", args[0])),
+    }
+}
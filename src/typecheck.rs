@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use crate::parser::{Expr, Type, UnaryOperator};
+
+/// Flags obvious type mismatches before running a program (`--typecheck`).
+///
+/// This is not a type system: only literal arguments and literal return values are compared with
+/// the declared parameter and return types, and calls are only checked when the callee is a
+/// function defined in the program under a unique name. Everything else is assumed to be fine.
+/// Only what the runtime would also reject is flagged.
+pub fn check_program(expressions: &[Expr]) -> Result<(), Vec<String>> {
+    let mut signatures = HashMap::new();
+    let mut ambiguous = Vec::new();
+    for expr in expressions {
+        collect_signatures(expr, &mut signatures, &mut ambiguous);
+    }
+    for name in ambiguous {
+        signatures.remove(&name);
+    }
+
+    let mut checker = Checker { signatures, shadowed: Vec::new(), errors: Vec::new() };
+    for (i, expr) in expressions.iter().enumerate() {
+        checker.check(expr, &format!("top-level expression {}", i + 1));
+    }
+    if checker.errors.is_empty() { Ok(()) } else { Err(checker.errors) }
+}
+
+/// Declared parameters of a function
+type Signature = Vec<(Type, String)>;
+
+fn collect_signatures(expr: &Expr, signatures: &mut HashMap<String, Signature>, ambiguous: &mut Vec<String>) {
+    if let Expr::FunctionDefinition { name, parameters, body, .. } = expr {
+        if signatures.insert(name.clone(), parameters.clone()).is_some() {
+            ambiguous.push(name.clone());
+        }
+        for expr in body {
+            collect_signatures(expr, signatures, ambiguous);
+        }
+    }
+}
+
+struct Checker {
+    signatures: HashMap<String, Signature>,
+    // Parameter names of the enclosing functions, which hide functions of the same name
+    shadowed: Vec<String>,
+    errors: Vec<String>,
+}
+
+impl Checker {
+    fn check(&mut self, expr: &Expr, location: &str) {
+        match expr {
+            Expr::Number(_) | Expr::Integer(_) | Expr::String(_) | Expr::Boolean(_) | Expr::Nun
+            | Expr::Identifier(_) | Expr::Import { .. } | Expr::Include(_) | Expr::FeatherFunction { .. } => {},
+            Expr::Array(elements) | Expr::Block(elements) => {
+                for element in elements {
+                    self.check(element, location);
+                }
+            },
+            Expr::Map(entries) => {
+                for (_, value) in entries {
+                    self.check(value, location);
+                }
+            },
+            Expr::Unary { operator, operand } => {
+                match (operator, literal_type(operand)) {
+                    (UnaryOperator::Negate, Some(found)) if found != "num" && found != "int" => {
+                        self.errors.push(format!("In {}: '-' applied to a {} literal", location, found));
+                    },
+                    (UnaryOperator::Not, Some(found)) if found != "bool" => {
+                        self.errors.push(format!("In {}: '!' applied to a {} literal", location, found));
+                    },
+                    _ => {},
+                }
+                self.check(operand, location);
+            },
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.check(left, location);
+                self.check(right, location);
+            },
+            Expr::If { condition, then_branch, else_branch, .. } => {
+                if let Some(found) = literal_type(condition).filter(|found| *found != "bool") {
+                    self.errors.push(format!("In {}: 'if' condition is a {} literal", location, found));
+                }
+                self.check(condition, location);
+                for expr in then_branch.iter().chain(else_branch.iter().flatten()) {
+                    self.check(expr, location);
+                }
+            },
+            Expr::While { condition, body, .. } => {
+                if let Some(found) = literal_type(condition).filter(|found| *found != "bool") {
+                    self.errors.push(format!("In {}: 'while' condition is a {} literal", location, found));
+                }
+                self.check(condition, location);
+                for expr in body {
+                    self.check(expr, location);
+                }
+            },
+            Expr::ForEach { iterable, body, .. } => {
+                self.check(iterable, location);
+                for expr in body {
+                    self.check(expr, location);
+                }
+            },
+            Expr::Index { target, index } => {
+                self.check(target, location);
+                self.check(index, location);
+            },
+            Expr::Return(value) | Expr::Assignment { value, .. } | Expr::Let { value, .. } => self.check(value, location),
+            Expr::FunctionCall { callee, arguments } => {
+                if let Expr::Identifier(name) = callee.as_ref() {
+                    self.check_call(name, arguments, location);
+                }
+                self.check(callee, location);
+                for argument in arguments {
+                    self.check(argument, location);
+                }
+            },
+            Expr::RustFunctionCall { arguments, .. } | Expr::FeatherFunctionCall { arguments, .. } => {
+                for argument in arguments {
+                    self.check(argument, location);
+                }
+            },
+            Expr::FunctionDefinition { return_type, name, parameters, body } => {
+                let location = format!("function '{}'", name);
+                self.check_returns(return_type, body, &location);
+                let shadowed = self.shadowed.len();
+                self.shadowed.extend(parameters.iter().map(|(_, param)| param.clone()));
+                for expr in body {
+                    self.check(expr, &location);
+                }
+                self.shadowed.truncate(shadowed);
+            },
+        }
+    }
+
+    fn check_call(&mut self, name: &str, arguments: &[Expr], location: &str) {
+        if self.shadowed.iter().any(|param| param == name) {
+            return;
+        }
+        let Some(parameters) = self.signatures.get(name) else {
+            return;
+        };
+        if parameters.len() != arguments.len() {
+            self.errors.push(format!(
+                "In {}: '{}' expects {} arguments but is called with {}",
+                location, name, parameters.len(), arguments.len()
+            ));
+            return;
+        }
+        for ((declared, param), argument) in parameters.iter().zip(arguments) {
+            if let Some(found) = literal_type(argument) {
+                if accepts(declared, found) == Some(false) {
+                    self.errors.push(format!(
+                        "In {}: '{}' expects {} for parameter '{}' but is passed a {} literal",
+                        location, name, declared, param, found
+                    ));
+                }
+            }
+        }
+    }
+
+    // Explicit returns directly in the body and the implicit result of the last expression.
+    // The runtime does not check what a `nun` function returns.
+    fn check_returns(&mut self, return_type: &Type, body: &[Box<Expr>], location: &str) {
+        if *return_type == Type::Nun {
+            return;
+        }
+        let returned = body.iter().filter_map(|expr| match expr.as_ref() {
+            Expr::Return(value) => Some(value.as_ref()),
+            _ => None,
+        });
+        let last = body.last().map(|expr| expr.as_ref()).filter(|expr| !matches!(expr, Expr::Return(_)));
+        for value in returned.chain(last) {
+            if let Some(found) = literal_type(value) {
+                if accepts(return_type, found) == Some(false) {
+                    self.errors.push(format!(
+                        "In {}: declared to return {} but returns a {} literal",
+                        location, return_type, found
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Runtime type name of a literal expression, as returned by `Value::type_name`.
+fn literal_type(expr: &Expr) -> Option<&'static str> {
+    match expr {
+        Expr::Number(_) => Some("num"),
+        Expr::Integer(_) => Some("int"),
+        Expr::String(_) => Some("str"),
+        Expr::Boolean(_) => Some("bool"),
+        Expr::Nun => Some("nun"),
+        Expr::Array(_) => Some("array"),
+        Expr::Map(_) => Some("map"),
+        Expr::Unary { operator: UnaryOperator::Not, .. } => Some("bool"),
+        Expr::Unary { operand, .. } => literal_type(operand).filter(|found| *found == "num" || *found == "int"),
+        _ => None,
+    }
+}
+
+/// Whether a declared type accepts a literal type, `None` for custom types such as `int` or
+/// `array`, which the runtime does not check either.
+fn accepts(declared: &Type, found: &str) -> Option<bool> {
+    match declared {
+        Type::Num => Some(found == "num" || found == "int"),
+        Type::Str => Some(found == "str"),
+        Type::Bool => Some(found == "bool"),
+        Type::Nun => Some(found == "nun"),
+        Type::Custom(_) => None,
+    }
+}
@@ -0,0 +1,436 @@
+use std::fs;
+use std::process::Command;
+
+fn pelin() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_pelin"))
+}
+
+#[test]
+fn test_call_main_sets_exit_code() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.pl"), "fn num main() { return 3 }").unwrap();
+
+    let status = pelin().current_dir(dir.path()).args(["--call-main", "prog.pl"]).status().unwrap();
+    assert_eq!(status.code(), Some(3));
+
+    let status = pelin().current_dir(dir.path()).arg("prog.pl").status().unwrap();
+    assert_eq!(status.code(), Some(0));
+}
+
+#[test]
+fn test_call_main_rejects_exit_codes_that_are_not_whole_or_too_large() {
+    let dir = tempfile::tempdir().unwrap();
+    for (result, shown) in [("2.7", "2.7"), ("4294967296i", "4294967296")] {
+        fs::write(dir.path().join("prog.pl"), format!("fn any main() {{ return {} }}", result)).unwrap();
+
+        let output = pelin().current_dir(dir.path()).args(["--call-main", "prog.pl"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let expected = format!("main must return a whole number exit code, got {}", shown);
+        assert!(String::from_utf8_lossy(&output.stderr).contains(&expected), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+}
+
+#[test]
+fn test_exit_sets_exit_code_and_flushes_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = "imp std_process\nRUST[std_func::print](\"bye\")\nstd_process.exit(4)\nRUST[std_func::print](\"unreachable\")";
+    fs::write(dir.path().join("prog.pl"), source).unwrap();
+    fs::create_dir(dir.path().join("feathers")).unwrap();
+    fs::copy(concat!(env!("CARGO_MANIFEST_DIR"), "/feathers/std_process.pl"), dir.path().join("feathers/std_process.pl")).unwrap();
+
+    let output = pelin().current_dir(dir.path()).args(["--buffered-output", "prog.pl"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "bye");
+}
+
+#[test]
+fn test_unknown_option_fails() {
+    let output = pelin().arg("--no-such-flag").output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown option: --no-such-flag"));
+}
+
+#[test]
+fn test_runs_main_pl_without_arguments() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("main.pl"), "RUST[std_func::file_write](\"ran.txt\", \"yes\")").unwrap();
+
+    let status = pelin().current_dir(dir.path()).status().unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read_to_string(dir.path().join("ran.txt")).unwrap(), "yes");
+}
+
+#[test]
+fn test_no_arguments_without_main_pl_starts_repl() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = pelin().current_dir(dir.path()).env("PELIN_HISTORY", dir.path().join("history")).output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("REPL"));
+}
+
+#[test]
+fn test_print_and_println_write_display_form() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.pl"), r#"
+        RUST[std_func::print]("a")
+        RUST[std_func::println](5)
+        RUST[std_func::println](nun)
+        RUST[std_func::println](["b", 1.5])
+    "#).unwrap();
+
+    let output = pelin().current_dir(dir.path()).arg("prog.pl").output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "a5\nnun\n[\"b\", 1.5]\n");
+}
+
+#[test]
+fn test_arguments_after_the_file_are_passed_to_the_program() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.pl"), "RUST[std_func::println](args)\nRUST[std_func::println](RUST[std_func::array_len](args))").unwrap();
+
+    let output = pelin().current_dir(dir.path()).args(["prog.pl", "one", "--two"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[\"one\", \"--two\"]\n2\n");
+
+    let output = pelin().current_dir(dir.path()).arg("prog.pl").output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[]\n0\n");
+}
+
+#[test]
+fn test_read_line_reads_stdin_until_end() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.pl"), r#"
+        let line = RUST[std_func::read_line]()
+        while (line != nun) {
+            RUST[std_func::println](RUST[std_func::str_to_upper](line))
+            line = RUST[std_func::read_line]()
+        }
+    "#).unwrap();
+
+    let mut child = pelin()
+        .current_dir(dir.path())
+        .arg("prog.pl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"a\nb\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "A\nB\n");
+}
+
+#[test]
+fn test_infinite_recursion_fails_without_crashing() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.pl"), "fn num forever(num n) { forever(n + 1) }\nforever(0)").unwrap();
+
+    let output = pelin().current_dir(dir.path()).arg("prog.pl").output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Maximum recursion depth (1000) exceeded"));
+}
+
+#[test]
+fn test_profile_reports_call_counts() {
+    let dir = tempfile::tempdir().unwrap();
+    let program = r#"
+        fn num hot(num x) { return x }
+        fn num cold() { return hot(hot(1)) }
+        hot(1)
+        cold()
+    "#;
+    fs::write(dir.path().join("prog.pl"), program).unwrap();
+
+    let output = pelin().current_dir(dir.path()).args(["--profile", "prog.pl"]).output().unwrap();
+    assert!(output.status.success());
+    let report = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<&str> = report.lines().collect();
+    assert!(lines[0].contains("calls"));
+    assert!(lines[1].trim_start().starts_with("3 ") && lines[1].ends_with(" hot"), "{}", report);
+    assert!(lines[2].trim_start().starts_with("1 ") && lines[2].ends_with(" cold"), "{}", report);
+}
+
+#[test]
+fn test_colored_output_is_plain_when_piped() {
+    let dir = tempfile::tempdir().unwrap();
+    let program = r#"
+        RUST[std_func::print_colored]("red ", "red")
+        RUST[std_func::print_styled]("bold", { "bold" = true, "color" = "blue" })
+    "#;
+    fs::write(dir.path().join("prog.pl"), program).unwrap();
+
+    let output = pelin().current_dir(dir.path()).arg("prog.pl").output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "red bold");
+}
+
+#[test]
+fn test_print_result_prints_final_value() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("sum.pl"), "RUST[std_func::add](1, 2)").unwrap();
+
+    let output = pelin().current_dir(dir.path()).args(["--print-result", "sum.pl"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+
+    let output = pelin().current_dir(dir.path()).arg("sum.pl").output().unwrap();
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_empty_program_runs_silently() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("empty.pl"), "  \n// nothing yet\n").unwrap();
+
+    let output = pelin().current_dir(dir.path()).arg("empty.pl").output().unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+
+    let output = pelin().current_dir(dir.path()).args(["--print-result", "empty.pl"]).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "nun\n");
+}
+
+#[test]
+fn test_int_literals_flag() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("kind.pl"), "RUST[std_func::is_int](5)").unwrap();
+
+    let output = pelin().current_dir(dir.path()).args(["--print-result", "kind.pl"]).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "false\n");
+
+    let output = pelin().current_dir(dir.path()).args(["--int-literals", "--print-result", "kind.pl"]).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "true\n");
+}
+
+#[test]
+fn test_buffered_output_is_written_at_exit() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("out.pl"), "RUST[std_func::print_colored](\"buffered\", \"red\")").unwrap();
+
+    let output = pelin().current_dir(dir.path()).args(["--buffered-output", "out.pl"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "buffered");
+}
+
+#[test]
+fn test_flush_writes_buffered_output_mid_run() {
+    // Unbounded recursion fails with an error rather than crashing, so the buffer is written
+    // when the program ends whether or not it was flushed
+    let crash = "fn nun forever() { forever() }\nforever()";
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("flushed.pl"), format!("RUST[std_func::print_colored](\"early\", \"red\")\nRUST[std_func::flush]()\n{}", crash)).unwrap();
+    fs::write(dir.path().join("unflushed.pl"), format!("RUST[std_func::print_colored](\"early\", \"red\")\n{}", crash)).unwrap();
+
+    let output = pelin().current_dir(dir.path()).args(["--buffered-output", "flushed.pl"]).output().unwrap();
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "early");
+
+    let output = pelin().current_dir(dir.path()).args(["--buffered-output", "unflushed.pl"]).output().unwrap();
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "early");
+}
+
+#[test]
+fn test_typecheck_flag_stops_before_running() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("prog.pl"), "fn num id(num x) { x }\nRUST[std_func::file_write](\"ran.txt\", \"yes\")\nid(\"1\")").unwrap();
+
+    let output = pelin().current_dir(dir.path()).args(["--typecheck", "prog.pl"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Type check failed"));
+    assert!(!dir.path().join("ran.txt").exists());
+}
+
+#[test]
+fn test_typecheck_agrees_with_runtime() {
+    let dir = tempfile::tempdir().unwrap();
+    let run = |program: &str, typecheck: bool| {
+        fs::write(dir.path().join("prog.pl"), program).unwrap();
+        let mut command = pelin();
+        command.current_dir(dir.path());
+        if typecheck {
+            command.arg("--typecheck");
+        }
+        command.arg("prog.pl").status().unwrap().success()
+    };
+
+    // Neither checks custom types nor the result of a `nun` function
+    let unchecked = "fn int f() { 1.5 }\nfn nun g() { 5 }\nf()\ng()";
+    assert!(run(unchecked, false));
+    assert!(run(unchecked, true));
+
+    let mismatch = "fn num f() { \"1\" }\nf()";
+    assert!(!run(mismatch, false));
+    assert!(!run(mismatch, true));
+}
+
+#[test]
+fn test_import_flag_preloads_feathers() {
+    let dir = tempfile::tempdir().unwrap();
+    let program = dir.path().join("sine.pl");
+    fs::write(&program, "std_math.sin(0)").unwrap();
+
+    // The repository root, so the standard feathers are found
+    let root = env!("CARGO_MANIFEST_DIR");
+    let output = pelin().current_dir(root).args(["--import", "std_math", "--print-result"]).arg(&program).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "0\n");
+
+    let output = pelin().current_dir(root).arg(&program).output().unwrap();
+    assert!(!output.status.success());
+
+    let output = pelin().current_dir(root).arg("--import").output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Missing feather name after --import"));
+}
+
+#[test]
+fn test_repl_paste_mode_and_history() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = tempfile::tempdir().unwrap();
+    let history = dir.path().join("history");
+    let session = ":paste\nfn num twice(num x) {\n\n    RUST[std_func::multiply](x, 2)\n}\n:end\ntwice(21)\nexit\n";
+
+    let mut child = pelin()
+        .current_dir(dir.path())
+        .env("PELIN_HISTORY", &history)
+        .arg("--repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(session.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().last(), Some("42"));
+    let saved = fs::read_to_string(&history).unwrap();
+    assert!(saved.contains("twice(21)"), "{}", saved);
+}
+
+#[test]
+fn test_repl_continues_unbalanced_braces_and_keeps_state() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = tempfile::tempdir().unwrap();
+    let session = "let total = 40\nfn num add_to_total(num x) {\n    // a } in a comment\n    total = total + x\n}\nadd_to_total(2)\n\"{\"\ntotal\n";
+
+    let mut child = pelin()
+        .current_dir(dir.path())
+        .env("PELIN_HISTORY", dir.path().join("history"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(session.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let values: Vec<&str> = stdout.lines().skip(1).collect();
+    assert_eq!(values, vec!["40", "42", "{", "42"]);
+}
+
+#[test]
+fn test_watch_reruns_after_change() {
+    use std::io::{BufRead, BufReader, Read};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    let dir = tempfile::tempdir().unwrap();
+    let program = dir.path().join("prog.pl");
+    fs::write(&program, "RUST[std_func::add](1, 2)").unwrap();
+
+    let mut child = pelin()
+        .current_dir(dir.path())
+        .args(["--watch", "--print-result", "prog.pl"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Lines from both streams, tagged with whether they came from stdout
+    let (sender, lines) = mpsc::channel();
+    fn forward(stream: impl Read + Send + 'static, is_stdout: bool, sender: mpsc::Sender<(bool, String)>) {
+        std::thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                if sender.send((is_stdout, line.unwrap())).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    forward(child.stdout.take().unwrap(), true, sender.clone());
+    forward(child.stderr.take().unwrap(), false, sender);
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut results = Vec::new();
+    let mut watching = false;
+    // The file is changed only once the watch is set up, and again until the rerun shows up,
+    // in case a change lands before the watcher sees it
+    while results.len() < 2 && Instant::now() < deadline {
+        if watching && !results.is_empty() {
+            fs::write(&program, "RUST[std_func::add](2, 3)").unwrap();
+        }
+        match lines.recv_timeout(Duration::from_millis(200)) {
+            Ok((true, line)) => results.push(line),
+            Ok((false, line)) => watching |= line.starts_with("Watching"),
+            Err(_) => {}
+        }
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert_eq!(results, vec!["3", "5"]);
+}
+
+#[test]
+fn test_profile_calls_output_is_reproducible() {
+    let dir = tempfile::tempdir().unwrap();
+    let program = r#"
+        fn num b(num x) { return x }
+        fn num a(num x) { return b(x) }
+        fn num hot(num x) { return x }
+        hot(a(1))
+        hot(2)
+    "#;
+    fs::write(dir.path().join("prog.pl"), program).unwrap();
+
+    let run = || pelin().current_dir(dir.path()).args(["--profile-calls", "prog.pl"]).output().unwrap().stderr;
+    let first = run();
+    assert_eq!(first, run());
+    // Ties in the call count are ordered by name
+    assert_eq!(String::from_utf8_lossy(&first), "calls  function\n    2  hot\n    1  a\n    1  b\n");
+}
+
+#[test]
+fn test_list_functions_output_is_reproducible() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("lib.pl"), "fn str shout(str s) { s }").unwrap();
+    fs::write(dir.path().join("prog.pl"), "imp .lib\nfn num twice(num x) { x }\nfn nun log() { }\nRUST[std_func::file_write](\"ran.txt\", \"yes\")").unwrap();
+
+    let run = || pelin().current_dir(dir.path()).args(["--list-functions", "prog.pl"]).output().unwrap();
+    let first = run();
+    assert!(first.status.success());
+    assert_eq!(first.stdout, run().stdout);
+    assert_eq!(String::from_utf8_lossy(&first.stdout), concat!(
+        "function   returns  parameters\n",
+        "lib.shout  str      str s\n",
+        "log        nun\n",
+        "twice      num      num x\n",
+    ));
+    // Listing does not run the program
+    assert!(!dir.path().join("ran.txt").exists());
+}
@@ -1,15 +1,19 @@
+use std::cell::RefCell;
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
-use pelin::feather::FeatherManager;
-use pelin::interpreter::Value;
+use std::rc::Rc;
+use pelin::feather::{FeatherManager, NunMode};
+use pelin::interpreter::{Interpreter, Value};
+use pelin::lexer::Lexer;
+use pelin::parser::{Expr, Parser};
 
 #[test]
 fn test_import_nonexistent_file() {
     let temp_dir = tempfile::tempdir().unwrap();
     let project_root = temp_dir.path().to_path_buf();
-    let mut manager = FeatherManager::new(project_root);
-    assert!(manager.import("nonexistent").is_err());
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root)));
+    assert!(FeatherManager::import(&manager, "nonexistent").is_err());
 }
 
 #[test]
@@ -23,10 +27,11 @@ fn test_feather_manager_creation() {
 
 #[test]
 fn test_import_non_existent_feather() {
-    let project_root = PathBuf::from("/tmp/test_project");
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project_root = temp_dir.path().to_path_buf();
     let root_dir = project_root.clone();
-    let mut manager = FeatherManager::new(project_root);
-    let result = manager.import("non_existent_feather");
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root)));
+    let result = FeatherManager::import(&manager, "non_existent_feather");
     assert!(result.is_err());
     let expected_error = format!(
         "Could not find Feather file: {}",
@@ -53,16 +58,99 @@ fn test_import_and_call_function() {
     file.write_all(feather_content.as_bytes()).unwrap();
 
     // Create FeatherManager and import the test feather
-    let mut manager = FeatherManager::new(project_root.path().to_path_buf());
-    let import_result = manager.import("test_math");
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    let import_result = FeatherManager::import(&manager, "test_math");
     assert!(import_result.is_ok());
 
     // Test calling the imported function
-    let result = manager.call_function("test_math", "add", vec![Value::Number(2.0), Value::Number(3.0)]);
+    let result = manager.borrow().call_function("test_math", "add", vec![Value::Number(2.0), Value::Number(3.0)]);
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), Value::Number(5.0));
 }
 
+#[test]
+fn test_import_relative_feather() {
+    let project_root = tempfile::tempdir().unwrap();
+    let feather_content = r#"
+    fn num add(num a, num b) {
+        RUST[std_func::add](a, b)
+    }
+    "#;
+    fs::write(project_root.path().join("local.pl"), feather_content).unwrap();
+
+    let mut lexer = Lexer::new("imp .local");
+    let tokens = lexer.tokenize().unwrap();
+    let expressions = Parser::new(tokens).parse().unwrap();
+    let name = match &expressions[0] {
+        Expr::Import { name, .. } => name.clone(),
+        other => panic!("Expected import, got {:?}", other),
+    };
+
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    assert!(FeatherManager::import(&manager, &name).is_ok());
+
+    let result = manager.borrow().call_function("local", "add", vec![Value::Number(1.0), Value::Number(2.0)]);
+    assert_eq!(result, Ok(Value::Number(3.0)));
+}
+
+#[test]
+fn test_import_pure_pelikan_feather_without_rust_libs() {
+    let project_root = tempfile::tempdir().unwrap();
+    let feathers_dir = project_root.path().join("feathers");
+    fs::create_dir(&feathers_dir).unwrap();
+    let feather_content = r#"
+    fn num identity(num x) {
+        return x
+    }
+
+    fn num double(num x) {
+        return RUST[std_func::add](identity(x), x)
+    }
+
+    fn num quadruple(num x) {
+        double(double(x))
+    }
+    "#;
+    fs::write(feathers_dir.join("pure.pl"), feather_content).unwrap();
+    assert!(!project_root.path().join("rust_libs").exists());
+
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    assert!(FeatherManager::import(&manager, "pure").is_ok());
+
+    let result = manager.borrow().call_function("pure", "quadruple", vec![Value::Number(2.0)]);
+    assert_eq!(result, Ok(Value::Number(8.0)));
+}
+
+#[test]
+fn test_feather_function_with_local_variables() {
+    let project_root = tempfile::tempdir().unwrap();
+    let feathers_dir = project_root.path().join("feathers");
+    fs::create_dir(&feathers_dir).unwrap();
+    let feather_content = r#"
+    let offset = 1
+
+    fn num square(num x) {
+        x * x
+    }
+
+    fn num shifted_square(num x) {
+        let result = square(x)
+        result = result + offset
+        if (result > 100) {
+            return 100
+        }
+        return result * 2
+    }
+    "#;
+    fs::write(feathers_dir.join("local.pl"), feather_content).unwrap();
+
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    FeatherManager::import(&manager, "local").unwrap();
+
+    assert_eq!(manager.borrow().call_function("local", "shifted_square", vec![Value::Number(3.0)]), Ok(Value::Number(20.0)));
+    assert_eq!(manager.borrow().call_function("local", "shifted_square", vec![Value::Number(20.0)]), Ok(Value::Number(100.0)));
+}
+
 #[test]
 fn test_call_non_existent_function() {
     let project_root = PathBuf::from("/tmp/test_project");
@@ -73,4 +161,288 @@ fn test_call_non_existent_function() {
         result.unwrap_err(),
         "Feather 'test_math' not found"
     );
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_missing_project_root_is_reported() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let missing = temp_dir.path().join("no_such_dir");
+    let expected = format!("Project root {} does not exist", missing.display());
+    assert_eq!(FeatherManager::try_new(missing.clone()).err(), Some(expected.clone()));
+
+    // `new` still builds a manager, the problem surfaces on import instead of a missing file
+    let manager = Rc::new(RefCell::new(FeatherManager::new(missing)));
+    assert_eq!(FeatherManager::import(&manager, "std_num"), Err(format!("Could not import feather 'std_num': {}", expected)));
+
+    let file = temp_dir.path().join("file.txt");
+    fs::write(&file, "").unwrap();
+    assert_eq!(
+        FeatherManager::try_new(file.clone()).err(),
+        Some(format!("Project root {} is not a directory", file.display()))
+    );
+    assert!(FeatherManager::try_new(temp_dir.path().to_path_buf()).is_ok());
+}
+
+#[test]
+fn test_strict_nun_arithmetic_errors() {
+    let manager = FeatherManager::new(PathBuf::from("/tmp/test_project"));
+    let result = manager.call_function("std_func", "add", vec![Value::Nun, Value::Number(5.0)]);
+    assert_eq!(result, Err("add function expects number arguments".to_string()));
+}
+
+#[test]
+fn test_propagating_nun_arithmetic() {
+    let mut manager = FeatherManager::new(PathBuf::from("/tmp/test_project"));
+    manager.set_nun_mode(NunMode::Propagate);
+    let result = manager.call_function("std_func", "add", vec![Value::Nun, Value::Number(5.0)]);
+    assert_eq!(result, Ok(Value::Nun));
+
+    // Non-arithmetic functions keep their usual behaviour
+    let result = manager.call_function("std_func", "eq", vec![Value::Nun, Value::Nun]);
+    assert_eq!(result, Ok(Value::Boolean(true)));
+}
+
+#[test]
+fn test_validate_all_reports_only_broken_feathers() {
+    let project_root = tempfile::tempdir().unwrap();
+    let feathers_dir = project_root.path().join("feathers");
+    fs::create_dir(&feathers_dir).unwrap();
+    fs::write(feathers_dir.join("valid.pl"), "fn num one() { return 1 }").unwrap();
+    fs::write(feathers_dir.join("broken.pl"), "fn num one( { return 1 }").unwrap();
+    fs::write(feathers_dir.join("notes.txt"), "not a feather (").unwrap();
+
+    let manager = FeatherManager::new(project_root.path().to_path_buf());
+    let failures = manager.validate_all().unwrap();
+
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].0, feathers_dir.join("broken.pl"));
+}
+
+// The example library is built as part of the test targets, next to the test binaries
+fn native_example_library() -> PathBuf {
+    let target_dir = std::env::current_exe().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    target_dir.join("examples").join(native_library_file("native_collections"))
+}
+
+fn native_library_file(name: &str) -> String {
+    format!("{}{}{}", std::env::consts::DLL_PREFIX, name, std::env::consts::DLL_SUFFIX)
+}
+
+#[test]
+fn test_native_function_returns_array_and_map() {
+    let project_root = tempfile::tempdir().unwrap();
+    let libs_dir = project_root.path().join("rust_libs");
+    fs::create_dir(&libs_dir).unwrap();
+    fs::copy(native_example_library(), libs_dir.join(native_library_file("native_collections"))).unwrap();
+    let manager = FeatherManager::new(project_root.path().to_path_buf());
+
+    let numbers = manager.call_rust_function("native_collections::numbers", vec![Value::Number(4.0)]).unwrap();
+    let Value::Array(elements) = &numbers else {
+        panic!("expected an array, got {:?}", numbers);
+    };
+    assert_eq!(*elements.borrow(), vec![Value::Number(1.0), Value::Number(2.0), Value::Number(3.0), Value::Number(4.0)]);
+
+    let summary = manager.call_rust_function("native_collections::summary", vec![numbers]).unwrap();
+    let Value::Map(entries) = &summary else {
+        panic!("expected a map, got {:?}", summary);
+    };
+    assert_eq!(entries.borrow().get("count"), Some(&Value::Number(4.0)));
+    assert_eq!(entries.borrow().get("sum"), Some(&Value::Number(10.0)));
+}
+
+#[test]
+fn test_rust_path_with_one_segment_is_explained() {
+    let manager = FeatherManager::new(PathBuf::from("/tmp/test_project"));
+    let err = manager.call_rust_function("add", vec![Value::Number(1.0)]).unwrap_err();
+    assert!(err.contains("'add'"), "{}", err);
+    assert!(err.contains("expected the form library::function"), "{}", err);
+}
+
+#[test]
+fn test_std_namespace_path_routes_to_std_functions() {
+    let manager = FeatherManager::new(PathBuf::from("/tmp/test_project"));
+    let result = manager.call_rust_function("std_func::add", vec![Value::Number(1.0), Value::Number(2.0)]);
+    assert_eq!(result, Ok(Value::Number(3.0)));
+
+    // Only the exact namespace is routed, a similar library name is loaded from rust_libs
+    let err = manager.call_rust_function("std_funcs::add", vec![]).unwrap_err();
+    assert!(err.contains("Failed to load library 'std_funcs'"), "{}", err);
+}
+
+#[test]
+fn test_circular_imports_are_reported_with_the_chain() {
+    let project_root = tempfile::tempdir().unwrap();
+    let feathers_dir = project_root.path().join("feathers");
+    fs::create_dir(&feathers_dir).unwrap();
+    fs::write(feathers_dir.join("a.pl"), "imp b\nfn num one() { 1 }").unwrap();
+    fs::write(feathers_dir.join("b.pl"), "imp a\nfn num two() { 2 }").unwrap();
+    fs::write(feathers_dir.join("selfish.pl"), "imp selfish").unwrap();
+
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    assert_eq!(FeatherManager::import(&manager, "a"), Err("Circular import detected: a -> b -> a".to_string()));
+    assert_eq!(FeatherManager::import(&manager, "b"), Err("Circular import detected: b -> a -> b".to_string()));
+    assert_eq!(FeatherManager::import(&manager, "selfish"), Err("Circular import detected: selfish -> selfish".to_string()));
+    assert!(manager.borrow().feathers.is_empty());
+}
+
+#[test]
+fn test_library_path_uses_platform_file_name() {
+    let manager = FeatherManager::new(PathBuf::from("/tmp/test_project"));
+    let path = manager.library_path("native");
+    assert!(path.starts_with(PathBuf::from("/tmp/test_project").join("rust_libs")));
+
+    let file_name = path.file_name().unwrap().to_str().unwrap();
+    if cfg!(target_os = "windows") {
+        assert_eq!(file_name, "native.dll");
+    } else if cfg!(target_os = "macos") {
+        assert_eq!(file_name, "libnative.dylib");
+    } else if cfg!(target_os = "linux") {
+        assert_eq!(file_name, "libnative.so");
+    }
+
+    // A missing library names the file it looked for
+    let err = manager.call_rust_function("native::f", vec![]).unwrap_err();
+    assert!(err.contains(&path.display().to_string()), "{}", err);
+}
+
+#[test]
+fn test_importing_a_feather_again_uses_the_loaded_version() {
+    let project_root = tempfile::tempdir().unwrap();
+    let feathers_dir = project_root.path().join("feathers");
+    fs::create_dir(&feathers_dir).unwrap();
+    let feather_path = feathers_dir.join("cached.pl");
+    fs::write(&feather_path, "fn num one() { 1 }").unwrap();
+
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    FeatherManager::import(&manager, "cached").unwrap();
+    // Without the file, a second import can only succeed from the loaded feather
+    fs::remove_file(&feather_path).unwrap();
+    FeatherManager::import(&manager, "cached").unwrap();
+    assert_eq!(manager.borrow().call_function("cached", "one", vec![]), Ok(Value::Number(1.0)));
+}
+
+#[test]
+fn test_importing_a_changed_feather_reloads_it() {
+    let project_root = tempfile::tempdir().unwrap();
+    let feathers_dir = project_root.path().join("feathers");
+    fs::create_dir(&feathers_dir).unwrap();
+    let feather_path = feathers_dir.join("changing.pl");
+    fs::write(&feather_path, "fn num version() { 1 }").unwrap();
+
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    FeatherManager::import(&manager, "changing").unwrap();
+
+    fs::write(&feather_path, "fn num version() { 2 }").unwrap();
+    let later = manager.borrow().feathers["changing"].modified.unwrap() + std::time::Duration::from_secs(10);
+    fs::File::options().write(true).open(&feather_path).unwrap().set_modified(later).unwrap();
+    FeatherManager::import(&manager, "changing").unwrap();
+    assert_eq!(manager.borrow().call_function("changing", "version", vec![]), Ok(Value::Number(2.0)));
+}
+
+#[test]
+fn test_feather_with_many_functions_shares_the_manager() {
+    let project_root = tempfile::tempdir().unwrap();
+    let feathers_dir = project_root.path().join("feathers");
+    fs::create_dir(&feathers_dir).unwrap();
+    // Each function calls the previous one, so `f49(0)` walks through all of them
+    let mut source = String::from("fn num f0(num x) { x + 1 }\n");
+    for i in 1..50 {
+        source.push_str(&format!("fn num f{}(num x) {{ f{}(x) + 1 }}\n", i, i - 1));
+    }
+    fs::write(feathers_dir.join("many.pl"), source).unwrap();
+
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    FeatherManager::import(&manager, "many").unwrap();
+    assert_eq!(manager.borrow().feathers["many"].functions.len(), 50);
+    assert_eq!(manager.borrow().call_function("many", "f49", vec![Value::Number(0.0)]), Ok(Value::Number(50.0)));
+
+    // The feather's interpreter only refers back to the manager and holds no copy of its state
+    assert_eq!(Rc::strong_count(&manager), 1);
+    assert_eq!(Rc::strong_count(&manager.borrow().std_functions["add"]), 1);
+}
+
+#[test]
+fn test_feathers_imported_by_a_feather_are_shared() {
+    let project_root = tempfile::tempdir().unwrap();
+    let feathers_dir = project_root.path().join("feathers");
+    fs::create_dir(&feathers_dir).unwrap();
+    fs::write(feathers_dir.join("base.pl"), "fn num one() { 1 }").unwrap();
+    fs::write(feathers_dir.join("derived.pl"), "imp base\nfn num two() { base.one() + 1 }").unwrap();
+
+    // Importing the dependency first, the feather's own import of it finds it loaded
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    FeatherManager::import(&manager, "base").unwrap();
+    fs::remove_file(feathers_dir.join("base.pl")).unwrap();
+    FeatherManager::import(&manager, "derived").unwrap();
+    assert_eq!(manager.borrow().call_function("derived", "two", vec![]), Ok(Value::Number(2.0)));
+
+    // Imported the other way round, the dependency becomes available to the importer as well
+    fs::write(feathers_dir.join("base.pl"), "fn num one() { 1 }").unwrap();
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    FeatherManager::import(&manager, "derived").unwrap();
+    assert_eq!(manager.borrow().call_function("base", "one", vec![]), Ok(Value::Number(1.0)));
+    assert_eq!(manager.borrow().call_function("derived", "two", vec![]), Ok(Value::Number(2.0)));
+}
+
+fn run_program(manager: &Rc<RefCell<FeatherManager>>, source: &str) -> Result<Value, String> {
+    let tokens = Lexer::new(source).tokenize()?;
+    let expressions = Parser::new(tokens).parse()?;
+    Ok(Interpreter::new(Rc::clone(manager)).interpret_program(&expressions)?)
+}
+
+#[test]
+fn test_import_as_alias() {
+    let project_root = tempfile::tempdir().unwrap();
+    let feathers_dir = project_root.path().join("feathers");
+    fs::create_dir(&feathers_dir).unwrap();
+    fs::write(feathers_dir.join("long_feather_name.pl"), "fn num double(num x) { x * 2 }").unwrap();
+
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    // The alias works for calls and function references, the feather's own name keeps working
+    let source = "imp long_feather_name as l\nlet f = l.double\nf(l.double(5)) + long_feather_name.double(1)";
+    assert_eq!(run_program(&manager, source), Ok(Value::Number(22.0)));
+    // Another program has its own aliases
+    assert_eq!(run_program(&manager, "l.double(1)"), Err("Feather 'l' not found".to_string()));
+}
+
+#[test]
+fn test_import_alias_collisions() {
+    let project_root = tempfile::tempdir().unwrap();
+    let feathers_dir = project_root.path().join("feathers");
+    fs::create_dir(&feathers_dir).unwrap();
+    fs::write(feathers_dir.join("first.pl"), "fn num one() { 1 }").unwrap();
+    fs::write(feathers_dir.join("second.pl"), "fn num two() { 2 }").unwrap();
+    fs::write(feathers_dir.join("f.pl"), "fn num three() { 3 }").unwrap();
+
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    // Importing again under the same alias is fine
+    assert_eq!(run_program(&manager, "imp first as f\nimp first as f\nf.one()"), Ok(Value::Number(1.0)));
+    assert_eq!(
+        run_program(&manager, "imp first as f\nimp second as f"),
+        Err("Cannot import feather 'second' as 'f': 'f' is already an alias for feather 'first'".to_string()),
+    );
+    assert_eq!(
+        run_program(&manager, "imp second as first"),
+        Err("Cannot import feather 'second' as 'first': a feather named 'first' is already imported".to_string()),
+    );
+    assert_eq!(
+        run_program(&manager, "imp first as f\nimp f"),
+        Err("Cannot import feather 'f': 'f' is already an alias for feather 'first'".to_string()),
+    );
+    assert!(!manager.borrow().feathers.contains_key("second"));
+    assert!(!manager.borrow().feathers.contains_key("f"));
+}
+
+#[test]
+fn test_import_aliases_are_local_to_the_importing_file() {
+    let project_root = tempfile::tempdir().unwrap();
+    let feathers_dir = project_root.path().join("feathers");
+    fs::create_dir(&feathers_dir).unwrap();
+    fs::write(feathers_dir.join("math_like.pl"), "fn num twice(num x) { x * 2 }").unwrap();
+    fs::write(feathers_dir.join("string_like.pl"), "fn str twice(str s) { s + s }").unwrap();
+    fs::write(feathers_dir.join("user.pl"), "imp math_like as m\nfn num quadruple(num x) { m.twice(m.twice(x)) }").unwrap();
+
+    let manager = Rc::new(RefCell::new(FeatherManager::new(project_root.path().to_path_buf())));
+    let source = "imp user\nimp string_like as m\n[m.twice(\"ab\"), user.quadruple(3)]";
+    assert_eq!(run_program(&manager, source).unwrap().to_string(), "[\"abab\", 12]");
+}
@@ -3,7 +3,7 @@ use std::rc::Rc;
 use pelin::feather::FeatherManager;
 use pelin::lexer::{Lexer};
 use pelin::parser::Parser;
-use pelin::interpreter::{InterpretResult, Interpreter, Value};
+use pelin::interpreter::{Environment, InterpretResult, Interpreter, Shared, Value};
 
 fn init_logger() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -142,6 +142,15 @@ fn test_interpret_feather_function() {
     assert_eq!(interpret(input), Ok(Value::Number(8.0)));
 }
 
+#[test]
+fn test_interpret_feather_calling_its_own_functions() {
+    let input = r#"
+        imp std_logic
+        std_logic.xor(true, false)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Boolean(true)));
+}
+
 #[test]
 fn test_interpret_file_feather() {
     let input = r#"
@@ -151,4 +160,1161 @@ fn test_interpret_file_feather() {
     //compare with real file content
     let real_content = std::fs::read_to_string("feathers/std_file.pl").unwrap();
     assert_eq!(interpret(input), Ok(Value::String(real_content)));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_interpret_array_literal() {
+    let result = interpret("[1, \"two\", true]").unwrap();
+    match result {
+        Value::Array(elements) => assert_eq!(*elements.borrow(), vec![
+            Value::Number(1.0),
+            Value::String("two".to_string()),
+            Value::Boolean(true),
+        ]),
+        other => panic!("Expected array, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_interpret_array_find() {
+    let input = r#"
+        fn bool is_big(num x) { return RUST[std_func::gt](x, 2) }
+        RUST[std_func::find]([1, 2, 3, 4], is_big)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Number(3.0)));
+}
+
+#[test]
+fn test_interpret_array_find_index() {
+    let input = r#"
+        fn bool is_big(num x) { return RUST[std_func::gt](x, 2) }
+        RUST[std_func::find_index]([1, 2, 3, 4], is_big)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Number(2.0)));
+}
+
+#[test]
+fn test_interpret_array_find_not_found() {
+    let input = r#"
+        fn bool is_huge(num x) { return RUST[std_func::gt](x, 100) }
+        RUST[std_func::find]([1, 2, 3], is_huge)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Nun));
+
+    let input = r#"
+        fn bool is_huge(num x) { return RUST[std_func::gt](x, 100) }
+        RUST[std_func::find_index]([1, 2, 3], is_huge)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Number(-1.0)));
+}
+
+#[test]
+fn test_interpret_array_find_non_boolean_predicate() {
+    let input = r#"
+        fn num identity(num x) { return x }
+        RUST[std_func::find]([1, 2, 3], identity)
+    "#;
+    assert_eq!(interpret(input), Err("find predicate: condition must be a boolean, got num (1)".to_string()));
+
+    let input = r#"
+        fn str answer(num x) { return "yes" }
+        RUST[std_func::find_index]([1, 2, 3], answer)
+    "#;
+    assert_eq!(interpret(input), Err("find_index predicate: condition must be a boolean, got str (\"yes\")".to_string()));
+}
+
+#[test]
+fn test_value_accessors() {
+    assert_eq!(Value::Number(1.5).as_number(), Ok(1.5));
+    assert_eq!(Value::Integer(-3).as_number(), Ok(-3.0));
+    assert_eq!(Value::String("1".to_string()).as_number(), Err("expected num, got str".to_string()));
+
+    assert_eq!(Value::String("pelin".to_string()).as_string(), Ok("pelin"));
+    assert_eq!(Value::Number(1.0).as_string(), Err("expected str, got num".to_string()));
+
+    assert_eq!(Value::Boolean(false).as_bool(), Ok(false));
+    assert_eq!(Value::Nun.as_bool(), Err("expected bool, got nun".to_string()));
+
+    assert!(Value::Nun.is_nun());
+    assert!(!Value::Boolean(false).is_nun());
+    assert!(!Value::String("nun".to_string()).is_nun());
+}
+
+#[test]
+fn test_value_as_condition() {
+    assert_eq!(Value::Boolean(true).as_condition(), Ok(true));
+    assert_eq!(Value::Boolean(false).as_condition(), Ok(false));
+    assert_eq!(Value::Number(0.0).as_condition(), Err("condition must be a boolean, got num (0)".to_string()));
+    assert_eq!(Value::String("".to_string()).as_condition(), Err("condition must be a boolean, got str (\"\")".to_string()));
+    assert_eq!(Value::Nun.as_condition(), Err("condition must be a boolean, got nun (nun)".to_string()));
+}
+
+#[test]
+fn test_interpret_booleans_in_array() {
+    let result = interpret("[true, false, [false]]").unwrap();
+    let elements = match result {
+        Value::Array(elements) => elements,
+        other => panic!("Expected array, got {:?}", other),
+    };
+    let elements = elements.borrow();
+    assert_eq!(elements[0], Value::Boolean(true));
+    assert_eq!(elements[1], Value::Boolean(false));
+    assert_eq!(elements[2], Value::Array(Shared::new(vec![Value::Boolean(false)])));
+}
+
+#[test]
+fn test_interpret_booleans_as_map_values() {
+    let input = r#"
+        imp std_map
+        fn map flags() { return { "on" = true, "off" = false } }
+        std_map.get(flags(), "off")
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Boolean(false)));
+
+    let input = r#"
+        fn bool toggled(map m) {
+            RUST[std_func::map_set](m, "on", false)
+            return RUST[std_func::map_get](m, "on")
+        }
+        toggled({ "on" = true })
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Boolean(false)));
+}
+
+#[test]
+fn test_interpret_booleans_as_map_keys() {
+    let input = r#"
+        imp std_map
+        let labels = { true = "on", false = "off" }
+        std_map.set(labels, false, "disabled")
+        [std_map.get(labels, true), std_map.get(labels, false), std_map.get(labels, "true"), std_map.has(labels, 1 > 2), std_map.keys(labels)]
+    "#;
+    assert_eq!(interpret(input).unwrap().to_string(), "[\"on\", \"disabled\", \"on\", true, [\"false\", \"true\"]]");
+    assert!(interpret("RUST[std_func::map_get]({}, 1)").is_err());
+}
+
+#[test]
+fn test_interpret_booleans_as_arguments_and_operands() {
+    let input = r#"
+        fn bool identity(bool b) { return b }
+        identity(false)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Boolean(false)));
+    assert_eq!(interpret("RUST[std_func::eq](true, true)"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("RUST[std_func::neq](true, false)"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("RUST[std_func::eq]([true, false], [true, false])"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("RUST[std_func::and](true, RUST[std_func::not](false))"), Ok(Value::Boolean(true)));
+}
+
+#[test]
+fn test_interpret_program_with_main() {
+    init_logger();
+    let input = r#"
+        fn num helper() { return 41 }
+        fn num main() { return RUST[std_func::add](helper(), 1) }
+        helper()
+    "#;
+    let tokens = Lexer::new(input).tokenize().unwrap();
+    let expressions = Parser::new(tokens).parse().unwrap();
+    let feather_manager = Rc::new(RefCell::new(FeatherManager::new(std::env::current_dir().unwrap())));
+    let interpreter = Interpreter::new(feather_manager);
+
+    assert_eq!(interpreter.interpret_program_with_main(&expressions), Ok(Value::Number(42.0)));
+}
+
+#[test]
+fn test_interpret_std_array_feather() {
+    let input = r#"
+        imp std_array
+        fn bool is_two(num x) { return RUST[std_func::eq](x, 2) }
+        std_array.find_index(std_array.unique([1, 1, 2, 3, 3]), is_two)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Number(1.0)));
+}
+
+#[test]
+fn test_interpret_std_math_min_max() {
+    let input = r#"
+        imp std_math
+        std_math.min(3, std_math.max(3, 5))
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Number(3.0)));
+}
+
+#[test]
+fn test_value_get_path() {
+    let input = r#"{ "servers" = [{ "host" = "a.example", "ports" = [80, 443] }], "name" = "prod" }"#;
+    let config = interpret(input).unwrap();
+
+    assert_eq!(config.get_path("name"), Ok(Value::String("prod".to_string())));
+    assert_eq!(config.get_path("servers[0].host"), Ok(Value::String("a.example".to_string())));
+    assert_eq!(config.get_path("servers[0].ports[1]"), Ok(Value::Number(443.0)));
+    assert_eq!(config.get_path(""), Ok(config.clone()));
+
+    assert_eq!(config.get_path("servers[1].host"), Ok(Value::Nun));
+    assert_eq!(config.get_path("servers.host"), Ok(Value::Nun));
+    assert_eq!(config.get_path("name.first"), Ok(Value::Nun));
+    assert_eq!(config.get_path("missing[0]"), Ok(Value::Nun));
+
+    assert!(config.get_path("servers[x]").is_err());
+    assert!(config.get_path("servers[0").is_err());
+    assert!(config.get_path("a..b").is_err());
+    assert!(config.get_path("servers[0]host").is_err());
+}
+
+#[test]
+fn test_interpret_std_map_get_path() {
+    let input = r#"
+        imp std_map
+        std_map.get_path([{ "id" = 7 }], "[0].id")
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Number(7.0)));
+}
+
+#[test]
+fn test_environment_define_many() {
+    let mut env = Environment::new();
+    env.define("a".to_string(), Value::Number(0.0));
+    env.define_many(vec![
+        ("a".to_string(), Value::Number(1.0)),
+        ("b".to_string(), Value::String("two".to_string())),
+        ("b".to_string(), Value::Boolean(true)),
+    ]);
+
+    assert_eq!(env.get("a"), Some(Value::Number(1.0)));
+    assert_eq!(env.get("b"), Some(Value::Boolean(true)));
+    env.define_many(Vec::new());
+    assert_eq!(env.get("c"), None);
+}
+
+// Value vs reference semantics: scalars are copied, arrays and maps are shared.
+
+#[test]
+fn test_semantics_assigning_number_copies() {
+    let mut env = Environment::new();
+    env.define("a".to_string(), Value::Number(1.0));
+    let copied = env.get("a").unwrap();
+    env.define("b".to_string(), copied);
+    env.assign("b", Value::Number(2.0)).unwrap();
+
+    assert_eq!(env.get("a"), Some(Value::Number(1.0)));
+    assert_eq!(env.get("b"), Some(Value::Number(2.0)));
+}
+
+#[test]
+fn test_semantics_assigning_array_shares() {
+    let mut env = Environment::new();
+    env.define("a".to_string(), Value::Array(Shared::new(vec![Value::Number(1.0)])));
+    let shared = env.get("a").unwrap();
+    env.define("b".to_string(), shared);
+
+    if let Some(Value::Array(elements)) = env.get("b") {
+        elements.borrow_mut().push(Value::Number(2.0));
+    }
+    let expected = Value::Array(Shared::new(vec![Value::Number(1.0), Value::Number(2.0)]));
+    assert_eq!(env.get("a"), Some(expected.clone()));
+    assert_eq!(env.get("b"), Some(expected));
+}
+
+#[test]
+fn test_semantics_array_bound_to_two_parameters_is_shared() {
+    let input = r#"
+        fn num push_through_first(any first, any second) {
+            RUST[std_func::array_push](first, 2)
+            return RUST[std_func::find_index](second, is_two)
+        }
+        fn bool is_two(num x) { return RUST[std_func::eq](x, 2) }
+        push_through_first([1], [1])
+    "#;
+    // Two distinct literals are two distinct arrays
+    assert_eq!(interpret(input), Ok(Value::Number(-1.0)));
+
+    let input = r#"
+        fn num push_through_first(any first, any second) {
+            RUST[std_func::array_push](first, 2)
+            return RUST[std_func::find_index](second, is_two)
+        }
+        fn bool is_two(num x) { return RUST[std_func::eq](x, 2) }
+        fn num alias(any arr) { return push_through_first(arr, arr) }
+        alias([1])
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Number(1.0)));
+}
+
+#[test]
+fn test_semantics_function_mutates_callers_array() {
+    let input = r#"
+        fn nun append(any arr) { RUST[std_func::array_push](arr, 3) }
+        fn any caller(any arr) {
+            append(arr)
+            return arr
+        }
+        caller([1, 2])
+    "#;
+    let expected = Value::Array(Shared::new(vec![
+        Value::Number(1.0),
+        Value::Number(2.0),
+        Value::Number(3.0),
+    ]));
+    assert_eq!(interpret(input), Ok(expected));
+}
+
+#[test]
+fn test_semantics_function_mutates_callers_map() {
+    let input = r#"
+        fn nun mark(map m) { RUST[std_func::map_set](m, "seen", true) }
+        fn any caller(map m) {
+            mark(m)
+            return RUST[std_func::map_get](m, "seen")
+        }
+        caller({})
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Boolean(true)));
+}
+
+#[test]
+fn test_interpret_implicit_return_of_last_value() {
+    let input = r#"
+        fn num answer() { 42 }
+        answer()
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Number(42.0)));
+
+    let input = r#"
+        fn str last(num x) {
+            x
+            "first"
+            "second"
+        }
+        last(1)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::String("second".to_string())));
+}
+
+#[test]
+fn test_interpret_implicit_return_of_conditional() {
+    let input = r#"
+        fn num positive(num x) {
+            if (x > 0) { 1 } else { 0 }
+        }
+        positive(5) + positive(0)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Number(1.0)));
+}
+
+#[test]
+fn test_interpret_definition_as_last_statement_returns_nun() {
+    let input = r#"
+        fn nun outer() { fn num inner() { 1 } }
+        outer()
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Nun));
+}
+
+#[test]
+fn test_interpret_program_result_is_last_top_level_value() {
+    let input = r#"
+        fn num one() { 1 }
+        one()
+        "done"
+    "#;
+    assert_eq!(interpret(input), Ok(Value::String("done".to_string())));
+}
+
+#[test]
+fn test_interpret_std_type_predicates() {
+    let input = r#"
+        imp std_type
+        fn nun f() { }
+        std_type.is_function(f)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Boolean(true)));
+
+    let input = r#"
+        imp std_type
+        std_type.is_num("1")
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Boolean(false)));
+}
+
+#[test]
+fn test_interpret_std_type_of() {
+    let cases = [
+        ("1", "num"), ("1i", "int"), ("\"a\"", "str"), ("true", "bool"), ("nun", "nun"),
+        ("[]", "array"), ("{}", "map"), ("f", "function"), ("std_type.is_num", "function"),
+    ];
+    for (value, name) in cases {
+        let input = format!("imp std_type\nfn nun f() {{ }}\nstd_type.type_of({})", value);
+        assert_eq!(interpret(&input), Ok(Value::String(name.to_string())), "{}", value);
+    }
+}
+
+#[test]
+fn test_interpret_number_suffixes() {
+    assert!(matches!(interpret("5i"), Ok(Value::Integer(5))));
+    assert!(matches!(interpret("5f"), Ok(Value::Number(n)) if n == 5.0));
+    assert!(matches!(interpret("0xff"), Ok(Value::Integer(255))));
+}
+
+#[test]
+fn test_interpret_integer_arithmetic() {
+    assert_eq!(interpret("RUST[std_func::add](2i, 3i)"), Ok(Value::Integer(5)));
+    assert_eq!(interpret("RUST[std_func::multiply](2i, 0.5)"), Ok(Value::Number(1.0)));
+    assert_eq!(interpret("RUST[std_func::divide](7i, 2i)"), Ok(Value::Number(3.5)));
+    assert_eq!(interpret("RUST[std_func::lt](2i, 2.5)"), Ok(Value::Boolean(true)));
+    assert!(interpret("RUST[std_func::multiply](0x7fffffffffffffff, 2i)").is_err());
+}
+
+#[test]
+fn test_interpret_integers_equal_floats_of_the_same_value() {
+    assert_eq!(interpret("[1i == 1, 1i != 1, 1i <= 1, 1i >= 1, 2i == 2.5]").unwrap().to_string(), "[true, false, true, true, false]");
+    assert_eq!(interpret("[[1i, [2i]] == [1, [2]], { \"a\" = 1i } == { \"a\" = 1 }]").unwrap().to_string(), "[true, true]");
+    // Compared exactly, not after rounding the integer to a float
+    assert_eq!(interpret("9007199254740993i == 9007199254740992"), Ok(Value::Boolean(false)));
+}
+
+#[test]
+fn test_display_value() {
+    assert_eq!(interpret("3").unwrap().to_string(), "3");
+    assert_eq!(interpret("2.5").unwrap().to_string(), "2.5");
+    assert_eq!(interpret("\"hi\"").unwrap().to_string(), "hi");
+    assert_eq!(interpret("nun").unwrap().to_string(), "nun");
+    assert_eq!(interpret("[1, \"a\", [true]]").unwrap().to_string(), "[1, \"a\", [true]]");
+    assert_eq!(interpret("{ \"b\" = 2, \"a\" = \"x\" }").unwrap().to_string(), "{\"a\" = \"x\", \"b\" = 2}");
+}
+
+#[test]
+fn test_interpret_empty_program_is_nun() {
+    assert_eq!(interpret(""), Ok(Value::Nun));
+    assert_eq!(interpret("  \n // just a comment\n"), Ok(Value::Nun));
+}
+
+#[test]
+fn test_interpret_std_num_bit_count() {
+    let input = r#"
+        imp std_num
+        std_num.bit_count(0xff)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Integer(8)));
+}
+
+#[test]
+fn test_interpret_match_type_dispatch() {
+    let handlers = r#"
+        imp std_type
+        fn str on_num(num n) { return "number" }
+        fn str on_str(str s) { return "string" }
+        fn str other(any v) { return "other" }
+    "#;
+    let input = format!("{}\nstd_type.match_type(1, {{ \"num\" = on_num, \"str\" = on_str }})", handlers);
+    assert_eq!(interpret(&input), Ok(Value::String("number".to_string())));
+
+    let input = format!("{}\nstd_type.match_type(\"x\", {{ \"num\" = on_num, \"str\" = on_str }})", handlers);
+    assert_eq!(interpret(&input), Ok(Value::String("string".to_string())));
+
+    let input = format!("{}\nstd_type.match_type(true, {{ \"num\" = on_num, \"_\" = other }})", handlers);
+    assert_eq!(interpret(&input), Ok(Value::String("other".to_string())));
+
+    let input = format!("{}\nstd_type.match_type(true, {{ \"num\" = on_num }})", handlers);
+    assert!(interpret(&input).unwrap_err().contains("no handler for type 'bool'"));
+}
+
+#[test]
+fn test_interpret_match_type_integer_falls_back_to_num() {
+    let handlers = r#"
+        imp std_type
+        fn str on_int(int n) { return "integer" }
+        fn str on_num(num n) { return "number" }
+        fn str other(any v) { return "other" }
+    "#;
+    let input = format!("{}\nstd_type.match_type(1i, {{ \"int\" = on_int, \"num\" = on_num }})", handlers);
+    assert_eq!(interpret(&input), Ok(Value::String("integer".to_string())));
+
+    let input = format!("{}\nstd_type.match_type(1i, {{ \"num\" = on_num, \"_\" = other }})", handlers);
+    assert_eq!(interpret(&input), Ok(Value::String("number".to_string())));
+
+    let input = format!("{}\nstd_type.match_type(1i, {{ \"str\" = on_num, \"_\" = other }})", handlers);
+    assert_eq!(interpret(&input), Ok(Value::String("other".to_string())));
+}
+
+#[test]
+fn test_interpret_try_catches_thrown_map() {
+    let input = r#"
+        imp std_error
+        imp std_map
+        fn any fetch() { std_error.error({ "code" = 404, "reason" = "missing" }) }
+        fn any handle(any e) { return [std_map.get(e, "code"), std_map.get(e, "reason")] }
+        std_error.try(fetch, handle)
+    "#;
+    let caught = Value::Array(Shared::new(vec![Value::Number(404.0), Value::String("missing".to_string())]));
+    assert_eq!(interpret(input), Ok(caught));
+}
+
+#[test]
+fn test_interpret_nested_try_catches_each_error_once() {
+    let input = r#"
+        imp std_error
+        fn any fail_inner() { std_error.error("inner") }
+        fn any rethrow(any e) { std_error.error([e, "rethrown"]) }
+        fn any inner() { std_error.try(fail_inner, rethrow) }
+        fn any handle(any e) { return e }
+        std_error.try(inner, handle)
+    "#;
+    assert_eq!(interpret(input).unwrap().to_string(), "[\"inner\", \"rethrown\"]");
+
+    // The inner handler fails with an interpreter error, the outer try gets its message
+    let input = r#"
+        imp std_error
+        fn any fail_inner() { std_error.error("inner") }
+        fn any broken(any e) { RUST[std_func::divide](1, "x") }
+        fn any inner() { std_error.try(fail_inner, broken) }
+        fn any handle(any e) { return e }
+        std_error.try(inner, handle)
+    "#;
+    let caught = interpret(input).unwrap();
+    assert!(matches!(&caught, Value::String(message) if !message.contains("inner")), "{:?}", caught);
+}
+
+#[test]
+fn test_interpret_try_passes_interpreter_errors_as_message() {
+    let input = r#"
+        imp std_error
+        fn any broken() { RUST[std_func::divide](1, "x") }
+        fn any handle(any e) { return e }
+        std_error.try(broken, handle)
+    "#;
+    assert!(matches!(interpret(input), Ok(Value::String(_))));
+
+    let input = r#"
+        imp std_error
+        fn num fine() { return 1 }
+        fn any handle(any e) { return e }
+        std_error.try(fine, handle)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Number(1.0)));
+}
+
+#[test]
+fn test_interpret_uncaught_error_reports_value() {
+    let input = r#"RUST[std_func::error]({ "code" = 1 })"#;
+    assert_eq!(interpret(input), Err("Uncaught error: {\"code\" = 1}".to_string()));
+}
+
+#[test]
+fn test_function_entry_and_exit_events() {
+    let input = r#"
+        fn num inner(num x) { return x }
+        fn num outer() { return inner(2) }
+        outer()
+    "#;
+    let tokens = Lexer::new(input).tokenize().unwrap();
+    let expressions = Parser::new(tokens).parse().unwrap();
+    let feather_manager = Rc::new(RefCell::new(FeatherManager::new(std::env::current_dir().unwrap())));
+    let mut interpreter = Interpreter::new(feather_manager);
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let entered = Rc::clone(&events);
+    interpreter.on_function_enter(move |name, args| entered.borrow_mut().push(format!("enter {} {:?}", name, args)));
+    let exited = Rc::clone(&events);
+    interpreter.on_function_exit(move |name, result| exited.borrow_mut().push(format!("exit {} {}", name, result)));
+
+    assert_eq!(interpreter.interpret_program(&expressions), Ok(Value::Number(2.0)));
+    assert_eq!(*events.borrow(), vec![
+        "enter outer []".to_string(),
+        "enter inner [Number(2.0)]".to_string(),
+        "exit inner 2".to_string(),
+        "exit outer 2".to_string(),
+    ]);
+}
+
+#[test]
+fn test_registered_builtin_calls_passed_function() {
+    let input = r#"
+        fn num triple(num x) { return RUST[std_func::multiply](x, 3) }
+        fn num apply_twice(any f, num x) { return RUST[std_func::twice](f, x) }
+        apply_twice(triple, 2)
+    "#;
+    let tokens = Lexer::new(input).tokenize().unwrap();
+    let expressions = Parser::new(tokens).parse().unwrap();
+    let feather_manager = Rc::new(RefCell::new(FeatherManager::new(std::env::current_dir().unwrap())));
+    let mut interpreter = Interpreter::new(feather_manager);
+
+    let calls = Rc::new(RefCell::new(0));
+    let counted = Rc::clone(&calls);
+    interpreter.register_builtin("twice", move |interpreter, args| {
+        *counted.borrow_mut() += 1;
+        let once = interpreter.call_value(&args[0], vec![args[1].clone()])?;
+        interpreter.call_value(&args[0], vec![once])
+    });
+
+    // Reached from inside apply_twice, so nested calls see it too
+    assert_eq!(interpreter.interpret_program(&expressions), Ok(Value::Number(18.0)));
+    assert_eq!(*calls.borrow(), 1);
+}
+
+#[test]
+fn test_registered_builtin_shadows_std_function() {
+    let tokens = Lexer::new("RUST[std_func::add](1, 2)").tokenize().unwrap();
+    let expressions = Parser::new(tokens).parse().unwrap();
+    let feather_manager = Rc::new(RefCell::new(FeatherManager::new(std::env::current_dir().unwrap())));
+    let mut interpreter = Interpreter::new(feather_manager);
+    interpreter.register_builtin("add", |_, args| Ok(Value::Number(args.len() as f64)));
+
+    assert_eq!(interpreter.interpret_program(&expressions), Ok(Value::Number(2.0)));
+}
+
+#[test]
+fn test_interpret_arguments_are_checked_against_parameter_types() {
+    let definition = "fn num add(num a, num b) { a + b }\n";
+    assert_eq!(interpret(&format!("{}add(1, 2i)", definition)), Ok(Value::Number(3.0)));
+    assert_eq!(interpret(&format!("{}add(1, \"2\")", definition)), Err("Argument 'b' expected num, got str".to_string()));
+    assert_eq!(interpret("fn bool not(bool b) { !b }\nnot(nun)"), Err("Argument 'b' expected bool, got nun".to_string()));
+    // Custom types are not checked yet
+    assert_eq!(interpret("fn any first(array xs) { xs }\nfirst(\"x\")"), Ok(Value::String("x".to_string())));
+}
+
+#[test]
+fn test_interpret_return_value_is_checked_against_return_type() {
+    assert_eq!(interpret("fn str name() { return 5 }\nname()"), Err("Function 'name' expected to return str, got num".to_string()));
+    assert_eq!(interpret("fn num half(num n) { n / 2 }\nhalf(3i)"), Ok(Value::Number(1.5)));
+    // The last expression of a `nun` function is usually a side effect and not checked
+    assert_eq!(interpret("let x = 0\nfn nun set() { x = 5 }\nset()"), Ok(Value::Number(5.0)));
+}
+
+#[test]
+fn test_interpret_closure_keeps_captured_parameter_after_return() {
+    let input = r#"
+        fn any make_adder(num n) {
+            fn num add(num x) {
+                x + n
+            }
+            add
+        }
+        let add5 = make_adder(5)
+        let add10 = make_adder(10)
+        [add5(1), add10(1), add5(2)]
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Array(Shared::new(vec![Value::Number(6.0), Value::Number(11.0), Value::Number(7.0)]))));
+}
+
+#[test]
+fn test_interpret_closures_have_separate_captured_state() {
+    let input = r#"
+        fn any counter() {
+            let count = 0
+            fn num next() {
+                count = count + 1
+                count
+            }
+            next
+        }
+        let a = counter()
+        let b = counter()
+        a()
+        a()
+        [a(), b()]
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Array(Shared::new(vec![Value::Number(3.0), Value::Number(1.0)]))));
+}
+
+#[test]
+fn test_interpret_consecutive_unary_operators() {
+    assert_eq!(interpret("!!true"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("!true"), Ok(Value::Boolean(false)));
+    assert_eq!(interpret("--5"), Ok(Value::Number(5.0)));
+    assert_eq!(interpret("-5i"), Ok(Value::Integer(-5)));
+    assert_eq!(interpret("-RUST[std_func::add](1, 2)"), Ok(Value::Number(-3.0)));
+}
+
+#[test]
+fn test_interpret_unary_operators_on_grouped_expressions() {
+    assert_eq!(interpret("!(3 < 5)"), Ok(Value::Boolean(false)));
+    assert_eq!(interpret("-(2 + 3) * 2"), Ok(Value::Number(-10.0)));
+    assert_eq!(interpret("-[1, 2][1]"), Ok(Value::Number(-2.0)));
+    assert_eq!(interpret("!(1 + 1)"), Err("'!' expects a boolean, got num".to_string()));
+}
+
+#[test]
+fn test_interpret_unary_type_errors() {
+    assert_eq!(interpret("-!true"), Err("'-' expects a number, got bool".to_string()));
+    assert_eq!(interpret("!5"), Err("'!' expects a boolean, got num".to_string()));
+    assert_eq!(interpret("-\"a\""), Err("'-' expects a number, got str".to_string()));
+}
+
+#[test]
+fn test_interpret_feather_function_as_value() {
+    let input = r#"
+        imp std_math
+        imp std_array
+        fn any apply(any f) {
+            std_array.map([0, 1], f)
+        }
+        apply(std_math.sin)
+    "#;
+    let expected = Value::Array(Shared::new(vec![Value::Number(0.0), Value::Number(1f64.sin())]));
+    assert_eq!(interpret(input), Ok(expected));
+}
+
+#[test]
+fn test_interpret_map_with_user_function() {
+    let input = r#"
+        imp std_array
+        fn num double(num x) { RUST[std_func::add](x, x) }
+        std_array.map([1, 2, 3], double)
+    "#;
+    let expected = Value::Array(Shared::new(vec![Value::Number(2.0), Value::Number(4.0), Value::Number(6.0)]));
+    assert_eq!(interpret(input), Ok(expected));
+}
+
+#[test]
+fn test_interpret_reference_to_missing_feather_function() {
+    let input = r#"
+        imp std_math
+        std_math.nope
+    "#;
+    assert!(interpret(input).unwrap_err().contains("Function 'nope' not found in feather 'std_math'"));
+}
+
+fn interpret_in(project_root: &std::path::Path, input: &str) -> Result<Value, String> {
+    let tokens = Lexer::new(input).tokenize()?;
+    let expressions = Parser::new(tokens).parse()?;
+    let feather_manager = Rc::new(RefCell::new(FeatherManager::new(project_root.to_path_buf())));
+    Ok(Interpreter::new(feather_manager).interpret_program(&expressions)?)
+}
+
+#[test]
+fn test_include_defines_functions_globally() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("lib")).unwrap();
+    std::fs::write(dir.path().join("lib/helpers.pl"), "inc \"more.pl\"\nfn num twice(num x) { RUST[std_func::add](x, x) }").unwrap();
+    std::fs::write(dir.path().join("lib/more.pl"), "fn num thrice(num x) { RUST[std_func::add](twice(x), x) }").unwrap();
+
+    let result = interpret_in(dir.path(), "inc \"lib/helpers.pl\"\nthrice(2)");
+    assert_eq!(result, Ok(Value::Number(6.0)));
+}
+
+#[test]
+fn test_include_rejects_recursion() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.pl"), "inc \"b.pl\"").unwrap();
+    std::fs::write(dir.path().join("b.pl"), "inc \"a.pl\"").unwrap();
+
+    let err = interpret_in(dir.path(), "inc \"a.pl\"").unwrap_err();
+    assert!(err.contains("Recursive include of"), "{}", err);
+    assert!(err.contains("a.pl"), "{}", err);
+}
+
+#[test]
+fn test_include_error_names_file_and_line() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("broken.pl"), "fn num ok() { 1 }\n\nfn num (").unwrap();
+
+    let err = interpret_in(dir.path(), "inc \"broken.pl\"").unwrap_err();
+    assert!(err.contains("broken.pl"), "{}", err);
+    assert!(err.contains("line 3"), "{}", err);
+}
+
+#[test]
+fn test_interpret_rust_path_with_one_segment() {
+    let err = interpret("RUST[add](1, 2)").unwrap_err();
+    assert_eq!(err, "Invalid Rust function path 'add': expected the form library::function, e.g. std_func::add");
+}
+
+fn interpret_copy_on_write(input: &str) -> Result<Value, String> {
+    let tokens = Lexer::new(input).tokenize()?;
+    let expressions = Parser::new(tokens).parse()?;
+    let feather_manager = Rc::new(RefCell::new(FeatherManager::new(std::env::current_dir().unwrap())));
+    let mut interpreter = Interpreter::new(feather_manager);
+    interpreter.set_copy_on_write(true);
+    Ok(interpreter.interpret_program(&expressions)?)
+}
+
+#[test]
+fn test_copy_on_write_mutation_does_not_affect_original() {
+    let input = r#"
+        fn nun append(any arr) { RUST[std_func::array_push](arr, 3) }
+        fn any caller(any arr) {
+            append(arr)
+            return arr
+        }
+        caller([1, 2])
+    "#;
+    let expected = Value::Array(Shared::new(vec![Value::Number(1.0), Value::Number(2.0)]));
+    assert_eq!(interpret_copy_on_write(input), Ok(expected));
+
+    let input = r#"
+        fn nun mark(map m) { RUST[std_func::map_set](m, "seen", true) }
+        fn any caller(map m) {
+            mark(m)
+            return RUST[std_func::map_get](m, "seen")
+        }
+        caller({})
+    "#;
+    assert_eq!(interpret_copy_on_write(input), Ok(Value::Nun));
+}
+
+#[test]
+fn test_copy_on_write_shares_until_mutation() {
+    let original = Shared::new(vec![Value::Number(1.0)]);
+    let copy = original.copy_on_write();
+    assert_eq!(copy.borrow().len(), 1);
+    assert!(copy.shares_elements_with(&original));
+
+    copy.borrow_mut().push(Value::Number(2.0));
+    assert!(!copy.shares_elements_with(&original));
+    assert_eq!(*original.borrow(), vec![Value::Number(1.0)]);
+    assert_eq!(*copy.borrow(), vec![Value::Number(1.0), Value::Number(2.0)]);
+
+    // A plain clone is the same collection, mutations are visible through both
+    let alias = original.clone();
+    alias.borrow_mut().push(Value::Number(3.0));
+    assert_eq!(original.borrow().len(), 2);
+}
+
+#[test]
+fn test_interpret_arithmetic_operators() {
+    assert_eq!(interpret("2 + 3 * 4"), Ok(Value::Number(14.0)));
+    assert_eq!(interpret("10 - 4 - 3"), Ok(Value::Number(3.0)));
+    assert_eq!(interpret("7 % 4"), Ok(Value::Number(3.0)));
+    assert_eq!(interpret("7i + 2i * 3i"), Ok(Value::Integer(13)));
+    assert_eq!(interpret("7i % 4i"), Ok(Value::Integer(3)));
+    assert_eq!(interpret("7i / 2i"), Ok(Value::Number(3.5)));
+    assert_eq!(interpret("1i + 0.5"), Ok(Value::Number(1.5)));
+}
+
+#[test]
+fn test_interpret_remainder_is_euclidean() {
+    assert_eq!(interpret("7 % 3"), Ok(Value::Number(1.0)));
+    assert_eq!(interpret("(-7) % 3"), Ok(Value::Number(2.0)));
+    assert_eq!(interpret("-7 % 3"), Ok(Value::Number(2.0)));
+    assert_eq!(interpret("7 % -3"), Ok(Value::Number(1.0)));
+    assert_eq!(interpret("-7.5 % 2"), Ok(Value::Number(0.5)));
+    assert_eq!(interpret("(-7i) % 3i"), Ok(Value::Integer(2)));
+    assert_eq!(interpret("-0x7fffffffffffffff % -1i"), Ok(Value::Integer(0)));
+    assert_eq!(interpret("7 / 2"), Ok(Value::Number(3.5)));
+}
+
+#[test]
+fn test_interpret_remainder_and_mod_differ_for_a_negative_divisor() {
+    // `%` is never negative, `std_num.mod` takes the sign of the divisor
+    for (input, remainder, modulo) in [("-1i, -3i", 2, -1), ("1i, -3i", 1, -2), ("-7i, -3i", 2, -1), ("6i, -3i", 0, 0)] {
+        let (a, b) = input.split_once(", ").unwrap();
+        assert_eq!(interpret(&format!("{} % {}", a, b)), Ok(Value::Integer(remainder)), "{}", input);
+        assert_eq!(interpret(&format!("imp std_num\nstd_num.mod({})", input)), Ok(Value::Integer(modulo)), "{}", input);
+    }
+    assert_eq!(interpret("-1 % -3"), Ok(Value::Number(2.0)));
+    assert_eq!(interpret("imp std_num\nstd_num.mod(-1, -3)"), Ok(Value::Number(-1.0)));
+}
+
+#[test]
+fn test_interpret_floor_divide_pairs_with_remainder() {
+    for (a, b) in [(7, 3), (-7, 3), (7, 2), (-8, 4), (0, 5)] {
+        let input = format!("imp std_num\nlet a = {}i\nlet b = {}i\nb * std_num.floor_divide(a, b) + a % b == a", a, b);
+        assert_eq!(interpret(&input), Ok(Value::Boolean(true)), "{} {}", a, b);
+    }
+}
+
+#[test]
+fn test_interpret_string_concatenation() {
+    assert_eq!(interpret("\"a\" + \"b\""), Ok(Value::String("ab".to_string())));
+    assert_eq!(interpret("\"a\" + 1"), Err("'+' expects two numbers or two strings, got str and num".to_string()));
+    assert_eq!(interpret("\"a\" * 2"), Err("'*' expects two numbers, got str and num".to_string()));
+}
+
+#[test]
+fn test_interpret_division_by_zero_is_error() {
+    assert_eq!(interpret("10 / 0"), Err("Cannot divide 10 by zero".to_string()));
+    assert_eq!(interpret("10i / 0i"), Err("Cannot divide 10 by zero".to_string()));
+    assert_eq!(interpret("10 % 0"), Err("Cannot take the remainder of 10 divided by zero".to_string()));
+    assert_eq!(interpret("10i % 0i"), Err("Cannot take the remainder of 10 divided by zero".to_string()));
+    assert!(interpret("0x7fffffffffffffff + 1i").unwrap_err().contains("overflows"));
+}
+
+#[test]
+fn test_interpret_comparison_operators() {
+    assert_eq!(interpret("3 < 5"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("5 <= 5"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("3 > 5"), Ok(Value::Boolean(false)));
+    assert_eq!(interpret("2i >= 1.5"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("\"apple\" < \"banana\""), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("1 + 1 == 2"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("\"a\" != \"b\""), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("[1, 2] == [1, 2]"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("nun == false"), Ok(Value::Boolean(false)));
+    assert_eq!(interpret("true < 1"), Err("'<' expects two numbers or two strings, got bool and num".to_string()));
+}
+
+#[test]
+fn test_interpret_logical_operators_short_circuit() {
+    assert_eq!(interpret("true && 1 < 2"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("false || 2 == 2"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("false && undefined_var"), Ok(Value::Boolean(false)));
+    assert_eq!(interpret("true || undefined_var"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("true && undefined_var"), Err("Undefined variable 'undefined_var'.".to_string()));
+    assert_eq!(interpret("false || 1 / 0 == 1"), Err("Cannot divide 1 by zero".to_string()));
+}
+
+#[test]
+fn test_interpret_logical_operators_require_booleans() {
+    assert_eq!(interpret("1 && true"), Err("'&&' expects two booleans, got num".to_string()));
+    assert_eq!(interpret("false || \"yes\""), Err("'||' expects two booleans, got str".to_string()));
+    assert_eq!(interpret("nun || true"), Err("'||' expects two booleans, got nun".to_string()));
+}
+
+#[test]
+fn test_interpret_if_evaluates_only_taken_branch() {
+    assert_eq!(interpret("if (true) { 1 } else { undefined_var }"), Ok(Value::Number(1.0)));
+    assert_eq!(interpret("if (false) { undefined_var } else { 2 }"), Ok(Value::Number(2.0)));
+    assert_eq!(interpret("if (false) { undefined_var }"), Ok(Value::Nun));
+    assert_eq!(interpret("if (1 > 2) { 1 } else if (2 > 1) { 2 } else { undefined_var }"), Ok(Value::Number(2.0)));
+}
+
+#[test]
+fn test_interpret_if_condition_must_be_boolean() {
+    assert_eq!(
+        interpret("if (1) { 1 }"),
+        Err("[line 1, column 5] condition must be a boolean, got num (1)".to_string())
+    );
+    let input = "let name = \"pelin\"\nif (false) { 1 } else if (name) { 2 }";
+    assert_eq!(interpret(input), Err("[line 2, column 27] condition must be a boolean, got str (\"pelin\")".to_string()));
+}
+
+#[test]
+fn test_interpret_return_inside_if() {
+    let input = r#"
+        fn str sign(num x) {
+            if (x < 0) {
+                return "negative"
+            } else if (x == 0) {
+                return "zero"
+            }
+            "positive"
+        }
+        [sign(-3), sign(0), sign(3)]
+    "#;
+    assert_eq!(interpret(input).unwrap().to_string(), "[\"negative\", \"zero\", \"positive\"]");
+}
+
+#[test]
+fn test_interpret_while_runs_until_condition_is_false() {
+    let function = r#"
+        fn num count_iterations(num limit, num count) {
+            while (count < limit) {
+                count = count + 1
+            }
+            count
+        }
+    "#;
+    for (call, iterations) in [("count_iterations(3, 0)", 3.0), ("count_iterations(0, 0)", 0.0)] {
+        assert_eq!(interpret(&format!("{}{}", function, call)), Ok(Value::Number(iterations)));
+    }
+}
+
+#[test]
+fn test_interpret_while_result_and_condition() {
+    assert_eq!(interpret("while (false) { undefined_var }"), Ok(Value::Nun));
+    assert_eq!(interpret("while (0) { 1 }"), Err("[line 1, column 8] condition must be a boolean, got num (0)".to_string()));
+}
+
+#[test]
+fn test_interpret_return_inside_while() {
+    let input = r#"
+        fn num first() {
+            while (true) {
+                return 5
+            }
+        }
+        first()
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Number(5.0)));
+}
+
+#[test]
+fn test_interpret_conditional_expression() {
+    assert_eq!(interpret("let x = 0 x = 1 < 2 ? \"yes\" : \"no\" x"), Ok(Value::String("yes".to_string())));
+    assert_eq!(interpret("let n = 0 n == 0 ? \"zero\" : n < 0 ? \"negative\" : \"positive\""), Ok(Value::String("zero".to_string())));
+    assert_eq!(interpret("1 ? 2 : 3"), Err("[line 1, column 1] condition must be a boolean, got num (1)".to_string()));
+}
+
+#[test]
+fn test_interpret_conditional_evaluates_only_taken_branch() {
+    assert_eq!(interpret("true ? 1 : undefined_var"), Ok(Value::Number(1.0)));
+    assert_eq!(interpret("false ? undefined_fn() : 2"), Ok(Value::Number(2.0)));
+    assert_eq!(interpret("let calls = 0 false ? (calls = calls + 1) : 0 calls"), Ok(Value::Number(0.0)));
+}
+
+#[test]
+fn test_interpret_block_scope() {
+    assert_eq!(interpret("let y = { let x = 2 x * 3 } y"), Ok(Value::Number(6.0)));
+    assert_eq!(interpret("{ let x = 1 } x"), Err("Undefined variable 'x'.".to_string()));
+    assert_eq!(interpret("let x = 1 { let x = 2 } x"), Ok(Value::Number(1.0)));
+}
+
+#[test]
+fn test_interpret_block_reads_and_assigns_outer_variables() {
+    assert_eq!(interpret("let outer = 5 { { outer + 1 } }"), Ok(Value::Number(6.0)));
+    assert_eq!(interpret("let count = 0 { count = count + 1 } count"), Ok(Value::Number(1.0)));
+}
+
+#[test]
+fn test_interpret_for_sums_array() {
+    assert_eq!(interpret("let total = 0 for (x in [1, 2, 3, 4]) { total = total + x } total"), Ok(Value::Number(10.0)));
+}
+
+#[test]
+fn test_interpret_for_over_empty_array() {
+    assert_eq!(interpret("let runs = 0 for (x in []) { runs = runs + 1 } runs"), Ok(Value::Number(0.0)));
+    assert_eq!(interpret("for (x in [1]) { x }"), Ok(Value::Nun));
+}
+
+#[test]
+fn test_interpret_for_scope_and_errors() {
+    assert_eq!(interpret("for (x in [1, 2]) { let y = x } x"), Err("Undefined variable 'x'.".to_string()));
+    assert_eq!(interpret("for (x in [1, 2]) { let y = x } y"), Err("Undefined variable 'y'.".to_string()));
+    assert_eq!(interpret("for (x in 3) { x }"), Err("'for' expects an array to iterate over, got num".to_string()));
+}
+
+#[test]
+fn test_interpret_return_inside_for() {
+    let input = r#"
+        fn num first_over(any xs, num limit) {
+            for (x in xs) {
+                if (x > limit) { return x }
+            }
+            return -1
+        }
+        first_over([1, 5, 9], 3)
+    "#;
+    assert_eq!(interpret(input), Ok(Value::Number(5.0)));
+}
+
+#[test]
+fn test_interpret_let_and_assignment() {
+    assert_eq!(interpret("let x = 1 x = x + 1 x"), Ok(Value::Number(2.0)));
+    assert_eq!(interpret("let count = 0 while (count < 4) { count = count + 1 } count"), Ok(Value::Number(4.0)));
+    assert_eq!(interpret("x = 5"), Err("Undefined variable 'x'.".to_string()));
+}
+
+#[test]
+fn test_interpret_let_declares_in_current_scope() {
+    let input = r#"
+        let x = 1
+        fn num shadow() {
+            let x = 10
+            x = x + 1
+            x
+        }
+        fn num bump() {
+            x = x + 1
+        }
+        [shadow(), x, bump(), x]
+    "#;
+    assert_eq!(interpret(input).unwrap().to_string(), "[11, 1, 2, 2]");
+}
+
+#[test]
+fn test_interpret_nested_array_literals() {
+    let result = interpret("[[1], [2, 3]]").unwrap();
+    assert_eq!(result.to_string(), "[[1], [2, 3]]");
+    assert_eq!(interpret("[[1], [2, 3]] == [[1], [2, 3]]"), Ok(Value::Boolean(true)));
+    assert_eq!(interpret("[[1], [2, 3]] == [[1], [3, 2]]"), Ok(Value::Boolean(false)));
+    assert_eq!(interpret("RUST[std_func::array_get](RUST[std_func::array_get]([[1], [2, 3]], 1), 0)"), Ok(Value::Number(2.0)));
+}
+
+#[test]
+fn test_interpret_index_arrays_and_strings() {
+    assert_eq!(interpret("let xs = [10, [20, 30]] xs[1][0]"), Ok(Value::Number(20.0)));
+    assert_eq!(interpret("[1, 2, 3][2i]"), Ok(Value::Number(3.0)));
+    assert_eq!(interpret("\"héllo\"[1]"), Ok(Value::String("é".to_string())));
+    assert_eq!(interpret("fn any pair() { [1, 2] } pair()[1]"), Ok(Value::Number(2.0)));
+}
+
+#[test]
+fn test_interpret_index_errors() {
+    assert_eq!(interpret("[1, 2, 3][3]"), Err("Index 3 is out of bounds, the array has 3 elements".to_string()));
+    assert_eq!(interpret("\"abc\"[5]"), Err("Index 5 is out of bounds, the str has 3 characters".to_string()));
+    assert_eq!(interpret("[1][-1]"), Err("Index must be a non-negative whole number, got num (-1)".to_string()));
+    assert_eq!(interpret("[1][0.5]"), Err("Index must be a non-negative whole number, got num (0.5)".to_string()));
+    assert_eq!(interpret("[1][\"0\"]"), Err("Index must be a non-negative whole number, got str (\"0\")".to_string()));
+    assert_eq!(interpret("5[0]"), Err("Cannot index a num, only arrays and strings".to_string()));
+}
+
+#[test]
+fn test_interpret_map_set_get_has_and_keys() {
+    let input = r#"
+        let config = { "name" = "pelin", "debug" = false }
+        RUST[std_func::map_set](config, "level", 3)
+        RUST[std_func::map_set](config, "empty", nun)
+        [
+            RUST[std_func::map_get](config, "level"),
+            RUST[std_func::map_get](config, "missing"),
+            RUST[std_func::map_has](config, "empty"),
+            RUST[std_func::map_has](config, "missing"),
+            RUST[std_func::map_keys](config)
+        ]
+    "#;
+    assert_eq!(
+        interpret(input).unwrap().to_string(),
+        "[3, nun, true, false, [\"debug\", \"empty\", \"level\", \"name\"]]"
+    );
+    assert_eq!(interpret("RUST[std_func::map_get]({}, \"key\")"), Ok(Value::Nun));
+    assert!(interpret("RUST[std_func::map_keys]([1])").is_err());
+}
+
+fn interpret_with_max_depth(input: &str, max_depth: usize) -> Result<Value, String> {
+    let tokens = Lexer::new(input).tokenize()?;
+    let expressions = Parser::new(tokens).parse()?;
+    let feather_manager = Rc::new(RefCell::new(FeatherManager::new(std::env::current_dir().unwrap())));
+    Ok(Interpreter::with_max_depth(feather_manager, max_depth).interpret_program(&expressions)?)
+}
+
+#[test]
+fn test_runaway_recursion_reports_maximum_depth() {
+    let input = "fn num forever(num n) { forever(n + 1) } forever(0)";
+    assert_eq!(interpret_with_max_depth(input, 50), Err("Maximum recursion depth (50) exceeded".to_string()));
+}
+
+#[test]
+fn test_recursion_through_a_feather_reports_maximum_depth() {
+    let input = "imp std_array\nfn any f(num n) { std_array.map([n], f) }\nf(1)";
+    assert_eq!(interpret_with_max_depth(input, 50), Err("Maximum recursion depth (50) exceeded".to_string()));
+    // Failed calls give their depth back
+    assert_eq!(interpret_with_max_depth("fn num one() { 1 } one()", 1), Ok(Value::Number(1.0)));
+}
+
+#[test]
+fn test_recursion_up_to_maximum_depth_succeeds() {
+    let input = r#"
+        fn num countdown(num n) {
+            if (n == 0) { return 0 }
+            countdown(n - 1) + 1
+        }
+        countdown(49)
+    "#;
+    assert_eq!(interpret_with_max_depth(input, 50), Ok(Value::Number(49.0)));
+    assert!(interpret_with_max_depth(&input.replace("49", "50"), 50).is_err());
+}
+
+#[test]
+fn test_sibling_calls_do_not_accumulate_depth() {
+    let input = r#"
+        fn num one() { 1 }
+        fn num sum_ones(num count) {
+            let total = 0
+            while (total < count) { total = total + one() }
+            total
+        }
+        sum_ones(200)
+    "#;
+    assert_eq!(interpret_with_max_depth(input, 2), Ok(Value::Number(200.0)));
+}
+
+#[test]
+fn test_interpret_string_functions_through_feather() {
+    let input = r#"
+        imp std_string
+        let word = std_string.trim("  héllo ")
+        [std_string.length(word), std_string.to_upper(std_string.substring(word, 0, 2)), RUST[std_func::str_length](word)]
+    "#;
+    assert_eq!(interpret(input).unwrap().to_string(), "[5, \"HÉ\", 5]");
+}
@@ -1,6 +1,8 @@
-use pelin::lexer::{Lexer, TokenType, tokens_to_token_types};
+use pelin::error::PelinError;
+use pelin::lexer::{Lexer, Token, TokenType, tokens_to_token_types};
 
 #[test]
+#[allow(clippy::approx_constant)]
 fn test_lexer_numbers() {
     let mut lexer = Lexer::new("42 3.14");
     let tokens = lexer.tokenize().unwrap();
@@ -23,7 +25,7 @@ fn test_lexer_strings() {
 
 #[test]
 fn test_lexer_identifiers_and_keywords() {
-    let mut lexer = Lexer::new("fn return nun true false myVar if while");
+    let mut lexer = Lexer::new("fn return nun true false myVar if while for in inside");
     let tokens = lexer.tokenize().unwrap();
     assert_eq!(tokens_to_token_types(tokens), vec![
         TokenType::Fn,
@@ -32,8 +34,11 @@ fn test_lexer_identifiers_and_keywords() {
         TokenType::Boolean(true),
         TokenType::Boolean(false),
         TokenType::Identifier("myVar".to_string()),
-        TokenType::Identifier("if".to_string()),
-        TokenType::Identifier("while".to_string()),
+        TokenType::If,
+        TokenType::While,
+        TokenType::For,
+        TokenType::In,
+        TokenType::Identifier("inside".to_string()),
         TokenType::EOF
     ]);
 }
@@ -81,6 +86,13 @@ fn test_lexer_whitespace_only() {
     assert_eq!(tokens_to_token_types(tokens), vec![TokenType::EOF]);
 }
 
+#[test]
+fn test_lexer_comment_only() {
+    let mut lexer = Lexer::new("// nothing here\n  // or here");
+    let tokens = lexer.tokenize().unwrap();
+    assert_eq!(tokens_to_token_types(tokens), vec![TokenType::EOF]);
+}
+
 #[test]
 fn test_lexer_invalid_number() {
     let mut lexer = Lexer::new("42.42.42");
@@ -184,4 +196,256 @@ fn test_lexer_double_colon() {
         TokenType::Identifier("add".to_string()),
         TokenType::EOF
     ]);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_lexer_number_suffixes() {
+    let mut lexer = Lexer::new("5i 5f 2.5f 5");
+    let tokens = lexer.tokenize().unwrap();
+    assert_eq!(tokens_to_token_types(tokens), vec![
+        TokenType::Integer(5),
+        TokenType::Number(5.0),
+        TokenType::Number(2.5),
+        TokenType::Number(5.0),
+        TokenType::EOF
+    ]);
+}
+
+#[test]
+fn test_lexer_hex_integer() {
+    let mut lexer = Lexer::new("0xff 0x10i");
+    let tokens = lexer.tokenize().unwrap();
+    assert_eq!(tokens_to_token_types(tokens), vec![
+        TokenType::Integer(255),
+        TokenType::Integer(16),
+        TokenType::EOF
+    ]);
+}
+
+#[test]
+fn test_lexer_invalid_integer_literals() {
+    assert!(Lexer::new("5i.5").tokenize().is_err());
+    assert!(Lexer::new("2.5i").tokenize().is_err());
+    assert!(Lexer::new("5ix").tokenize().is_err());
+    assert!(Lexer::new("0x").tokenize().is_err());
+}
+
+#[test]
+fn test_lexer_skips_comments_by_default() {
+    let mut lexer = Lexer::new("// header\nx // trailing");
+    let tokens = lexer.tokenize().unwrap();
+    assert_eq!(tokens_to_token_types(tokens), vec![
+        TokenType::Identifier("x".to_string()),
+        TokenType::EOF
+    ]);
+}
+
+#[test]
+fn test_lexer_keep_comments() {
+    let mut lexer = Lexer::new("x\n  // adds two numbers\nfn");
+    lexer.set_keep_comments(true);
+    let tokens = lexer.tokenize().unwrap();
+    assert_eq!(tokens[1], Token {
+        kind: TokenType::Comment(" adds two numbers".to_string()),
+        lexeme: "// adds two numbers".to_string(),
+        line: 2,
+        column: 3,
+    });
+    assert_eq!(tokens[2].kind, TokenType::Fn);
+}
+
+#[test]
+fn test_lexer_int_literals_mode() {
+    let mut lexer = Lexer::new("5 5.0 5f 5i");
+    assert_eq!(tokens_to_token_types(lexer.tokenize().unwrap()), vec![
+        TokenType::Number(5.0),
+        TokenType::Number(5.0),
+        TokenType::Number(5.0),
+        TokenType::Integer(5),
+        TokenType::EOF
+    ]);
+
+    let mut lexer = Lexer::new("5 5.0 5f 5i");
+    lexer.set_int_literals(true);
+    assert_eq!(tokens_to_token_types(lexer.tokenize().unwrap()), vec![
+        TokenType::Integer(5),
+        TokenType::Number(5.0),
+        TokenType::Number(5.0),
+        TokenType::Integer(5),
+        TokenType::EOF
+    ]);
+}
+
+#[test]
+fn test_lexer_unary_operators() {
+    let mut lexer = Lexer::new("-!x --5");
+    assert_eq!(tokens_to_token_types(lexer.tokenize().unwrap()), vec![
+        TokenType::Minus,
+        TokenType::Bang,
+        TokenType::Identifier("x".to_string()),
+        TokenType::Minus,
+        TokenType::Minus,
+        TokenType::Number(5.0),
+        TokenType::EOF
+    ]);
+}
+
+#[test]
+fn test_lexer_binary_operators() {
+    let mut lexer = Lexer::new("+ - * / % == != < <= > >= = ! && || ? : ::");
+    assert_eq!(tokens_to_token_types(lexer.tokenize().unwrap()), vec![
+        TokenType::Plus,
+        TokenType::Minus,
+        TokenType::Star,
+        TokenType::Slash,
+        TokenType::Percent,
+        TokenType::EqualEqual,
+        TokenType::BangEqual,
+        TokenType::Less,
+        TokenType::LessEqual,
+        TokenType::Greater,
+        TokenType::GreaterEqual,
+        TokenType::Equal,
+        TokenType::Bang,
+        TokenType::AndAnd,
+        TokenType::OrOr,
+        TokenType::Question,
+        TokenType::Colon,
+        TokenType::DoubleColon,
+        TokenType::EOF
+    ]);
+    assert_eq!(Lexer::new("a & b").tokenize(), Err(PelinError::Lex {
+        message: "Unexpected character: '&' at line 1, column 3".to_string(),
+        line: 1,
+        column: 3,
+    }));
+
+    let mut lexer = Lexer::new("a<=b // a comment");
+    assert_eq!(tokens_to_token_types(lexer.tokenize().unwrap()), vec![
+        TokenType::Identifier("a".to_string()),
+        TokenType::LessEqual,
+        TokenType::Identifier("b".to_string()),
+        TokenType::EOF
+    ]);
+}
+
+#[test]
+fn test_lexer_if_else_keywords() {
+    let mut lexer = Lexer::new("if else iffy");
+    assert_eq!(tokens_to_token_types(lexer.tokenize().unwrap()), vec![
+        TokenType::If,
+        TokenType::Else,
+        TokenType::Identifier("iffy".to_string()),
+        TokenType::EOF
+    ]);
+}
+
+#[test]
+fn test_lexer_let_keyword() {
+    let mut lexer = Lexer::new("let x = 1");
+    assert_eq!(tokens_to_token_types(lexer.tokenize().unwrap()), vec![
+        TokenType::Let,
+        TokenType::Identifier("x".to_string()),
+        TokenType::Equal,
+        TokenType::Number(1.0),
+        TokenType::EOF
+    ]);
+}
+
+#[test]
+fn test_lexer_string_escapes() {
+    let mut lexer = Lexer::new(r#""line1\nline2\tend\r\0" "a\"b" "back\\slash""#);
+    let tokens = lexer.tokenize().unwrap();
+    assert_eq!(tokens[0].lexeme, r"line1\nline2\tend\r\0");
+    assert_eq!(tokens_to_token_types(tokens), vec![
+        TokenType::String("line1\nline2\tend\r\0".to_string()),
+        TokenType::String("a\"b".to_string()),
+        TokenType::String("back\\slash".to_string()),
+        TokenType::EOF
+    ]);
+}
+
+#[test]
+fn test_lexer_string_escape_errors() {
+    let mut lexer = Lexer::new("\n  \"ok \\q\"");
+    assert_eq!(lexer.tokenize().map_err(|e| e.to_string()), Err("Unknown escape sequence '\\q' at line 2, column 7".to_string()));
+
+    let mut lexer = Lexer::new(r#""ends in an escaped quote\""#);
+    assert!(lexer.tokenize().unwrap_err().message().starts_with("Unterminated string"));
+    let mut lexer = Lexer::new(r#""ends in a backslash\"#);
+    assert!(lexer.tokenize().unwrap_err().message().starts_with("Unterminated string"));
+}
+
+fn positions(input: &str) -> Vec<(String, usize, usize)> {
+    Lexer::new(input).tokenize().unwrap().into_iter()
+        .map(|token| (token.lexeme, token.line, token.column))
+        .collect()
+}
+
+#[test]
+fn test_lexer_token_positions_point_at_first_character() {
+    let expected = |items: &[(&str, usize, usize)]| -> Vec<(String, usize, usize)> {
+        items.iter().map(|(lexeme, line, column)| (lexeme.to_string(), *line, *column)).collect()
+    };
+    assert_eq!(positions("let s = \"hi\" + 42 <= x1\n  f(0x1F, \"a\\nb\") :: y"), expected(&[
+        ("let", 1, 1), ("s", 1, 5), ("=", 1, 7), ("hi", 1, 9), ("+", 1, 14), ("42", 1, 16),
+        ("<=", 1, 19), ("x1", 1, 22),
+        ("f", 2, 3), ("(", 2, 4), ("0x1F", 2, 5), (",", 2, 9), ("a\\nb", 2, 11), (")", 2, 17),
+        ("::", 2, 19), ("y", 2, 22), ("", 2, 23),
+    ]));
+}
+
+#[test]
+fn test_lexer_multiline_string_position_is_its_opening_quote() {
+    assert_eq!(positions("x \"two\nlines\" y")[1..3], [
+        ("two\nlines".to_string(), 1, 3),
+        ("y".to_string(), 2, 8),
+    ]);
+    assert_eq!(Lexer::new("a\n  \"open").tokenize().map_err(|e| e.to_string()), Err("Unterminated string at line 2, column 3".to_string()));
+}
+
+#[test]
+fn test_lexer_scientific_notation_and_digit_groups() {
+    let mut lexer = Lexer::new("1e3 2.5e-3 1E+6 1_000 1_000.000_5 2e1_0 3e2f");
+    let tokens = lexer.tokenize().unwrap();
+    assert_eq!(tokens[0].kind, TokenType::Number(1000.0));
+    assert_eq!(tokens[3].lexeme, "1_000");
+    assert_eq!(tokens_to_token_types(tokens), vec![
+        TokenType::Number(1000.0),
+        TokenType::Number(0.0025),
+        TokenType::Number(1_000_000.0),
+        TokenType::Number(1000.0),
+        TokenType::Number(1000.0005),
+        TokenType::Number(2e10),
+        TokenType::Number(300.0),
+        TokenType::EOF
+    ]);
+
+    let mut lexer = Lexer::new("1_000i 42.len");
+    lexer.set_int_literals(true);
+    assert_eq!(tokens_to_token_types(lexer.tokenize().unwrap()), vec![
+        TokenType::Integer(1000),
+        TokenType::Identifier("42".to_string()),
+        TokenType::Dot,
+        TokenType::Identifier("len".to_string()),
+        TokenType::EOF
+    ]);
+}
+
+#[test]
+fn test_lexer_rejects_misplaced_underscores_and_exponents() {
+    assert_eq!(Lexer::new("1__0").tokenize().map_err(|e| e.to_string()), Err("Invalid number format at line 1, column 2: '_' must be between two digits".to_string()));
+    assert!(Lexer::new("1_").tokenize().is_err());
+    assert!(Lexer::new("1_.5").tokenize().is_err());
+    assert!(Lexer::new("1.5_").tokenize().is_err());
+    assert!(Lexer::new("1e").tokenize().is_err());
+    assert!(Lexer::new("1e+").tokenize().is_err());
+    assert_eq!(
+        Lexer::new("1e3i").tokenize().map_err(|e| e.to_string()),
+        Err("Invalid integer literal at line 1, column 1: integers cannot have an exponent".to_string())
+    );
+    assert_eq!(
+        tokens_to_token_types(Lexer::new("_1").tokenize().unwrap()),
+        vec![TokenType::Identifier("_1".to_string()), TokenType::EOF]
+    );
+}
@@ -0,0 +1,37 @@
+use pelin::error::PelinError;
+use pelin::interpreter::Value;
+use pelin::{eval, run_source};
+
+#[test]
+fn test_eval_returns_last_value() {
+    assert_eq!(eval("1 + 1"), Ok(Value::Number(2.0)));
+    assert_eq!(eval("let x = 2 return x * 3 x"), Ok(Value::Number(6.0)));
+    assert_eq!(eval(""), Ok(Value::Nun));
+}
+
+#[test]
+fn test_eval_reports_errors() {
+    assert_eq!(eval("x"), Err(PelinError::Runtime { message: "Undefined variable 'x'.".to_string() }));
+    assert!(matches!(eval("1 +"), Err(PelinError::Parse { line: 1, column: 3, .. })));
+    assert!(matches!(eval("let s = \"open"), Err(PelinError::Lex { line: 1, column: 9, .. })));
+}
+
+#[test]
+fn test_run_source_imports_from_project_root() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("feathers")).unwrap();
+    std::fs::write(dir.path().join("feathers/double.pl"), "fn num double(num n) { n * 2 }").unwrap();
+
+    assert_eq!(run_source("imp double\ndouble.double(21)", dir.path().to_path_buf()), Ok(Value::Number(42.0)));
+}
+
+#[test]
+fn test_exit_is_returned_to_the_embedder() {
+    let exited = eval("imp std_process\nstd_process.exit(3)\n1");
+    assert_eq!(exited, Err(PelinError::Exit { code: 3, message: "Program exited with status 3".to_string() }));
+
+    // `try` lets it through, and the next program does not see it
+    let source = "imp std_error\nimp std_process\nfn any leave() { std_process.exit(0) }\nfn any handle(any e) { 1 }\nstd_error.try(leave, handle)";
+    assert!(matches!(eval(source), Err(PelinError::Exit { code: 0, .. })));
+    assert_eq!(eval("x"), Err(PelinError::Runtime { message: "Undefined variable 'x'.".to_string() }));
+}
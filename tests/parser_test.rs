@@ -1,5 +1,6 @@
+use pelin::error::PelinError;
 use pelin::lexer::{Lexer};
-use pelin::parser::{Parser, Expr, Type};
+use pelin::parser::{Parser, Expr, Type, UnaryOperator, BinaryOperator, LogicalOperator};
 
 #[test]
 fn test_parse_number() {
@@ -28,6 +29,50 @@ fn test_parse_identifier() {
     assert_eq!(expr, vec![Expr::Identifier("variable_name".to_string())]);
 }
 
+#[test]
+fn test_parse_array_literal() {
+    let mut lexer = Lexer::new("[1, \"a\", []]");
+    let tokens = lexer.tokenize().unwrap();
+    let mut parser = Parser::new(tokens);
+    let expr = parser.parse().unwrap();
+    assert_eq!(expr, vec![Expr::Array(vec![
+        Expr::Number(1.0),
+        Expr::String("a".to_string()),
+        Expr::Array(vec![]),
+    ])]);
+}
+
+#[test]
+fn test_parse_map_literal() {
+    let mut lexer = Lexer::new("{ \"a\" = 1, \"b\" = true }");
+    let tokens = lexer.tokenize().unwrap();
+    let mut parser = Parser::new(tokens);
+    let expr = parser.parse().unwrap();
+    assert_eq!(expr, vec![Expr::Map(vec![
+        ("a".to_string(), Expr::Number(1.0)),
+        ("b".to_string(), Expr::Boolean(true)),
+    ])]);
+}
+
+#[test]
+fn test_parse_block_or_map() {
+    assert_eq!(parse_single("{}"), Expr::Map(vec![]));
+    assert_eq!(parse_single("{ \"a\" = 1 }"), Expr::Map(vec![("a".to_string(), Expr::Number(1.0))]));
+    assert_eq!(parse_single("{ true = 1, false = 0 }"), Expr::Map(vec![
+        ("true".to_string(), Expr::Number(1.0)),
+        ("false".to_string(), Expr::Number(0.0)),
+    ]));
+    assert_eq!(parse_single("{ \"a\" }"), Expr::Block(vec![Expr::String("a".to_string())]));
+    assert_eq!(parse_single("{ let x = 1 x }"), Expr::Block(vec![
+        Expr::Let { name: "x".to_string(), value: Box::new(Expr::Number(1.0)) },
+        Expr::Identifier("x".to_string()),
+    ]));
+    assert_eq!(parse_single("{ { 1 } }"), Expr::Block(vec![Expr::Block(vec![Expr::Number(1.0)])]));
+
+    let tokens = Lexer::new("{ 1").tokenize().unwrap();
+    assert!(Parser::new(tokens).parse().is_err());
+}
+
 #[test]
 fn test_parse_function_call() {
     let mut lexer = Lexer::new("add(5, 3)");
@@ -83,13 +128,12 @@ fn test_parse_return_statement() {
     let expr = parser.parse().unwrap();
     assert_eq!(expr, vec![Expr::Return(Box::new(Expr::Number(42.0)))]);
 }
-//TODO implement assignments
-/*
+
 #[test]
 fn test_parse_function_with_multiple_statements() {
     let mut lexer = Lexer::new("fn nun test() { x = 5 return x }");
     let tokens = lexer.tokenize().unwrap();
-    let mut parser = Parser::new(tokens_to_token_types(tokens));
+    let mut parser = Parser::new(tokens);
     let expr = parser.parse().unwrap();
     assert_eq!(expr, vec![Expr::FunctionDefinition {
         return_type: Type::Nun,
@@ -104,7 +148,6 @@ fn test_parse_function_with_multiple_statements() {
         ],
     }]);
 }
-*/
 
 #[test]
 fn test_parse_empty_function() {
@@ -126,7 +169,27 @@ fn test_parse_import_statement() {
     let tokens = lexer.tokenize().unwrap();
     let mut parser = Parser::new(tokens);
     let expr = parser.parse().unwrap();
-    assert_eq!(expr, vec![Expr::Import("std_num".to_string())]);
+    assert_eq!(expr, vec![Expr::Import { name: "std_num".to_string(), alias: None }]);
+}
+
+#[test]
+fn test_parse_relative_import_statement() {
+    let mut lexer = Lexer::new("imp .local");
+    let tokens = lexer.tokenize().unwrap();
+    let mut parser = Parser::new(tokens);
+    let expr = parser.parse().unwrap();
+    assert_eq!(expr, vec![Expr::Import { name: ".local".to_string(), alias: None }]);
+}
+
+#[test]
+fn test_parse_aliased_import_statement() {
+    assert_eq!(parse_single("imp std_math as m"), Expr::Import { name: "std_math".to_string(), alias: Some("m".to_string()) });
+    assert_eq!(parse_single("imp .local as l"), Expr::Import { name: ".local".to_string(), alias: Some("l".to_string()) });
+
+    for input in ["imp std_math as", "imp std_math as 1", "imp as m"] {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err(), "{}", input);
+    }
 }
 
 #[test]
@@ -156,7 +219,7 @@ fn test_parse_mixed_expressions() {
     let expr = parser.parse().unwrap();
 
     assert_eq!(expr.len(), 3);
-    assert_eq!(expr[0], Expr::Import("std_num".to_string()));
+    assert_eq!(expr[0], Expr::Import { name: "std_num".to_string(), alias: None });
     match &expr[1] {
         Expr::FunctionDefinition { return_type, name, parameters, body } => {
             assert_eq!(*return_type, Type::Num);
@@ -166,4 +229,409 @@ fn test_parse_mixed_expressions() {
         },
         _ => panic!("Expected FunctionDefinition"),
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_validate_correct_program() {
+    let input = r#"
+        imp std_num
+        fn num add(num a, num b) { return RUST[std_func::add](a, b) }
+        add(1, [2, 3])
+    "#;
+    let tokens = Lexer::new(input).tokenize().unwrap();
+    let expr = Parser::new(tokens).parse().unwrap();
+    assert_eq!(Expr::validate_program(&expr), Ok(()));
+}
+
+#[test]
+fn test_validate_malformed_ast() {
+    let malformed = Expr::FunctionDefinition {
+        return_type: Type::Num,
+        name: "broken".to_string(),
+        parameters: vec![(Type::Num, "a".to_string()), (Type::Num, "a".to_string())],
+        body: vec![
+            Box::new(Expr::Import { name: "std_num".to_string(), alias: None }),
+            Box::new(Expr::RustFunctionCall { path: vec!["add".to_string()], arguments: vec![] }),
+        ],
+    };
+    let errors = malformed.validate().unwrap_err();
+    assert_eq!(errors, vec![
+        "Duplicate parameter 'a' in function 'broken'".to_string(),
+        "Import of 'std_num' is only allowed at the top level".to_string(),
+        "Rust function path 'add' needs at least two segments".to_string(),
+    ]);
+
+    // The interpreter ends the program at a top-level `return`, so it is valid
+    let top_level_return = Expr::Return(Box::new(Expr::Number(1.0)));
+    assert_eq!(top_level_return.validate(), Ok(()));
+    let tokens = Lexer::new("if (true) { return 1 }\n2").tokenize().unwrap();
+    assert_eq!(Expr::validate_program(&Parser::new(tokens).parse().unwrap()), Ok(()));
+}
+
+#[test]
+fn test_parse_empty_program() {
+    for input in ["", "  \n\t ", "// only a comment\n"] {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        assert_eq!(parser.parse().unwrap(), vec![]);
+    }
+}
+
+fn parse_single(input: &str) -> Expr {
+    let tokens = Lexer::new(input).tokenize().unwrap();
+    let mut expressions = Parser::new(tokens).parse().unwrap();
+    assert_eq!(expressions.len(), 1);
+    expressions.remove(0)
+}
+
+fn unary(operator: UnaryOperator, operand: Expr) -> Expr {
+    Expr::Unary { operator, operand: Box::new(operand) }
+}
+
+#[test]
+fn test_parse_consecutive_unary_operators_nest_right() {
+    assert_eq!(parse_single("!!true"), unary(UnaryOperator::Not, unary(UnaryOperator::Not, Expr::Boolean(true))));
+    assert_eq!(parse_single("--5"), unary(UnaryOperator::Negate, unary(UnaryOperator::Negate, Expr::Number(5.0))));
+    assert_eq!(
+        parse_single("-!x"),
+        unary(UnaryOperator::Negate, unary(UnaryOperator::Not, Expr::Identifier("x".to_string())))
+    );
+}
+
+#[test]
+fn test_parse_unary_applies_to_whole_call() {
+    let expected = unary(UnaryOperator::Negate, Expr::FunctionCall {
+        callee: Box::new(Expr::Identifier("f".to_string())),
+        arguments: vec![],
+    });
+    assert_eq!(parse_single("-f()"), expected);
+    assert!(matches!(parse_single("-RUST[std_func::add](1, 2)"), Expr::Unary { operator: UnaryOperator::Negate, .. }));
+}
+
+#[test]
+fn test_parse_dangling_unary_operator_is_error() {
+    let tokens = Lexer::new("!").tokenize().unwrap();
+    assert!(Parser::new(tokens).parse().is_err());
+}
+
+#[test]
+fn test_parse_feather_function_reference() {
+    assert_eq!(parse_single("std_math.sin"), Expr::FeatherFunction {
+        feather: "std_math".to_string(),
+        function: "sin".to_string(),
+    });
+}
+
+#[test]
+fn test_parse_feather_function_call() {
+    let call = Expr::FeatherFunctionCall {
+        feather: "std_math".to_string(),
+        function: "add".to_string(),
+        arguments: vec![Expr::Number(5.0), Expr::Number(3.0)],
+    };
+    assert_eq!(parse_single("std_math.add(5, 3)"), call);
+    assert_eq!(parse_single("std_math.add(5, 3)(1)"), Expr::FunctionCall {
+        callee: Box::new(call.clone()),
+        arguments: vec![Expr::Number(1.0)],
+    });
+    assert_eq!(parse_single("std_math.add(5, 3)[0]"), Expr::Index {
+        target: Box::new(call),
+        index: Box::new(Expr::Number(0.0)),
+    });
+
+    let tokens = Lexer::new("\"a\".x").tokenize().unwrap();
+    assert_eq!(Parser::new(tokens).parse(), Err(PelinError::Parse {
+        message: "[line 1, column 4] Error at '.': Expected feather name before '.'".to_string(),
+        line: 1,
+        column: 4,
+    }));
+    for input in ["f().g", "std_math.(1)", "std_math.add(1"] {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_parse_include_statement() {
+    assert_eq!(parse_single("inc \"lib/helpers.pl\""), Expr::Include("lib/helpers.pl".to_string()));
+    let tokens = Lexer::new("inc helpers").tokenize().unwrap();
+    assert!(Parser::new(tokens).parse().is_err());
+}
+
+fn binary(operator: BinaryOperator, left: Expr, right: Expr) -> Expr {
+    Expr::Binary { operator, left: Box::new(left), right: Box::new(right) }
+}
+
+#[test]
+fn test_parse_multiplication_binds_tighter_than_addition() {
+    let expected = binary(
+        BinaryOperator::Add,
+        Expr::Number(2.0),
+        binary(BinaryOperator::Multiply, Expr::Number(3.0), Expr::Number(4.0)),
+    );
+    assert_eq!(parse_single("2 + 3 * 4"), expected);
+
+    let expected = binary(
+        BinaryOperator::Add,
+        binary(BinaryOperator::Multiply, Expr::Number(2.0), Expr::Number(3.0)),
+        Expr::Number(4.0),
+    );
+    assert_eq!(parse_single("2 * 3 + 4"), expected);
+}
+
+#[test]
+fn test_parse_binary_operators_are_left_associative() {
+    let expected = binary(
+        BinaryOperator::Subtract,
+        binary(BinaryOperator::Subtract, Expr::Number(1.0), Expr::Number(2.0)),
+        Expr::Number(3.0),
+    );
+    assert_eq!(parse_single("1 - 2 - 3"), expected);
+}
+
+#[test]
+fn test_parse_comparison_binds_looser_than_arithmetic() {
+    let expected = binary(
+        BinaryOperator::Equal,
+        binary(
+            BinaryOperator::Less,
+            binary(BinaryOperator::Add, Expr::Identifier("a".to_string()), Expr::Number(1.0)),
+            binary(BinaryOperator::Remainder, Expr::Identifier("b".to_string()), Expr::Number(2.0)),
+        ),
+        Expr::Boolean(true),
+    );
+    assert_eq!(parse_single("a + 1 < b % 2 == true"), expected);
+}
+
+#[test]
+fn test_parse_binary_operands_are_calls_and_unary_expressions() {
+    let expected = binary(
+        BinaryOperator::Subtract,
+        Expr::FunctionCall {
+            callee: Box::new(Expr::Identifier("f".to_string())),
+            arguments: vec![binary(BinaryOperator::Divide, Expr::Number(1.0), Expr::Number(2.0))],
+        },
+        unary(UnaryOperator::Negate, Expr::Number(3.0)),
+    );
+    assert_eq!(parse_single("f(1 / 2) - -3"), expected);
+}
+
+#[test]
+fn test_parse_logical_operators_bind_looser_than_equality() {
+    let logical = |operator, left, right| Expr::Logical { operator, left: Box::new(left), right: Box::new(right) };
+    let expected = logical(
+        LogicalOperator::Or,
+        Expr::Identifier("a".to_string()),
+        logical(
+            LogicalOperator::And,
+            binary(BinaryOperator::Equal, Expr::Identifier("b".to_string()), Expr::Number(1.0)),
+            unary(UnaryOperator::Not, Expr::Identifier("c".to_string())),
+        ),
+    );
+    assert_eq!(parse_single("a || b == 1 && !c"), expected);
+}
+
+#[test]
+fn test_parse_dangling_binary_operator_is_error() {
+    let tokens = Lexer::new("1 +").tokenize().unwrap();
+    assert!(Parser::new(tokens).parse().is_err());
+}
+
+#[test]
+fn test_parse_if_else_if_chain() {
+    let expected = Expr::If {
+        condition: Box::new(Expr::Identifier("a".to_string())),
+        then_branch: vec![Expr::Number(1.0)],
+        else_branch: Some(vec![Expr::If {
+            condition: Box::new(Expr::Identifier("b".to_string())),
+            then_branch: vec![Expr::Number(2.0)],
+            else_branch: Some(vec![Expr::Number(3.0)]),
+            line: 1,
+            column: 23,
+        }]),
+        line: 1,
+        column: 5,
+    };
+    assert_eq!(parse_single("if (a) { 1 } else if (b) { 2 } else { 3 }"), expected);
+
+    let expected = Expr::If {
+        condition: Box::new(binary(BinaryOperator::Less, Expr::Identifier("x".to_string()), Expr::Number(0.0))),
+        then_branch: vec![],
+        else_branch: None,
+        line: 1,
+        column: 5,
+    };
+    assert_eq!(parse_single("if (x < 0) {}"), expected);
+}
+
+#[test]
+fn test_parse_malformed_if_is_error() {
+    for input in ["if a { 1 }", "if (a) 1", "if (a) { 1 } else 2", "if (a) { 1"] {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_parse_conditional_expression() {
+    let conditional = |condition: &str, column, then_value, else_value| Expr::If {
+        condition: Box::new(Expr::Identifier(condition.to_string())),
+        then_branch: vec![then_value],
+        else_branch: Some(vec![else_value]),
+        line: 1,
+        column,
+    };
+    assert_eq!(parse_single("a ? 1 : 2"), conditional("a", 1, Expr::Number(1.0), Expr::Number(2.0)));
+    assert_eq!(
+        parse_single("a ? 1 : b ? 2 : 3"),
+        conditional("a", 1, Expr::Number(1.0), conditional("b", 9, Expr::Number(2.0), Expr::Number(3.0))),
+    );
+    assert_eq!(parse_single("x = a ? 1 : 2"), Expr::Assignment {
+        name: "x".to_string(),
+        value: Box::new(conditional("a", 5, Expr::Number(1.0), Expr::Number(2.0))),
+    });
+
+    for input in ["a ? 1", "a ? 1 :", "a : 1", "? 1 : 2"] {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_parse_while_loop() {
+    let expected = Expr::While {
+        condition: Box::new(binary(BinaryOperator::Less, Expr::Identifier("i".to_string()), Expr::Number(3.0))),
+        body: vec![Expr::FunctionCall {
+            callee: Box::new(Expr::Identifier("step".to_string())),
+            arguments: vec![],
+        }],
+        line: 1,
+        column: 8,
+    };
+    assert_eq!(parse_single("while (i < 3) { step() }"), expected);
+
+    for input in ["while i { 1 }", "while (i) 1", "while (i) { 1"] {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_parse_for_loop() {
+    let expected = Expr::ForEach {
+        variable: "x".to_string(),
+        iterable: Box::new(Expr::Identifier("xs".to_string())),
+        body: vec![Expr::FunctionCall {
+            callee: Box::new(Expr::Identifier("step".to_string())),
+            arguments: vec![Expr::Identifier("x".to_string())],
+        }],
+    };
+    assert_eq!(parse_single("for (x in xs) { step(x) }"), expected);
+
+    for input in ["for x in xs { 1 }", "for (x xs) { 1 }", "for (1 in xs) { 1 }", "for (x in xs) 1", "for (x in xs) { 1"] {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_parse_let_and_assignment() {
+    assert_eq!(parse_single("let x = 1 + 2"), Expr::Let {
+        name: "x".to_string(),
+        value: Box::new(binary(BinaryOperator::Add, Expr::Number(1.0), Expr::Number(2.0))),
+    });
+    assert_eq!(parse_single("a = b = 1"), Expr::Assignment {
+        name: "a".to_string(),
+        value: Box::new(Expr::Assignment { name: "b".to_string(), value: Box::new(Expr::Number(1.0)) }),
+    });
+    assert_eq!(parse_single("a == 1"), binary(BinaryOperator::Equal, Expr::Identifier("a".to_string()), Expr::Number(1.0)));
+
+    for input in ["let = 1", "let x 1", "let x ="] {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_parse_index_after_primary_and_call() {
+    let call = Expr::FunctionCall { callee: Box::new(Expr::Identifier("foo".to_string())), arguments: vec![] };
+    assert_eq!(parse_single("foo()[1]"), Expr::Index { target: Box::new(call), index: Box::new(Expr::Number(1.0)) });
+
+    let inner = Expr::Index {
+        target: Box::new(Expr::Identifier("xs".to_string())),
+        index: Box::new(binary(BinaryOperator::Add, Expr::Identifier("i".to_string()), Expr::Number(1.0))),
+    };
+    assert_eq!(parse_single("xs[i + 1][0]"), Expr::Index { target: Box::new(inner), index: Box::new(Expr::Number(0.0)) });
+}
+
+#[test]
+fn test_parse_parentheses_group_operands() {
+    let comparison = binary(BinaryOperator::Less, Expr::Number(3.0), Expr::Number(5.0));
+    assert_eq!(parse_single("!(3 < 5)"), unary(UnaryOperator::Not, comparison));
+
+    let sum = binary(BinaryOperator::Add, Expr::Number(1.0), Expr::Number(2.0));
+    assert_eq!(parse_single("-(1 + 2) * 3"), binary(BinaryOperator::Multiply, unary(UnaryOperator::Negate, sum), Expr::Number(3.0)));
+
+    let tokens = Lexer::new("(1").tokenize().unwrap();
+    assert!(Parser::new(tokens).parse().is_err());
+}
+
+#[test]
+fn test_parse_parentheses_on_next_line_are_not_a_call() {
+    let tokens = Lexer::new("x\n(1)").tokenize().unwrap();
+    assert_eq!(Parser::new(tokens).parse().unwrap(), vec![
+        Expr::Identifier("x".to_string()),
+        Expr::Number(1.0),
+    ]);
+}
+
+#[test]
+fn test_parse_array_on_next_line_is_not_an_index() {
+    let tokens = Lexer::new("xs\n[1, 2]").tokenize().unwrap();
+    assert_eq!(Parser::new(tokens).parse().unwrap(), vec![
+        Expr::Identifier("xs".to_string()),
+        Expr::Array(vec![Expr::Number(1.0), Expr::Number(2.0)]),
+    ]);
+}
+
+#[test]
+fn test_parse_minus_on_next_line_is_not_a_subtraction() {
+    let tokens = Lexer::new("let x = 5\n-x").tokenize().unwrap();
+    assert_eq!(Parser::new(tokens).parse().unwrap(), vec![
+        Expr::Let { name: "x".to_string(), value: Box::new(Expr::Number(5.0)) },
+        unary(UnaryOperator::Negate, Expr::Identifier("x".to_string())),
+    ]);
+    // Within a line, and after an operator that needs its right operand, it still subtracts
+    let tokens = Lexer::new("5 -\nx").tokenize().unwrap();
+    assert!(matches!(Parser::new(tokens).parse().unwrap()[..], [Expr::Binary { operator: BinaryOperator::Subtract, .. }]));
+}
+
+#[test]
+fn test_parse_reports_every_independent_error() {
+    let input = "let = 5\nx = 1\nfn num f() {\n    1 +\n}\ny = )\nf()";
+    let tokens = Lexer::new(input).tokenize().unwrap();
+    let errors: Vec<String> = Parser::new(tokens).parse_all().unwrap_err().iter().map(|e| e.to_string()).collect();
+    assert_eq!(errors, vec![
+        "[line 1, column 5] Error at '=': Expected variable name after 'let'".to_string(),
+        "[line 5, column 1] Error at '}': Unexpected token".to_string(),
+        "[line 6, column 5] Error at ')': Unexpected token".to_string(),
+    ]);
+
+    // One error for all of them, at the position of the first
+    let tokens = Lexer::new(input).tokenize().unwrap();
+    let error = Parser::new(tokens).parse().unwrap_err();
+    assert!(matches!(error, PelinError::Parse { line: 1, column: 5, .. }), "{:?}", error);
+    assert_eq!(error.to_string(), errors.join("\n"));
+}
+
+#[test]
+fn test_parse_skips_the_rest_of_a_failed_block() {
+    // The error inside the block must not cause follow-up errors for the block's remaining lines
+    let input = "fn num g() {\n    if (true) { ) } else { 2 }\n    3\n}\ng()\n1 +";
+    let tokens = Lexer::new(input).tokenize().unwrap();
+    let errors: Vec<String> = Parser::new(tokens).parse_all().unwrap_err().iter().map(|e| e.to_string()).collect();
+    assert_eq!(errors, vec![
+        "[line 2, column 17] Error at ')': Unexpected token".to_string(),
+        "[line 6, column 3] Error at '+': Unexpected token".to_string(),
+    ]);
+}
@@ -0,0 +1,635 @@
+use pelin::interpreter::{Shared, Value};
+use pelin::std_functions::*;
+
+fn array(values: &[f64]) -> Value {
+    Value::Array(Shared::new(values.iter().map(|n| Value::Number(*n)).collect()))
+}
+
+#[test]
+fn test_format_number_groups_thousands() {
+    let result = std_num_format_number(vec![Value::Number(1000000.0), Value::String(",".to_string())]);
+    assert_eq!(result, Ok(Value::String("1,000,000".to_string())));
+}
+
+#[test]
+fn test_format_number_with_decimals() {
+    let args = vec![
+        Value::Number(-1234567.25),
+        Value::String(".".to_string()),
+        Value::String(",".to_string()),
+    ];
+    assert_eq!(std_num_format_number(args), Ok(Value::String("-1.234.567,25".to_string())));
+
+    let result = std_num_format_number(vec![Value::Number(999.5), Value::String(",".to_string())]);
+    assert_eq!(result, Ok(Value::String("999.5".to_string())));
+}
+
+#[test]
+fn test_format_number_rejects_non_numbers() {
+    let result = std_num_format_number(vec![Value::String("1000".to_string()), Value::String(",".to_string())]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_string_eq_ignore_case() {
+    let args = vec![Value::String("Hello".to_string()), Value::String("HELLO".to_string())];
+    assert_eq!(std_string_eq_ignore_case(args), Ok(Value::Boolean(true)));
+
+    let args = vec![Value::String("Straße".to_string()), Value::String("STRASSE".to_string())];
+    assert_eq!(std_string_eq_ignore_case(args), Ok(Value::Boolean(false)));
+}
+
+#[test]
+fn test_string_to_title_case() {
+    let result = std_string_to_title_case(vec![Value::String("hello wORLD  from élan".to_string())]);
+    assert_eq!(result, Ok(Value::String("Hello World  From Élan".to_string())));
+}
+
+#[test]
+fn test_array_unique_keeps_first_occurrence() {
+    assert_eq!(std_array_unique(vec![array(&[1.0, 1.0, 2.0, 3.0, 3.0])]), Ok(array(&[1.0, 2.0, 3.0])));
+    assert_eq!(std_array_unique(vec![array(&[3.0, 1.0, 3.0])]), Ok(array(&[3.0, 1.0])));
+}
+
+#[test]
+fn test_array_dedup_removes_consecutive_duplicates() {
+    assert_eq!(std_array_dedup(vec![array(&[1.0, 1.0, 2.0, 1.0])]), Ok(array(&[1.0, 2.0, 1.0])));
+}
+
+#[test]
+fn test_array_set_operations() {
+    let a = array(&[1.0, 2.0, 3.0, 2.0]);
+    let b = array(&[2.0, 3.0, 4.0]);
+    assert_eq!(std_array_intersection(vec![a.clone(), b.clone()]), Ok(array(&[2.0, 3.0])));
+    assert_eq!(std_array_union(vec![a.clone(), b.clone()]), Ok(array(&[1.0, 2.0, 3.0, 4.0])));
+    assert_eq!(std_array_difference(vec![a, b]), Ok(array(&[1.0])));
+}
+
+#[test]
+fn test_array_set_operations_treat_integers_and_floats_of_the_same_value_as_equal() {
+    let mixed = |values: &[Value]| Value::Array(Shared::new(values.to_vec()));
+    let one = mixed(&[Value::Integer(1), Value::Number(1.0)]);
+    assert_eq!(std_array_unique(vec![one.clone()]), Ok(mixed(&[Value::Integer(1)])));
+    assert_eq!(std_array_dedup(vec![one]), Ok(mixed(&[Value::Integer(1)])));
+    let integers = mixed(&[Value::Integer(1), Value::Integer(2)]);
+    let floats = mixed(&[Value::Number(1.0)]);
+    assert_eq!(std_array_intersection(vec![integers.clone(), floats.clone()]), Ok(mixed(&[Value::Integer(1)])));
+    assert_eq!(std_array_union(vec![integers.clone(), floats.clone()]), Ok(mixed(&[Value::Integer(1), Value::Integer(2)])));
+    assert_eq!(std_array_difference(vec![integers, floats]), Ok(mixed(&[Value::Integer(2)])));
+}
+
+#[test]
+fn test_math_min_max() {
+    assert_eq!(std_math_min(vec![Value::Number(3.0), Value::Number(5.0)]), Ok(Value::Number(3.0)));
+    assert_eq!(std_math_max(vec![Value::Number(3.0), Value::Number(5.0)]), Ok(Value::Number(5.0)));
+    assert!(std_math_min(vec![Value::Number(3.0)]).is_err());
+    assert!(std_math_max(vec![Value::Number(3.0), Value::Nun]).is_err());
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+#[test]
+fn test_string_trim_start_and_end() {
+    assert_eq!(std_string_trim_start(vec![string("  hi  ")]), Ok(string("hi  ")));
+    assert_eq!(std_string_trim_end(vec![string("  hi  ")]), Ok(string("  hi")));
+    assert_eq!(std_string_trim_start(vec![string("hi")]), Ok(string("hi")));
+}
+
+#[test]
+fn test_string_strip_prefix() {
+    assert_eq!(std_string_strip_prefix(vec![string("prefix_name"), string("prefix_")]), Ok(string("name")));
+    assert_eq!(std_string_strip_prefix(vec![string("name"), string("prefix_")]), Ok(string("name")));
+}
+
+#[test]
+fn test_string_strip_suffix() {
+    assert_eq!(std_string_strip_suffix(vec![string("file.pl"), string(".pl")]), Ok(string("file")));
+    assert_eq!(std_string_strip_suffix(vec![string("file.rs"), string(".pl")]), Ok(string("file.rs")));
+}
+
+#[test]
+fn test_ansi_wrap() {
+    assert_eq!(ansi_wrap("hi", &[31], true), "\x1b[31mhi\x1b[0m");
+    assert_eq!(ansi_wrap("hi", &[1, 4], true), "\x1b[1;4mhi\x1b[0m");
+    assert_eq!(ansi_wrap("hi", &[31], false), "hi");
+}
+
+#[test]
+fn test_print_colored_returns_nun() {
+    assert_eq!(std_io_print_colored(vec![string(""), string("green")]), Ok(Value::Nun));
+    assert!(std_io_print_colored(vec![string("x"), string("chartreuse")]).is_err());
+}
+
+#[test]
+fn test_type_predicates() {
+    let map = Value::Map(Shared::new(Default::default()));
+    let feather_function = Value::FeatherFunction("std_num".to_string(), "add".to_string());
+    type StdFunction = fn(Vec<Value>) -> Result<Value, String>;
+    let cases: Vec<(StdFunction, Value)> = vec![
+        (std_type_is_num, Value::Number(1.0)),
+        (std_type_is_str, string("s")),
+        (std_type_is_bool, Value::Boolean(false)),
+        (std_type_is_nun, Value::Nun),
+        (std_type_is_array, array(&[1.0])),
+        (std_type_is_map, map),
+        (std_type_is_function, feather_function),
+    ];
+    for (i, (predicate, value)) in cases.iter().enumerate() {
+        assert_eq!(predicate(vec![value.clone()]), Ok(Value::Boolean(true)), "{:?}", value);
+        let other = &cases[(i + 1) % cases.len()].1;
+        assert_eq!(predicate(vec![other.clone()]), Ok(Value::Boolean(false)), "{:?}", other);
+    }
+}
+
+fn map(entries: &[(&str, f64)]) -> Value {
+    Value::Map(Shared::new(entries.iter().map(|(k, v)| (k.to_string(), Value::Number(*v))).collect()))
+}
+
+#[test]
+fn test_map_merge_overrides_overlapping_key() {
+    let a = map(&[("x", 1.0), ("y", 2.0)]);
+    let b = map(&[("y", 20.0), ("z", 30.0)]);
+    let merged = std_map_merge(vec![a.clone(), b]).unwrap();
+    assert_eq!(merged, map(&[("x", 1.0), ("y", 20.0), ("z", 30.0)]));
+    assert_eq!(a, map(&[("x", 1.0), ("y", 2.0)]));
+}
+
+#[test]
+fn test_map_remove_existing_and_missing_key() {
+    let m = map(&[("x", 1.0)]);
+    assert_eq!(std_map_remove(vec![m.clone(), Value::String("x".to_string())]), Ok(Value::Number(1.0)));
+    assert_eq!(m, map(&[]));
+    assert_eq!(std_map_remove(vec![m, Value::String("x".to_string())]), Ok(Value::Nun));
+}
+
+#[test]
+fn test_bit_helpers() {
+    assert_eq!(std_num_bit_count(vec![Value::Integer(7)]), Ok(Value::Integer(3)));
+    assert_eq!(std_num_trailing_zeros(vec![Value::Integer(8)]), Ok(Value::Integer(3)));
+    assert_eq!(std_num_leading_zeros(vec![Value::Integer(1)]), Ok(Value::Integer(63)));
+    assert_eq!(std_num_bit_count(vec![Value::Integer(-1)]), Ok(Value::Integer(64)));
+    assert!(std_num_bit_count(vec![Value::Number(7.0)]).is_err());
+}
+
+#[test]
+fn test_integer_range_helpers() {
+    let ints = |values: &[i64]| values.iter().map(|n| Value::Integer(*n)).collect::<Vec<_>>();
+    assert_eq!(std_num_clamp_int(ints(&[10, 0, 5])), Ok(Value::Integer(5)));
+    assert_eq!(std_num_clamp_int(ints(&[-3, 0, 5])), Ok(Value::Integer(0)));
+    assert!(std_num_clamp_int(ints(&[1, 5, 0])).is_err());
+    assert!(std_num_clamp_int(vec![Value::Number(10.0), Value::Integer(0), Value::Integer(5)]).is_err());
+    assert_eq!(std_num_abs_int(ints(&[-7])), Ok(Value::Integer(7)));
+    assert!(std_num_abs_int(ints(&[i64::MIN])).is_err());
+    assert_eq!(std_num_wrapping_add(ints(&[i64::MAX, 1])), Ok(Value::Integer(i64::MIN)));
+    assert_eq!(std_num_wrapping_add(ints(&[2, 3])), Ok(Value::Integer(5)));
+}
+
+#[test]
+fn test_string_count() {
+    assert_eq!(std_string_count(vec![string("ababab"), string("ab")]), Ok(Value::Number(3.0)));
+    assert_eq!(std_string_count(vec![string("aaa"), string("aa")]), Ok(Value::Number(1.0)));
+    assert!(std_string_count(vec![string("aaa"), string("")]).is_err());
+}
+
+#[test]
+fn test_string_lines_handles_crlf() {
+    let result = std_string_lines(vec![string("one\r\ntwo\nthree\r\n")]);
+    let expected = Value::Array(Shared::new(vec![string("one"), string("two"), string("three")]));
+    assert_eq!(result, Ok(expected));
+}
+
+#[test]
+fn test_array_splice_removes_middle_range() {
+    let values = array(&[1.0, 2.0, 3.0, 4.0]);
+    let removed = std_array_splice(vec![values.clone(), Value::Number(1.0), Value::Number(2.0), array(&[])]);
+    assert_eq!(removed, Ok(array(&[2.0, 3.0])));
+    assert_eq!(values, array(&[1.0, 4.0]));
+}
+
+#[test]
+fn test_array_splice_inserts_without_removing() {
+    let values = array(&[1.0, 4.0]);
+    let removed = std_array_splice(vec![values.clone(), Value::Integer(1), Value::Integer(0), array(&[2.0, 3.0])]);
+    assert_eq!(removed, Ok(array(&[])));
+    assert_eq!(values, array(&[1.0, 2.0, 3.0, 4.0]));
+}
+
+#[test]
+fn test_array_splice_out_of_range() {
+    let values = array(&[1.0, 2.0]);
+    assert!(std_array_splice(vec![values.clone(), Value::Number(1.0), Value::Number(2.0), array(&[])]).is_err());
+    assert!(std_array_splice(vec![values.clone(), Value::Number(3.0), Value::Number(0.0), array(&[])]).is_err());
+    assert_eq!(
+        std_array_splice(vec![values.clone(), Value::Number(0.5), Value::Number(0.0), array(&[])]),
+        Err("splice function expects a non-negative whole number, got 0.5".to_string())
+    );
+    assert!(std_array_splice(vec![values.clone(), Value::Number(1.0), Value::Number(1e20), array(&[])]).is_err());
+    assert!(std_array_splice(vec![values.clone(), Value::Number(1.0), Value::Number(1e19), array(&[])]).is_err());
+    assert_eq!(values, array(&[1.0, 2.0]));
+}
+
+#[test]
+fn test_round_even() {
+    assert_eq!(std_num_round_even(vec![Value::Number(2.5)]), Ok(Value::Number(2.0)));
+    assert_eq!(std_num_round_even(vec![Value::Number(3.5)]), Ok(Value::Number(4.0)));
+    assert_eq!(std_num_round_even(vec![Value::Number(2.4)]), Ok(Value::Number(2.0)));
+    assert_eq!(std_num_round_even(vec![Value::Number(-2.5)]), Ok(Value::Number(-2.0)));
+    assert_eq!(std_num_round_even(vec![Value::Integer(7)]), Ok(Value::Integer(7)));
+}
+
+#[test]
+fn test_base64_round_trip() {
+    let encoded = std_encoding_base64_encode(vec![string("Pelikan ü")]).unwrap();
+    assert_eq!(encoded, string("UGVsaWthbiDDvA=="));
+    assert_eq!(std_encoding_base64_decode(vec![encoded]), Ok(string("Pelikan ü")));
+}
+
+#[test]
+fn test_hex_round_trip() {
+    let encoded = std_encoding_hex_encode(vec![string("Hi!")]).unwrap();
+    assert_eq!(encoded, string("486921"));
+    assert_eq!(std_encoding_hex_decode(vec![encoded]), Ok(string("Hi!")));
+    assert_eq!(std_encoding_hex_decode(vec![string("4A4b")]), Ok(string("JK")));
+}
+
+#[test]
+fn test_decode_invalid_input() {
+    assert!(std_encoding_base64_decode(vec![string("not base64!")]).unwrap_err().contains("invalid base64"));
+    assert!(std_encoding_hex_decode(vec![string("abc")]).unwrap_err().contains("odd number of digits"));
+    assert!(std_encoding_hex_decode(vec![string("zz")]).unwrap_err().contains("invalid hex digits"));
+    assert!(std_encoding_hex_decode(vec![string("ff")]).unwrap_err().contains("not valid UTF-8"));
+}
+
+#[test]
+fn test_array_chunk() {
+    let chunks = std_array_chunk(vec![array(&[1.0, 2.0, 3.0, 4.0, 5.0]), Value::Number(2.0)]);
+    assert_eq!(chunks, Ok(Value::Array(Shared::new(vec![array(&[1.0, 2.0]), array(&[3.0, 4.0]), array(&[5.0])]))));
+    assert_eq!(std_array_chunk(vec![array(&[]), Value::Integer(3)]), Ok(array(&[])));
+}
+
+#[test]
+fn test_array_window() {
+    let windows = std_array_window(vec![array(&[1.0, 2.0, 3.0]), Value::Number(2.0)]);
+    assert_eq!(windows, Ok(Value::Array(Shared::new(vec![array(&[1.0, 2.0]), array(&[2.0, 3.0])]))));
+    assert_eq!(std_array_window(vec![array(&[1.0]), Value::Number(2.0)]), Ok(array(&[])));
+}
+
+#[test]
+fn test_array_chunk_and_window_reject_size_zero() {
+    assert_eq!(
+        std_array_chunk(vec![array(&[1.0]), Value::Number(0.0)]),
+        Err("chunk function expects a size of at least 1".to_string())
+    );
+    assert_eq!(
+        std_array_window(vec![array(&[1.0]), Value::Integer(0)]),
+        Err("window function expects a size of at least 1".to_string())
+    );
+}
+
+#[test]
+fn test_negative_zero() {
+    assert_eq!(Value::Number(-0.0), Value::Number(0.0));
+    assert_eq!(std_num_is_negative_zero(vec![Value::Number(-0.0)]), Ok(Value::Boolean(true)));
+    assert_eq!(std_num_is_negative_zero(vec![Value::Number(0.0)]), Ok(Value::Boolean(false)));
+    assert_eq!(std_num_is_negative_zero(vec![Value::Number(-1.0)]), Ok(Value::Boolean(false)));
+    assert_eq!(std_num_is_negative_zero(vec![Value::Integer(0)]), Ok(Value::Boolean(false)));
+    assert!(std_num_is_negative_zero(vec![Value::String("-0".to_string())]).is_err());
+}
+
+#[test]
+fn test_negative_zero_formatting() {
+    assert_eq!(Value::Number(-0.0).to_string(), "-0");
+    assert_eq!(std_convert_to_string(vec![Value::Number(-0.0)]), Ok(Value::String("-0".to_string())));
+    assert_eq!(
+        std_num_format_number(vec![Value::Number(-0.0), Value::String(",".to_string()), Value::String(".".to_string())]),
+        Ok(Value::String("0".to_string()))
+    );
+}
+
+#[test]
+fn test_parse_int_with_radix() {
+    let parse = |s: &str, radix: f64| std_convert_parse_int(vec![string(s), Value::Number(radix)]);
+    assert_eq!(parse("ff", 16.0), Ok(Value::Integer(255)));
+    assert_eq!(parse("-101", 2.0), Ok(Value::Integer(-5)));
+    assert_eq!(parse("42", 10.0), Ok(Value::Integer(42)));
+    assert_eq!(parse("ffg", 16.0), Err("parse_int function found invalid character 'g' at index 2 in \"ffg\"".to_string()));
+    assert!(parse("", 10.0).is_err());
+    assert!(parse("10", 37.0).is_err());
+    assert!(parse("99999999999999999999", 10.0).is_err());
+}
+
+#[test]
+fn test_parse_float() {
+    assert_eq!(std_convert_parse_float(vec![string("3.25")]), Ok(Value::Number(3.25)));
+    assert_eq!(std_convert_parse_float(vec![string("-1.5e3")]), Ok(Value::Number(-1500.0)));
+    assert_eq!(
+        std_convert_parse_float(vec![string("1.2.3")]),
+        Err("parse_float function found invalid character '.' at index 3 in \"1.2.3\"".to_string())
+    );
+    assert!(std_convert_parse_float(vec![string("1e")]).is_err());
+    assert!(std_convert_parse_float(vec![string("abc")]).is_err());
+}
+
+#[test]
+fn test_array_fill() {
+    assert_eq!(std_array_fill(vec![Value::Number(0.0), Value::Number(3.0)]), Ok(array(&[0.0, 0.0, 0.0])));
+    assert_eq!(std_array_fill(vec![string("x"), Value::Integer(0)]), Ok(array(&[])));
+    assert_eq!(
+        std_array_fill(vec![Value::Number(0.0), Value::Number(-1.0)]),
+        Err("fill function expects a non-negative whole number, got -1".to_string())
+    );
+    assert!(std_array_fill(vec![Value::Number(0.0), Value::Number(1e20)]).is_err());
+    assert!(std_array_fill(vec![Value::Number(0.0), Value::Number(1e18)]).is_err());
+}
+
+#[test]
+fn test_array_range_to_array() {
+    assert_eq!(std_array_range_to_array(vec![Value::Number(1.0), Value::Number(4.0)]), Ok(array(&[1.0, 2.0, 3.0])));
+    assert_eq!(
+        std_array_range_to_array(vec![Value::Integer(-1), Value::Integer(1)]),
+        Ok(Value::Array(Shared::new(vec![Value::Integer(-1), Value::Integer(0)])))
+    );
+    assert_eq!(std_array_range_to_array(vec![Value::Number(4.0), Value::Number(1.0)]), Ok(array(&[])));
+    assert!(std_array_range_to_array(vec![Value::Number(0.5), Value::Number(3.0)]).is_err());
+    assert!(std_array_range_to_array(vec![Value::Number(0.0), Value::Number(1e20)]).is_err());
+    assert!(std_array_range_to_array(vec![Value::Integer(i64::MIN), Value::Integer(i64::MAX)]).is_err());
+}
+
+#[test]
+fn test_sort_unicode_differs_from_byte_order() {
+    let words = ["zebra", "Éclair", "apple", "eclair"];
+    let strings = |words: &[&str]| Value::Array(Shared::new(words.iter().map(|w| string(w)).collect()));
+
+    let mut byte_order = words;
+    byte_order.sort();
+    assert_eq!(byte_order, ["apple", "eclair", "zebra", "Éclair"]);
+
+    let collated = std_string_sort_unicode(vec![strings(&words)]);
+    assert_eq!(collated, Ok(strings(&["apple", "eclair", "Éclair", "zebra"])));
+    assert!(std_string_sort_unicode(vec![Value::Array(Shared::new(vec![Value::Number(1.0)]))]).is_err());
+}
+
+#[test]
+fn test_compare_unicode() {
+    assert_eq!(std_string_compare_unicode(vec![string("Éclair"), string("zebra")]), Ok(Value::Number(-1.0)));
+    assert_eq!(std_string_compare_unicode(vec![string("b"), string("a")]), Ok(Value::Number(1.0)));
+    assert_eq!(std_string_compare_unicode(vec![string("same"), string("same")]), Ok(Value::Number(0.0)));
+}
+
+#[test]
+fn test_pow_int() {
+    let pow = |base: i64, exp: i64| std_math_pow_int(vec![Value::Integer(base), Value::Integer(exp)]);
+    assert_eq!(pow(2, 10), Ok(Value::Integer(1024)));
+    assert_eq!(pow(3, 0), Ok(Value::Integer(1)));
+    assert_eq!(pow(-2, 3), Ok(Value::Integer(-8)));
+    assert_eq!(pow(1, i64::MAX), Ok(Value::Integer(1)));
+    assert_eq!(pow(-1, i64::MAX), Ok(Value::Integer(-1)));
+    assert_eq!(pow(2, 64), Err("pow_int function overflowed".to_string()));
+    assert_eq!(pow(10, 1_000_000_000_000), Err("pow_int function overflowed".to_string()));
+    assert!(pow(2, -1).is_err());
+    assert!(std_math_pow_int(vec![Value::Number(2.0), Value::Integer(2)]).is_err());
+}
+
+#[test]
+fn test_display_writes_values_as_print_shows_them() {
+    assert_eq!(Value::Number(5.0).to_string(), "5");
+    assert_eq!(Value::Number(2.5).to_string(), "2.5");
+    assert_eq!(string("plain").to_string(), "plain");
+    assert_eq!(Value::Boolean(false).to_string(), "false");
+    assert_eq!(Value::Nun.to_string(), "nun");
+}
+
+#[test]
+fn test_array_get_and_len() {
+    let nested = Value::Array(Shared::new(vec![array(&[1.0]), array(&[2.0, 3.0])]));
+    assert_eq!(std_array_len(vec![nested.clone()]), Ok(Value::Number(2.0)));
+    assert_eq!(std_array_get(vec![nested.clone(), Value::Integer(1)]), Ok(array(&[2.0, 3.0])));
+    assert_eq!(std_array_get(vec![nested.clone(), Value::Number(2.0)]), Ok(Value::Nun));
+    assert!(std_array_get(vec![nested, Value::Number(-1.0)]).is_err());
+    assert!(std_array_len(vec![string("abc")]).is_err());
+
+    let pushed = array(&[]);
+    std_array_push(vec![pushed.clone(), array(&[1.0])]).unwrap();
+    assert_eq!(std_array_len(vec![pushed.clone()]), Ok(Value::Number(1.0)));
+    assert_eq!(std_array_get(vec![pushed, Value::Number(0.0)]), Ok(array(&[1.0])));
+}
+
+#[test]
+fn test_string_length_and_substring_count_characters() {
+    assert_eq!(std_string_length(vec![string("héllo")]), Ok(Value::Number(5.0)));
+    assert_eq!(std_string_length(vec![string("")]), Ok(Value::Number(0.0)));
+    assert_eq!(std_string_substring(vec![string("héllo"), Value::Number(1.0), Value::Number(3.0)]), Ok(string("éll")));
+    assert_eq!(std_string_substring(vec![string("héllo"), Value::Integer(3), Value::Integer(10)]), Ok(string("lo")));
+    assert_eq!(std_string_substring(vec![string("héllo"), Value::Number(9.0), Value::Number(1.0)]), Ok(string("")));
+    assert!(std_string_substring(vec![string("héllo"), Value::Number(-1.0), Value::Number(1.0)]).is_err());
+}
+
+#[test]
+fn test_string_split_concat_case_trim_and_contains() {
+    let parts = std_string_split(vec![string("a,b,,ç"), string(",")]).unwrap();
+    assert_eq!(parts.to_string(), "[\"a\", \"b\", \"\", \"ç\"]");
+    assert!(std_string_split(vec![string("abc"), string("")]).is_err());
+
+    assert_eq!(std_string_concat(vec![string("pe"), string("li"), string("n")]), Ok(string("pelin")));
+    assert_eq!(std_string_concat(vec![]), Ok(string("")));
+    assert!(std_string_concat(vec![string("a"), Value::Number(1.0)]).is_err());
+
+    assert_eq!(std_string_to_upper(vec![string("straße")]), Ok(string("STRASSE")));
+    assert_eq!(std_string_to_lower(vec![string("ÉCOLE")]), Ok(string("école")));
+    assert_eq!(std_string_trim(vec![string("\t hi \n")]), Ok(string("hi")));
+    assert_eq!(std_string_contains(vec![string("héllo"), string("él")]), Ok(Value::Boolean(true)));
+    assert_eq!(std_string_contains(vec![string("héllo"), string("x")]), Ok(Value::Boolean(false)));
+}
+
+#[test]
+fn test_math_pow_log_abs_and_rounding() {
+    assert_eq!(std_math_pow(vec![Value::Number(2.0), Value::Number(10.0)]), Ok(Value::Number(1024.0)));
+    assert_eq!(std_math_pow(vec![Value::Integer(2), Value::Number(0.5)]), Ok(Value::Number(2f64.sqrt())));
+    assert_eq!(std_math_floor(vec![Value::Number(3.7)]), Ok(Value::Number(3.0)));
+    assert_eq!(std_math_floor(vec![Value::Number(-3.2)]), Ok(Value::Number(-4.0)));
+    assert_eq!(std_math_ceil(vec![Value::Number(3.2)]), Ok(Value::Number(4.0)));
+    assert_eq!(std_math_round(vec![Value::Number(2.5)]), Ok(Value::Number(3.0)));
+    assert_eq!(std_math_round(vec![Value::Integer(7)]), Ok(Value::Integer(7)));
+    assert_eq!(std_math_abs(vec![Value::Number(-1.5)]), Ok(Value::Number(1.5)));
+    assert_eq!(std_math_abs(vec![Value::Integer(-3)]), Ok(Value::Integer(3)));
+    assert!(std_math_abs(vec![Value::Integer(i64::MIN)]).is_err());
+    assert_eq!(std_math_log(vec![Value::Number(std::f64::consts::E)]), Ok(Value::Number(1.0)));
+    assert_eq!(std_math_log(vec![Value::Number(0.0)]), Err("log function expects a positive number".to_string()));
+    assert_eq!(std_math_tan(vec![Value::Number(0.0)]), Ok(Value::Number(0.0)));
+    assert_eq!(std_math_pi(vec![]), Ok(Value::Number(std::f64::consts::PI)));
+    assert_eq!(std_math_e(vec![]), Ok(Value::Number(std::f64::consts::E)));
+}
+
+#[test]
+fn test_math_functions_validate_arguments() {
+    assert_eq!(std_math_floor(vec![string("3.7")]), Err("floor function expects a number argument".to_string()));
+    assert_eq!(std_math_floor(vec![]), Err("floor function expects 1 argument".to_string()));
+    assert_eq!(std_math_pow(vec![Value::Number(2.0)]), Err("pow function expects 2 arguments".to_string()));
+    assert_eq!(std_math_pi(vec![Value::Number(1.0)]), Err("pi function expects no arguments".to_string()));
+}
+
+#[test]
+fn test_num_mod_is_floored() {
+    assert_eq!(std_num_mod(vec![Value::Number(10.0), Value::Number(3.0)]), Ok(Value::Number(1.0)));
+    assert_eq!(std_num_mod(vec![Value::Number(-1.0), Value::Number(3.0)]), Ok(Value::Number(2.0)));
+    assert_eq!(std_num_mod(vec![Value::Number(1.0), Value::Number(-3.0)]), Ok(Value::Number(-2.0)));
+    assert_eq!(std_num_mod(vec![Value::Number(5.5), Value::Number(2.0)]), Ok(Value::Number(1.5)));
+    assert_eq!(std_num_mod(vec![Value::Integer(-1), Value::Integer(3)]), Ok(Value::Integer(2)));
+    assert_eq!(std_num_mod(vec![Value::Integer(-6), Value::Integer(3)]), Ok(Value::Integer(0)));
+    assert_eq!(std_num_mod(vec![Value::Integer(i64::MIN), Value::Integer(-1)]), Err("mod function overflowed".to_string()));
+}
+
+#[test]
+fn test_num_int_divide_truncates() {
+    assert_eq!(std_num_int_divide(vec![Value::Number(7.0), Value::Number(2.0)]), Ok(Value::Number(3.0)));
+    assert_eq!(std_num_int_divide(vec![Value::Number(-7.0), Value::Number(2.0)]), Ok(Value::Number(-3.0)));
+    assert_eq!(std_num_int_divide(vec![Value::Integer(-7), Value::Integer(2)]), Ok(Value::Integer(-3)));
+}
+
+#[test]
+fn test_num_floor_divide_rounds_down() {
+    assert_eq!(std_num_floor_divide(vec![Value::Number(7.0), Value::Number(2.0)]), Ok(Value::Number(3.0)));
+    assert_eq!(std_num_floor_divide(vec![Value::Number(-7.0), Value::Number(2.0)]), Ok(Value::Number(-4.0)));
+    assert_eq!(std_num_floor_divide(vec![Value::Integer(-7), Value::Integer(2)]), Ok(Value::Integer(-4)));
+    assert_eq!(std_num_floor_divide(vec![Value::Integer(7), Value::Integer(-2)]), Ok(Value::Integer(-4)));
+    assert_eq!(std_num_floor_divide(vec![Value::Integer(-8), Value::Integer(2)]), Ok(Value::Integer(-4)));
+    assert!(std_num_floor_divide(vec![Value::Integer(i64::MIN), Value::Integer(-1)]).is_err());
+}
+
+#[test]
+fn test_num_mod_and_int_divide_by_zero() {
+    for function in [std_num_mod, std_num_int_divide, std_num_floor_divide] {
+        assert_eq!(function(vec![Value::Number(1.0), Value::Number(0.0)]), Err("division by zero".to_string()));
+        assert_eq!(function(vec![Value::Integer(1), Value::Integer(0)]), Err("division by zero".to_string()));
+    }
+    assert_eq!(std_num_mod(vec![Value::Number(1.0)]), Err("mod function expects 2 arguments".to_string()));
+}
+
+#[test]
+fn test_read_line_strips_line_endings_until_end_of_input() {
+    let mut input = &b"first\r\nsecond\n\nlast"[..];
+    assert_eq!(read_line(&mut input), Ok(Value::String("first".to_string())));
+    assert_eq!(read_line(&mut input), Ok(Value::String("second".to_string())));
+    assert_eq!(read_line(&mut input), Ok(Value::String("".to_string())));
+    assert_eq!(read_line(&mut input), Ok(Value::String("last".to_string())));
+    assert_eq!(read_line(&mut input), Ok(Value::Nun));
+}
+
+#[test]
+fn test_read_all_returns_rest_of_input() {
+    let mut input = &b"one\ntwo\nthree\n"[..];
+    read_line(&mut input).unwrap();
+    assert_eq!(read_all(&mut input), Ok(Value::String("two\nthree\n".to_string())));
+    assert_eq!(read_all(&mut input), Ok(Value::Nun));
+    assert!(read_all(&mut &b"\xff"[..]).is_err());
+}
+
+#[test]
+fn test_compare_functions_return_booleans() {
+    let pair = |a: f64, b: f64| vec![Value::Number(a), Value::Number(b)];
+    assert_eq!(std_compare_eq(pair(3.0, 3.0)), Ok(Value::Boolean(true)));
+    assert_eq!(std_compare_neq(pair(3.0, 3.0)), Ok(Value::Boolean(false)));
+    assert_eq!(std_compare_eq(vec![Value::Integer(3), Value::Number(3.0)]), Ok(Value::Boolean(true)));
+    assert_eq!(std_compare_neq(vec![Value::Integer(3), Value::Number(3.0)]), Ok(Value::Boolean(false)));
+    assert_eq!(std_compare_lt(pair(2.0, 3.0)), Ok(Value::Boolean(true)));
+    assert_eq!(std_compare_gt(pair(2.0, 3.0)), Ok(Value::Boolean(false)));
+    assert_eq!(std_compare_lte(pair(3.0, 3.0)), Ok(Value::Boolean(true)));
+    assert_eq!(std_compare_gte(vec![Value::Integer(2), Value::Number(2.5)]), Ok(Value::Boolean(false)));
+    assert_eq!(std_compare_lte(vec![Value::Boolean(true), Value::Number(1.0)]), Err("lte function expects number arguments".to_string()));
+    assert_eq!(std_compare_gte(vec![Value::Number(1.0)]), Err("gte function expects 2 arguments".to_string()));
+}
+
+#[test]
+fn test_logic_functions() {
+    let and = std_logic_and(vec![Value::Boolean(true), Value::Boolean(false)]).unwrap();
+    assert_eq!(std_logic_not(vec![and]), Ok(Value::Boolean(true)));
+    assert_eq!(std_logic_or(vec![Value::Boolean(false), Value::Boolean(true)]), Ok(Value::Boolean(true)));
+    assert_eq!(std_logic_or(vec![Value::Boolean(false), Value::Number(1.0)]), Err("or function expects boolean arguments".to_string()));
+    assert_eq!(std_logic_not(vec![]), Err("not function expects 1 argument".to_string()));
+}
+
+#[test]
+fn test_json_round_trip() {
+    let parsed = std_json_parse(vec![string("{\"a\":[1,2]}")]).unwrap();
+    assert_eq!(std_json_stringify(vec![parsed]), Ok(string("{\"a\":[1,2]}")));
+
+    let text = "{\"b\":{\"c\":null,\"d\":true},\"n\":-1.5,\"s\":\"x\\\"y\"}";
+    let parsed = std_json_parse(vec![string(text)]).unwrap();
+    assert_eq!(std_json_stringify(vec![parsed]), Ok(string(text)));
+}
+
+#[test]
+fn test_json_parse_values() {
+    assert_eq!(std_json_parse(vec![string("[1, 2.5, \"a\", false, null]")]), Ok(Value::Array(Shared::new(vec![
+        Value::Number(1.0),
+        Value::Number(2.5),
+        string("a"),
+        Value::Boolean(false),
+        Value::Nun,
+    ]))));
+    let Ok(Value::Map(entries)) = std_json_parse(vec![string("{\"port\": 8080}")]) else {
+        panic!("expected a map");
+    };
+    assert_eq!(entries.borrow().get("port"), Some(&Value::Number(8080.0)));
+}
+
+#[test]
+fn test_json_errors() {
+    assert_eq!(std_json_parse(vec![string("{\"a\": }")]), Err("json_parse function got invalid JSON: expected value at line 1 column 7".to_string()));
+    assert_eq!(std_json_parse(vec![Value::Number(1.0)]), Err("json_parse function expects a string argument".to_string()));
+    assert_eq!(std_json_stringify(vec![Value::Number(f64::NAN)]), Err("json_stringify function cannot encode NaN".to_string()));
+    assert_eq!(std_json_stringify(vec![Value::Integer(3)]), Ok(string("3")));
+}
+
+#[test]
+fn test_env_set_then_get() {
+    assert_eq!(std_env_set(vec![string("PELIN_TEST_ENV_ROUND_TRIP"), string("feather")]), Ok(Value::Nun));
+    assert_eq!(std_env_get(vec![string("PELIN_TEST_ENV_ROUND_TRIP")]), Ok(string("feather")));
+}
+
+#[test]
+fn test_env_get_unset_is_nun() {
+    assert_eq!(std_env_get(vec![string("PELIN_TEST_ENV_DEFINITELY_UNSET")]), Ok(Value::Nun));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_env_get_non_utf8_value_is_an_error() {
+    use std::os::unix::ffi::OsStrExt;
+    std::env::set_var("PELIN_TEST_ENV_NOT_UTF8", std::ffi::OsStr::from_bytes(b"caf\xe9"));
+    assert_eq!(
+        std_env_get(vec![string("PELIN_TEST_ENV_NOT_UTF8")]),
+        Err("env_get function found a value for 'PELIN_TEST_ENV_NOT_UTF8' that is not valid UTF-8".to_string()),
+    );
+}
+
+#[test]
+fn test_env_set_invalid_name() {
+    assert!(std_env_set(vec![string("A=B"), string("x")]).is_err());
+    assert!(std_env_set(vec![string(""), string("x")]).is_err());
+    assert_eq!(std_env_set(vec![string("A"), Value::Number(1.0)]), Err("env_set function expects two string arguments".to_string()));
+}
+
+#[test]
+fn test_time_sleep_advances_monotonic_clock() {
+    let Ok(Value::Number(before)) = std_time_monotonic(vec![]) else { panic!("expected a number") };
+    assert_eq!(std_time_sleep(vec![Value::Number(10.0)]), Ok(Value::Nun));
+    let Ok(Value::Number(after)) = std_time_monotonic(vec![]) else { panic!("expected a number") };
+    let elapsed = after - before;
+    assert!((0.009..1.0).contains(&elapsed), "slept for {} seconds", elapsed);
+}
+
+#[test]
+fn test_time_now_and_sleep_arguments() {
+    let Ok(Value::Number(now)) = std_time_now(vec![]) else { panic!("expected a number") };
+    // Some time after 2020
+    assert!(now > 1.6e9, "{}", now);
+    assert_eq!(std_time_sleep(vec![Value::Integer(0)]), Ok(Value::Nun));
+    assert_eq!(std_time_sleep(vec![Value::Number(-1.0)]), Err("time_sleep function expects a non-negative number of milliseconds, got -1".to_string()));
+    assert_eq!(std_time_sleep(vec![Value::Number(1e30)]), Err("time_sleep function expects a non-negative number of milliseconds, got 1000000000000000000000000000000".to_string()));
+    assert!(std_time_sleep(vec![string("10")]).is_err());
+    assert_eq!(std_process_exit(vec![Value::Number(1.5)]), Err("exit function expects a whole number status code, got 1.5".to_string()));
+    assert!(std_time_now(vec![Value::Nun]).is_err());
+}
@@ -0,0 +1,84 @@
+use pelin::lexer::Lexer;
+use pelin::parser::Parser;
+use pelin::typecheck::check_program;
+
+fn check(input: &str) -> Result<(), Vec<String>> {
+    let tokens = Lexer::new(input).tokenize().unwrap();
+    let expressions = Parser::new(tokens).parse().unwrap();
+    check_program(&expressions)
+}
+
+#[test]
+fn test_typecheck_accepts_matching_literals() {
+    let input = r#"
+        fn num double(num x) { return RUST[std_func::mul](x, 2) }
+        fn str greet(str name, bool loud) { "hi" }
+        double(4)
+        greet("Ada", true)
+    "#;
+    assert_eq!(check(input), Ok(()));
+}
+
+#[test]
+fn test_typecheck_reports_literal_argument_mismatch() {
+    let input = r#"
+        fn num double(num x) { return RUST[std_func::mul](x, 2) }
+        double("four")
+    "#;
+    let errors = check(input).unwrap_err();
+    assert_eq!(errors, vec![
+        "In top-level expression 2: 'double' expects num for parameter 'x' but is passed a str literal".to_string()
+    ]);
+}
+
+#[test]
+fn test_typecheck_reports_literal_return_mismatch() {
+    let input = r#"fn num answer() { return "42" }"#;
+    let errors = check(input).unwrap_err();
+    assert_eq!(errors, vec!["In function 'answer': declared to return num but returns a str literal".to_string()]);
+}
+
+#[test]
+fn test_typecheck_skips_what_the_runtime_does_not_check() {
+    let input = r#"
+        fn int half() { 1.5 }
+        fn nun log(array items) { 5 }
+        log("not an array")
+    "#;
+    assert_eq!(check(input), Ok(()));
+}
+
+#[test]
+fn test_typecheck_reports_arity_and_collects_all_errors() {
+    let input = r#"
+        fn bool both(bool a, bool b) { a }
+        both(true)
+        both(1, true)
+    "#;
+    let errors = check(input).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("'both' expects 2 arguments but is called with 1"));
+    assert!(errors[1].contains("passed a num literal"));
+}
+
+#[test]
+fn test_typecheck_skips_unknown_types_and_shadowed_names() {
+    let input = r#"
+        fn num apply(any f, Point p) { f("anything") }
+        fn num f(num x) { x }
+        apply(nun, "not a point")
+    "#;
+    assert_eq!(check(input), Ok(()));
+}
+
+#[test]
+fn test_typecheck_checks_if_conditions_and_branches() {
+    let input = r#"
+        fn num double(num x) { return RUST[std_func::mul](x, 2) }
+        if ("yes") { double("four") } else { double(1) }
+    "#;
+    assert_eq!(check(input).unwrap_err(), vec![
+        "In top-level expression 2: 'if' condition is a str literal".to_string(),
+        "In top-level expression 2: 'double' expects num for parameter 'x' but is passed a str literal".to_string(),
+    ]);
+}